@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scrapr::scrap::{extract_html_infos, extract_webpage, HtmlPage, ScrapOptions};
+
+const URL: &str = "https://fuzz.scrapr.test/page.html";
+
+fuzz_target!(|data: &[u8]| {
+    let content = String::from_utf8_lossy(data).into_owned();
+    let _ = extract_html_infos(content.clone());
+    let _ = extract_webpage(URL, HtmlPage::ok(URL, content), &ScrapOptions::default());
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scrapr::cleanup::{clean_url, cleanup_tracking_params};
+use scrapr::{domains, homograph, relations};
+
+fuzz_target!(|url: &str| {
+    let _ = domains::domain_fields(url);
+    let _ = homograph::host_warnings(url);
+    let _ = relations::favicon_url(&[], url);
+    let _ = clean_url(url);
+    let cleaned = cleanup_tracking_params(url);
+    assert_eq!(cleanup_tracking_params(&cleaned), cleaned);
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scrapr::filter::Filter;
+use scrapr::pluck::PluckPath;
+use scrapr::scrap::select_text;
+use serde_json::{json, Value};
+
+// a selector or expression, then on the next lines the document it applies to
+fuzz_target!(|input: &str| {
+    let (selector, document) = input.split_once('\n').unwrap_or((input, ""));
+    let _ = select_text(document, selector);
+    let value: Value = serde_json::from_str(document).unwrap_or_else(|_| {
+        json!({"title": document, "status": 200, "http": {"version": "HTTP/1.1"}, "links": [document]})
+    });
+    if let Ok(path) = PluckPath::parse(selector) {
+        let _ = path.select(&value);
+    }
+    if let Ok(filter) = Filter::parse(selector) {
+        let _ = filter.matches(&value);
+    }
+});
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use reqwest::Method;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use url::Url;
+
+use crate::opengraph::OpenGraph;
+use crate::scrap::{extract_html_infos, is_json, retrieve_html_page, ScrapOptions, ScrapedWebpage};
+use crate::twitter::TwitterCard;
+use crate::{domains, homograph, readability, simhash, structured, tags};
+
+const ACCEPT_ACTIVITY: &str =
+    "application/activity+json, application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\"";
+
+/// Find the activitypub representation advertised by a fediverse html page
+/// (`<link rel="alternate" type="application/activity+json">`), absolute
+pub fn discover_object_url(content: &str, page_url: &str) -> Option<String> {
+    let document = Html::parse_document(content);
+    let selector = Selector::parse(r#"link[rel="alternate"][type="application/activity+json"]"#)
+        .expect("valid selector");
+    document
+        .select(&selector)
+        .find_map(|link| link.value().attr("href"))
+        .map(|href| {
+            Url::parse(page_url)
+                .and_then(|base| base.join(href))
+                .map_or(href.to_string(), String::from)
+        })
+}
+
+/// Fetch an activitypub object (Note, Article...) and map it to a webpage
+pub async fn grab_object(
+    object_url: &str,
+    page_url: &str,
+    options: &ScrapOptions,
+) -> Result<ScrapedWebpage> {
+    let object = retrieve_activity(object_url, options).await?;
+    let author = match object.get("attributedTo").and_then(first_id) {
+        Some(actor_url) => Some(
+            retrieve_actor_name(&actor_url, options)
+                .await
+                .unwrap_or(actor_url),
+        ),
+        None => None,
+    };
+    let content = object
+        .get("content")
+        .and_then(Value::as_str)
+        .map(|content| extract_html_infos(content.to_string()).map(|html| html.text_content))
+        .transpose()?;
+    let attachments = object
+        .get("attachment")
+        .map(|attachment| match attachment {
+            Value::Array(items) => items.iter().filter_map(first_id).collect(),
+            item => first_id(item).into_iter().collect(),
+        })
+        .unwrap_or_default();
+
+    let title = object
+        .get("name")
+        .and_then(Value::as_str)
+        .map(|name| name.to_string())
+        .or_else(|| author.as_ref().map(|author| format!("Post by {}", author)))
+        .unwrap_or("No title".to_string());
+    let url = object
+        .get("url")
+        .and_then(first_id)
+        .unwrap_or(page_url.to_string());
+
+    let (domain, registrable_domain) = domains::domain_fields(&url);
+    let warnings = homograph::host_warnings(&url);
+    let language = object
+        .get("contentMap")
+        .and_then(Value::as_object)
+        .and_then(|map| map.keys().next().cloned());
+    let readability = content
+        .as_deref()
+        .and_then(|content| readability::text_readability(content, language.as_deref()));
+    let simhash = content.as_deref().and_then(simhash::text_simhash);
+    Ok(ScrapedWebpage {
+        title,
+        url,
+        canonical_url: None,
+        final_url: page_url.to_string(),
+        redirects: vec![],
+        domain,
+        registrable_domain,
+        status: 200,
+        description: content,
+        language,
+        author,
+        published_at: object
+            .get("published")
+            .and_then(Value::as_str)
+            .and_then(structured::normalize_date),
+        tags: tags::hashtags(&object),
+        image: first_image(&object),
+        open_graph: OpenGraph::default(),
+        twitter: TwitterCard::default(),
+        attachments,
+        forms: vec![],
+        headers: BTreeMap::new(),
+        relations: vec![],
+        favicon: None,
+        favicon_file: None,
+        http: None,
+        // content warnings are not ratings
+        adult_content: false,
+        cacheable_until: None,
+        price: None,
+        currency: None,
+        job: None,
+        rating: None,
+        reviews: vec![],
+        podcast: None,
+        video: None,
+        readability,
+        simhash,
+        hero_image: None,
+        source: None,
+        warnings,
+        links: vec![],
+        html: String::new(),
+    })
+}
+
+/// GET of an object with the network, cookies and limits of the page options; the
+/// shared client is left out, it asks for html
+async fn retrieve_activity(url: &str, options: &ScrapOptions) -> Result<Value> {
+    let options = ScrapOptions {
+        accept: ACCEPT_ACTIVITY.to_string(),
+        method: Method::GET,
+        body: None,
+        content_type: None,
+        client: None,
+        ..options.clone()
+    };
+    let page = retrieve_html_page(url, &options).await?;
+    if !is_json(page.content_type.as_deref()) {
+        return Err(anyhow!("Not an activitypub object: {}", url));
+    }
+    Ok(serde_json::from_str(&page.content)?)
+}
+
+async fn retrieve_actor_name(actor_url: &str, options: &ScrapOptions) -> Result<String> {
+    let actor = retrieve_activity(actor_url, options).await?;
+    let name = actor
+        .get("name")
+        .and_then(Value::as_str)
+        .filter(|name| !name.is_empty())
+        .or_else(|| actor.get("preferredUsername").and_then(Value::as_str))
+        .ok_or(anyhow!("Actor without name: {}", actor_url))?;
+    Ok(name.to_string())
+}
+
+/// Url of the first image attached to a post
+fn first_image(object: &Value) -> Option<String> {
+    let attachments = match object.get("attachment")? {
+        Value::Array(items) => items.iter().collect(),
+        item => vec![item],
+    };
+    attachments
+        .into_iter()
+        .find(|attachment| {
+            attachment
+                .get("mediaType")
+                .and_then(Value::as_str)
+                .is_some_and(|media_type| media_type.starts_with("image/"))
+        })
+        .and_then(first_id)
+}
+
+/// Activitystreams values can be a plain url, a link/object with `href`/`url`/`id`, or a list of them
+fn first_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.to_string()),
+        Value::Array(items) => items.iter().find_map(first_id),
+        Value::Object(object) => ["href", "url", "id"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(first_id)),
+        _ => None,
+    }
+}
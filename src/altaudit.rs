@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use scraper::{Html, Selector};
+use serde::Serialize;
+use url::Url;
+
+use crate::format::{format_json, Format, Shape};
+
+/// Alt texts which do not describe anything
+const PLACEHOLDER_ALTS: [&str; 9] = [
+    "image",
+    "img",
+    "photo",
+    "picture",
+    "placeholder",
+    "untitled",
+    "alt",
+    "graphic",
+    "thumbnail",
+];
+const IMAGE_EXTENSIONS: [&str; 6] = [".jpg", ".jpeg", ".png", ".gif", ".webp", ".svg"];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AltStatus {
+    Ok,
+    Missing,
+    /// `alt=""`, right for decorative images only
+    Empty,
+    /// A file name or a generic word like `image`
+    Placeholder,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageAlt {
+    pub src: String,
+    pub alt: Option<String>,
+    pub status: AltStatus,
+}
+
+/// Alt texts of the images of a page, with percentages of each status
+#[derive(Debug, Serialize)]
+pub struct AltAudit {
+    pub images: usize,
+    pub ok_percent: f64,
+    pub missing_percent: f64,
+    pub empty_percent: f64,
+    pub placeholder_percent: f64,
+    pub details: Vec<ImageAlt>,
+}
+
+pub fn audit_alt_texts(content: &str, page_url: &str) -> AltAudit {
+    let document = Html::parse_document(content);
+    let selector = Selector::parse("img").expect("valid selector");
+    let base = Url::parse(page_url).ok();
+    let details: Vec<ImageAlt> = document
+        .select(&selector)
+        .map(|image| {
+            let src = image.value().attr("src").unwrap_or_default();
+            let src = base
+                .as_ref()
+                .and_then(|base| base.join(src).ok())
+                .map_or(src.to_string(), String::from);
+            let alt = image.value().attr("alt").map(|alt| alt.trim().to_string());
+            ImageAlt {
+                status: alt_status(alt.as_deref()),
+                src,
+                alt,
+            }
+        })
+        .collect();
+    let percent = |status| {
+        let count = details
+            .iter()
+            .filter(|image| image.status == status)
+            .count();
+        match details.len() {
+            0 => 0.0,
+            total => (count as f64 * 1000.0 / total as f64).round() / 10.0,
+        }
+    };
+    AltAudit {
+        images: details.len(),
+        ok_percent: percent(AltStatus::Ok),
+        missing_percent: percent(AltStatus::Missing),
+        empty_percent: percent(AltStatus::Empty),
+        placeholder_percent: percent(AltStatus::Placeholder),
+        details,
+    }
+}
+
+fn alt_status(alt: Option<&str>) -> AltStatus {
+    let Some(alt) = alt else {
+        return AltStatus::Missing;
+    };
+    let lowercase = alt.to_lowercase();
+    if alt.is_empty() {
+        AltStatus::Empty
+    } else if PLACEHOLDER_ALTS.contains(&lowercase.as_str())
+        || IMAGE_EXTENSIONS
+            .iter()
+            .any(|extension| lowercase.ends_with(extension))
+    {
+        AltStatus::Placeholder
+    } else {
+        AltStatus::Ok
+    }
+}
+
+pub fn format_alt_audit(audit: &AltAudit, format: Format, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(audit, shape)?)?,
+        _ => {
+            writeln!(output, "| image | alt | status |")?;
+            writeln!(output, "| --- | --- | --- |")?;
+            for image in &audit.details {
+                writeln!(
+                    output,
+                    "| {} | {} | {} |",
+                    image.src,
+                    image.alt.as_deref().unwrap_or_default().replace('|', "\\|"),
+                    serde_json::to_value(image.status)?
+                        .as_str()
+                        .unwrap_or_default()
+                )?;
+            }
+            writeln!(output)?;
+            writeln!(
+                output,
+                "{} images: {}% ok, {}% missing, {}% empty, {}% placeholder",
+                audit.images,
+                audit.ok_percent,
+                audit.missing_percent,
+                audit.empty_percent,
+                audit.placeholder_percent
+            )?;
+        }
+    }
+    Ok(output)
+}
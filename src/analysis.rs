@@ -0,0 +1,39 @@
+use anyhow::Result;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+use crate::format::{Format, Shape};
+use crate::scrap::ScrapedWebpage;
+use crate::{duplicates, keywords};
+
+/// A report over the pages of a run, instead of a record per page
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Analysis {
+    /// Most frequent terms and bigrams of the main content, with their count per page
+    Keywords,
+    /// Clusters of near-duplicate pages by simhash, with their similarity
+    Duplicates,
+}
+
+/// The `analysis` report of pages, keeping the `top` keywords or clustering duplicates
+/// from `min_similarity`
+pub fn report(
+    analysis: Analysis,
+    pages: &[ScrapedWebpage],
+    top: usize,
+    min_similarity: f64,
+    format: Format,
+    shape: &Shape,
+) -> Result<String> {
+    match analysis {
+        Analysis::Keywords => {
+            keywords::format_keywords(&keywords::analyze_keywords(pages, top), format, shape)
+        }
+        Analysis::Duplicates => duplicates::format_duplicates(
+            &duplicates::find_duplicates(pages, min_similarity),
+            format,
+            shape,
+        ),
+    }
+}
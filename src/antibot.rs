@@ -0,0 +1,82 @@
+use std::fmt;
+
+use reqwest::header::{HeaderMap, SERVER};
+use serde::Serialize;
+
+/// Anti-bot interstitial served instead of the page, as `blocked_by`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Challenge {
+    CloudflareChallenge,
+    AkamaiBotManager,
+    PerimeterxCaptcha,
+    DatadomeCaptcha,
+}
+
+impl Challenge {
+    /// What may get past it
+    pub fn guidance(self) -> &'static str {
+        match self {
+            Challenge::CloudflareChallenge => "retry later at a slower rate, try --impersonate chrome, or send the cf_clearance cookie of a browser session through a profile",
+            Challenge::AkamaiBotManager => "try --impersonate chrome, or send the _abck cookie of a browser session through a profile",
+            Challenge::PerimeterxCaptcha => "solve the captcha in a browser and send its _px cookies through a profile",
+            Challenge::DatadomeCaptcha => "solve the captcha in a browser and send its datadome cookie through a profile",
+        }
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Challenge::CloudflareChallenge => "cloudflare_challenge",
+            Challenge::AkamaiBotManager => "akamai_bot_manager",
+            Challenge::PerimeterxCaptcha => "perimeterx_captcha",
+            Challenge::DatadomeCaptcha => "datadome_captcha",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Challenge page of a known anti-bot vendor, from its headers and markup
+pub fn detect_challenge(status: u16, headers: &HeaderMap, content: &str) -> Option<Challenge> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_lowercase)
+    };
+    let servers: Vec<String> = headers
+        .get_all(SERVER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(str::to_lowercase)
+        .collect();
+    let server = |name: &str| servers.iter().any(|server| server.starts_with(name));
+    let contains = |markers: &[&str]| markers.iter().any(|marker| content.contains(marker));
+    let is_denied = matches!(status, 403 | 429 | 503);
+    if header("cf-mitigated").as_deref() == Some("challenge")
+        || (server("cloudflare")
+            && is_denied
+            && contains(&[
+                "<title>Just a moment...</title>",
+                "challenge-platform",
+                "cf-browser-verification",
+                "Attention Required! | Cloudflare",
+            ]))
+    {
+        return Some(Challenge::CloudflareChallenge);
+    }
+    if (header("x-datadome").is_some() && is_denied) || contains(&["captcha-delivery.com"]) {
+        return Some(Challenge::DatadomeCaptcha);
+    }
+    if contains(&["_pxCaptcha", "px-captcha", "window._pxAppId"]) && is_denied {
+        return Some(Challenge::PerimeterxCaptcha);
+    }
+    if server("akamaighost")
+        && is_denied
+        && contains(&["Access Denied", "Reference&#32;&#35;", "bm-verify"])
+    {
+        return Some(Challenge::AkamaiBotManager);
+    }
+    None
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+
+use crate::markdown::{element_to_markdown, SKIPPED};
+use crate::scrap::ScrapedWebpage;
+
+/// Class and id words of the elements holding the content
+const POSITIVE: [&str; 10] = [
+    "article", "blog", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+
+/// Class and id words of boilerplate: comments, sidebars, share buttons, ads
+const NEGATIVE: [&str; 20] = [
+    "ad",
+    "banner",
+    "comment",
+    "cookie",
+    "footer",
+    "header",
+    "masthead",
+    "menu",
+    "meta",
+    "nav",
+    "newsletter",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "subscribe",
+    "widget",
+];
+
+/// Containers dropped from the content when made of links or boilerplate
+const CONTAINERS: &str = "div, section, ul, ol, table, p";
+
+/// The readable content of a page, its boilerplate left out
+#[derive(Debug, Serialize)]
+pub struct Article {
+    pub url: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub published_at: Option<String>,
+    pub excerpt: Option<String>,
+    pub words: usize,
+    pub markdown: String,
+}
+
+/// The element whose paragraphs score best, in markdown without its link lists and
+/// boilerplate; none without a paragraph of text
+pub fn extract_article(page: &ScrapedWebpage) -> Option<Article> {
+    let document = Html::parse_document(&page.html);
+    let content = best_candidate(&document)?;
+    let containers = Selector::parse(CONTAINERS).expect("valid selector");
+    let dropped: Vec<_> = content
+        .select(&containers)
+        .filter(|element| is_boilerplate(*element))
+        .map(|element| element.id())
+        .collect();
+    // drop from a copy, the ids of a tree being kept by its clone
+    let mut cleaned = document.clone();
+    for id in dropped {
+        if let Some(mut node) = cleaned.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    let content = cleaned.tree.get(content.id()).and_then(ElementRef::wrap)?;
+    let markdown = element_to_markdown(content, &page.final_url);
+    Some(Article {
+        url: page.url.clone(),
+        title: page.title.clone(),
+        author: page.author.clone(),
+        published_at: page.published_at.clone(),
+        excerpt: page.description.clone(),
+        words: markdown.split_whitespace().count(),
+        markdown,
+    })
+}
+
+/// Parent of the paragraphs with the most text and commas, their grandparent getting half,
+/// weighted by class and id, and by the share of text outside links
+fn best_candidate(document: &Html) -> Option<ElementRef<'_>> {
+    let paragraphs = Selector::parse("p, pre, td").expect("valid selector");
+    let mut scores = HashMap::new();
+    for paragraph in document.select(&paragraphs) {
+        if in_boilerplate(paragraph) {
+            continue;
+        }
+        let text: String = paragraph.text().collect();
+        let length = text.trim().chars().count();
+        if length < 25 {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f64 + (length as f64 / 100.0).min(3.0);
+        let mut ancestors = paragraph.ancestors().filter_map(ElementRef::wrap);
+        if let Some(parent) = ancestors.next() {
+            *scores.entry(parent.id()).or_insert(0.0) += score;
+        }
+        if let Some(grandparent) = ancestors.next() {
+            *scores.entry(grandparent.id()).or_insert(0.0) += score / 2.0;
+        }
+    }
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = document.tree.get(id).and_then(ElementRef::wrap)?;
+            let score = (score + class_weight(element)) * (1.0 - link_density(element));
+            Some((element, score))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| element)
+}
+
+/// Whether an element is in chrome, a control or an element with a boilerplate class
+fn in_boilerplate(element: ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| SKIPPED.contains(&ancestor.value().name()) || class_weight(ancestor) < 0.0)
+}
+
+/// A container of links, or with a boilerplate class, and without longer text
+fn is_boilerplate(element: ElementRef) -> bool {
+    let text: String = element.text().collect();
+    let long = text.trim().chars().count() >= 500;
+    class_weight(element) < 0.0 && !long || link_density(element) > 0.5
+}
+
+/// 25 for a content class or id, -25 for a boilerplate one; none for `<html>` and `<body>`,
+/// whose classes describe the whole page
+fn class_weight(element: ElementRef) -> f64 {
+    if matches!(element.value().name(), "html" | "body") {
+        return 0.0;
+    }
+    let names = [element.value().attr("class"), element.value().id()];
+    let words: Vec<String> = names
+        .into_iter()
+        .flatten()
+        .flat_map(|name| name.split(|c: char| !c.is_ascii_alphanumeric()))
+        .map(|word| word.to_ascii_lowercase())
+        .map(|word| word.strip_suffix('s').map(str::to_string).unwrap_or(word))
+        .collect();
+    let has = |list: &[&str]| words.iter().any(|word| list.contains(&word.as_str()));
+    match (has(&NEGATIVE), has(&POSITIVE)) {
+        (true, _) => -25.0,
+        (false, true) => 25.0,
+        (false, false) => 0.0,
+    }
+}
+
+/// Share of the text of an element inside links
+fn link_density(element: ElementRef) -> f64 {
+    let length = |element: ElementRef| -> usize {
+        element
+            .text()
+            .map(|text| text.split_whitespace().map(str::len).sum::<usize>())
+            .sum()
+    };
+    let total = length(element);
+    if total == 0 {
+        return 0.0;
+    }
+    let links = Selector::parse("a").expect("valid selector");
+    let linked: usize = element.select(&links).map(length).sum();
+    linked as f64 / total as f64
+}
+
+/// The title, the byline and the content in markdown
+pub fn format_article(article: &Article) -> Result<String> {
+    let mut output = String::new();
+    // articles usually start with their own title
+    let (title, content) = match article.markdown.strip_prefix("# ") {
+        Some(markdown) => markdown.split_once('\n').unwrap_or((markdown, "")),
+        None => (article.title.as_str(), article.markdown.as_str()),
+    };
+    writeln!(output, "# {}", title)?;
+    writeln!(output)?;
+    let byline: Vec<&str> = [&article.author, &article.published_at]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    if !byline.is_empty() {
+        writeln!(output, "_{}_", byline.join(", "))?;
+        writeln!(output)?;
+    }
+    writeln!(output, "<{}>", article.url)?;
+    let content = content.trim_start();
+    if !content.is_empty() {
+        writeln!(output)?;
+        writeln!(output, "{}", content)?;
+    }
+    Ok(output)
+}
@@ -0,0 +1,48 @@
+use scraper::{Html, Selector};
+use serde_json::Value;
+use url::Url;
+
+use crate::structured::{self, has_type};
+
+/// Author names of a page, comma separated: `<meta name="author">`, the JSON-LD author
+/// of the article, `article:author`, then `rel=author` links; profile urls come last
+pub fn extract_author(document: &Html, entities: &[Value], page_url: &str) -> Option<String> {
+    let meta = |key| structured::meta_content(document, key);
+    let candidates = [
+        meta("author"),
+        json_ld_author(entities),
+        meta("article:author"),
+        rel_author(document, page_url),
+    ];
+    let mut candidates: Vec<String> = candidates.into_iter().flatten().collect();
+    // `article:author` and `rel=author` are often profile urls
+    candidates.sort_by_key(|candidate| candidate.starts_with("http"));
+    candidates.into_iter().next()
+}
+
+/// Authors of the first entity with one, reviews aside
+fn json_ld_author(entities: &[Value]) -> Option<String> {
+    let author = entities
+        .iter()
+        .filter(|entity| !has_type(entity, "Review"))
+        .find_map(|entity| entity.get("author"))?;
+    let names: Vec<String> = match author {
+        Value::Array(authors) => authors.iter().filter_map(structured::value_text).collect(),
+        author => structured::value_text(author).into_iter().collect(),
+    };
+    Some(names.join(", ")).filter(|names| !names.is_empty())
+}
+
+/// Text of the first `rel=author` link, else its absolute target
+fn rel_author(document: &Html, page_url: &str) -> Option<String> {
+    let selector = Selector::parse("a[rel~=author], link[rel~=author]").expect("valid selector");
+    let link = document.select(&selector).next()?;
+    let text = link.text().collect::<String>().trim().to_string();
+    Some(text).filter(|text| !text.is_empty()).or_else(|| {
+        let href = link.value().attr("href")?;
+        Url::parse(page_url)
+            .and_then(|base| base.join(href))
+            .map(String::from)
+            .ok()
+    })
+}
@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use tokio::sync::Mutex;
+
+use crate::units::parse_bytes;
+
+/// Token bucket shared by all body downloads of a run
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: bytes_per_sec as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until `bytes` may be consumed, bursts limited to one second of bandwidth
+    pub async fn acquire(&self, bytes: usize) {
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+        bucket.refilled_at = now;
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens < 0.0 {
+            // other downloads wait behind the lock meanwhile
+            let wait = Duration::from_secs_f64(-bucket.tokens / self.bytes_per_sec);
+            tokio::time::sleep(wait).await;
+            bucket.tokens = 0.0;
+            bucket.refilled_at = Instant::now();
+        }
+    }
+}
+
+/// Parse a rate like `2MBps`, `500KB/s`, `1MiBps` or `4096` (bytes per second)
+pub fn parse_bandwidth(rate: &str) -> Result<u64> {
+    let size = rate.trim().trim_end_matches("ps").trim_end_matches("/s");
+    let bytes_per_sec = parse_bytes(size)?;
+    if bytes_per_sec == 0 {
+        return Err(anyhow!("Invalid bandwidth {}: expected e.g. 2MBps", rate));
+    }
+    Ok(bytes_per_sec)
+}
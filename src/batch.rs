@@ -0,0 +1,200 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use thiserror::Error;
+use tokio::task::JoinSet;
+
+use crate::domains;
+use crate::scrap::{grab_url, ScrapOptions, Scraped};
+#[cfg(feature = "store")]
+use crate::store::Store;
+
+/// Result of one url of a batch
+pub struct Outcome {
+    pub url: String,
+    pub latency: Duration,
+    pub result: Result<Scraped>,
+}
+
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("skipped_circuit_open: {0} failed {1} times in a row")]
+    CircuitOpen(String, usize),
+}
+
+/// Stop requesting a host after `threshold` failures in a row, for `cooldown`;
+/// the next failure after the cooldown opens it again
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    pub threshold: usize,
+    pub cooldown: Duration,
+}
+
+#[derive(Default)]
+struct HostCircuit {
+    failures: usize,
+    opened_at: Option<Instant>,
+}
+
+/// Urls of a host waiting for a fetch, and its running fetches
+#[derive(Default)]
+struct HostQueue {
+    pending: VecDeque<usize>,
+    running: usize,
+}
+
+/// Newline separated urls, blank lines and `#` comments skipped
+pub fn read_urls(reader: impl BufRead) -> Result<Vec<String>> {
+    Ok(read_numbered_urls(reader)?
+        .into_iter()
+        .map(|(_, url)| url)
+        .collect())
+}
+
+/// Urls with their line numbers, from 1
+pub fn read_numbered_urls(reader: impl BufRead) -> Result<Vec<(usize, String)>> {
+    let mut urls = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            urls.push((index + 1, line.to_string()));
+        }
+    }
+    Ok(urls)
+}
+
+/// Scrape urls with up to `concurrency` fetches at once on one client, and up to
+/// `per_host_concurrency` of the options on a host, a failing url does not stop the
+/// batch; outcomes are in the order of the urls.
+///
+/// Fetches already running when a circuit opens still complete.
+pub async fn grab_all(
+    urls: &[String],
+    options: &ScrapOptions,
+    breaker: Option<&CircuitBreaker>,
+    concurrency: usize,
+) -> Vec<Outcome> {
+    let mut outcomes: Vec<Option<Outcome>> = urls.iter().map(|_| None).collect();
+    grab_each(urls, options, breaker, concurrency, |index, outcome| {
+        outcomes[index] = Some(outcome)
+    })
+    .await;
+    outcomes.into_iter().flatten().collect()
+}
+
+/// Scrape urls like [`grab_all`], handing each outcome and the index of its url to `each`
+/// as soon as it completes
+pub async fn grab_each(
+    urls: &[String],
+    options: &ScrapOptions,
+    breaker: Option<&CircuitBreaker>,
+    concurrency: usize,
+    mut each: impl FnMut(usize, Outcome),
+) {
+    let per_host = options.per_host_concurrency.max(1);
+    let options = options.with_shared_client();
+    let mut circuits: HashMap<String, HostCircuit> = HashMap::new();
+    let mut hosts: HashMap<String, HostQueue> = HashMap::new();
+    for (index, url) in urls.iter().enumerate() {
+        let host = domains::domain_fields(url).0.unwrap_or(url.clone());
+        hosts.entry(host).or_default().pending.push_back(index);
+    }
+    // the next url of each host below its limit, the earliest first
+    let mut ready: BTreeSet<(usize, String)> = hosts
+        .iter()
+        .filter_map(|(host, queue)| Some((*queue.pending.front()?, host.clone())))
+        .collect();
+    let mut fetches = JoinSet::new();
+    loop {
+        while fetches.len() < concurrency.max(1) {
+            let Some((index, host)) = ready.pop_first() else {
+                break;
+            };
+            let queue = hosts.get_mut(&host).expect("queued host");
+            queue.pending.pop_front();
+            let url = &urls[index];
+            let circuit = circuits.entry(host.clone()).or_default();
+            let open = match (breaker, circuit.opened_at) {
+                (Some(breaker), Some(opened_at)) => opened_at.elapsed() < breaker.cooldown,
+                _ => false,
+            };
+            if open {
+                let outcome = Outcome {
+                    url: url.clone(),
+                    latency: Duration::ZERO,
+                    result: Err(anyhow!(BatchError::CircuitOpen(
+                        host.clone(),
+                        circuit.failures
+                    ))),
+                };
+                each(index, outcome);
+            } else {
+                queue.running += 1;
+                let (url, options, host) = (url.clone(), options.clone(), host.clone());
+                fetches.spawn(async move {
+                    let start = Instant::now();
+                    let result = grab_url(&url, &options).await;
+                    let outcome = Outcome {
+                        url,
+                        latency: start.elapsed(),
+                        result,
+                    };
+                    (index, host, outcome)
+                });
+            }
+            if let (true, Some(next)) = (queue.running < per_host, queue.pending.front()) {
+                ready.insert((*next, host));
+            }
+        }
+        let Some(joined) = fetches.join_next().await else {
+            break;
+        };
+        let (index, host, outcome) = match joined {
+            Ok(joined) => joined,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+        let queue = hosts.get_mut(&host).expect("queued host");
+        queue.running -= 1;
+        if let Some(next) = queue.pending.front() {
+            ready.insert((*next, host.clone()));
+        }
+        let circuit = circuits.entry(host).or_default();
+        match (&outcome.result, breaker) {
+            (Ok(_), _) => *circuit = HostCircuit::default(),
+            (Err(_), Some(breaker)) => {
+                circuit.failures += 1;
+                if circuit.failures >= breaker.threshold {
+                    circuit.opened_at = Some(Instant::now());
+                }
+            }
+            (Err(_), None) => {}
+        }
+        each(index, outcome);
+    }
+}
+
+/// Keep the latency of successful fetches per host, for `--timeout auto`
+#[cfg(feature = "store")]
+pub fn record_latencies(store: &Store, outcomes: &[Outcome]) -> Result<()> {
+    for outcome in outcomes.iter().filter(|outcome| outcome.result.is_ok()) {
+        if let (Some(host), _) = domains::domain_fields(&outcome.url) {
+            store.record_latency(&host, outcome.latency)?;
+        }
+    }
+    Ok(())
+}
+
+/// Queue the failed urls for `scrapr retry-failed`, and drop the ones which succeeded
+#[cfg(feature = "store")]
+pub fn record_failures(store: &Store, outcomes: &[Outcome], source: &str) -> Result<()> {
+    for outcome in outcomes {
+        match &outcome.result {
+            Ok(_) => store.remove_failure(&outcome.url)?,
+            Err(e) => store.record_failure(&outcome.url, source, &e.to_string())?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,250 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AGE, CACHE_CONTROL, DATE, EXPIRES};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::transfer::HttpInfo;
+
+/// On-disk cache of GET responses, evicting least recently used entries above `max_size`
+///
+/// Responses are fresh until their `cacheable_until` when `honor_headers` is set and they
+/// have caching headers, for `ttl` otherwise.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_size: u64,
+    honor_headers: bool,
+}
+
+/// A cached response, enough to rebuild the page without the network
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub status: u16,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub http: HttpInfo,
+    /// Urls redirected from before `url`
+    #[serde(default)]
+    pub redirects: Vec<String>,
+}
+
+impl CachedResponse {
+    pub fn header_map(&self) -> HeaderMap {
+        self.headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Until when a response may be reused, counted from its `Date`: `max-age` of
+/// `Cache-Control` minus `Age`, else `Expires`; `no-store` and `no-cache` make it stale
+/// at once. `None` without caching headers. `fetched` stands for a missing `Date`
+pub fn cacheable_until(headers: &HeaderMap, fetched: SystemTime) -> Option<SystemTime> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    let date = header(DATE)
+        .and_then(|date| httpdate::parse_http_date(date).ok())
+        .unwrap_or(fetched);
+    let directives: Vec<String> = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_lowercase())
+        .collect();
+    if directives
+        .iter()
+        .any(|directive| directive == "no-store" || directive == "no-cache")
+    {
+        return Some(date);
+    }
+    let max_age = directives.iter().find_map(|directive| {
+        let seconds = directive.strip_prefix("max-age=")?;
+        seconds.trim_matches('"').parse::<u64>().ok()
+    });
+    if let Some(max_age) = max_age {
+        let age = header(AGE)
+            .and_then(|age| age.parse::<u64>().ok())
+            .unwrap_or(0);
+        // a huge max-age lasts forever rather than overflowing
+        return Some(
+            date.checked_add(Duration::from_secs(max_age.saturating_sub(age)))
+                .map_or_else(far_future, |until| until.min(far_future())),
+        );
+    }
+    // invalid dates like `0` mean already expired
+    header(EXPIRES).map(|expires| httpdate::parse_http_date(expires).unwrap_or(date))
+}
+
+/// Last second of year 9999, the latest time an RFC 3339 date can hold
+fn far_future() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(253_402_300_799)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub size: u64,
+    pub max_size: u64,
+    pub oldest: Option<String>,
+}
+
+impl HttpCache {
+    pub fn new(dir: PathBuf, ttl: Duration, max_size: u64, honor_headers: bool) -> Self {
+        Self {
+            dir,
+            ttl,
+            max_size,
+            honor_headers,
+        }
+    }
+
+    fn entry_path(&self, url: &str, accept: &str) -> PathBuf {
+        let key = Sha256::digest(format!("{}\n{}", accept, url).as_bytes());
+        let key: String = key.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Fresh entry for the request, marked as recently used
+    pub fn get(&self, url: &str, accept: &str) -> Option<CachedResponse> {
+        let path = self.entry_path(url, accept);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        let entry: CachedResponse = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+        let cacheable_until =
+            cacheable_until(&entry.header_map(), modified).filter(|_| self.honor_headers);
+        let is_fresh = match cacheable_until {
+            Some(cacheable_until) => SystemTime::now() < cacheable_until,
+            None => age <= self.ttl,
+        };
+        if !is_fresh {
+            return None;
+        }
+        // reading does not update mtime on every filesystem, the lru order relies on accessed
+        let _ = File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_times(fs::FileTimes::new().set_accessed(SystemTime::now())));
+        Some(entry)
+    }
+
+    /// Store an entry, unless its caching headers make it stale already (`no-store`,
+    /// `no-cache`, `max-age=0`...)
+    pub fn put(&self, url: &str, accept: &str, entry: &CachedResponse) -> Result<()> {
+        let cacheable_until =
+            cacheable_until(&entry.header_map(), SystemTime::now()).filter(|_| self.honor_headers);
+        if cacheable_until.is_some_and(|cacheable_until| cacheable_until <= SystemTime::now()) {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.entry_path(url, accept), serde_json::to_string(entry)?)?;
+        self.evict()?;
+        Ok(())
+    }
+
+    /// Remove least recently used entries until the cache fits in `max_size`
+    pub fn evict(&self) -> Result<usize> {
+        let mut entries = self.entries()?;
+        let mut size: u64 = entries.iter().map(|entry| entry.size).sum();
+        entries.sort_by_key(|entry| entry.accessed);
+        let mut removed = 0;
+        for entry in entries {
+            if size <= self.max_size {
+                break;
+            }
+            fs::remove_file(&entry.path)?;
+            size -= entry.size;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Remove entries stored more than `age` ago
+    pub fn prune(&self, age: Duration) -> Result<usize> {
+        let limit = SystemTime::now() - age;
+        let mut removed = 0;
+        for entry in self.entries()? {
+            if entry.modified < limit {
+                fs::remove_file(&entry.path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn clear(&self) -> Result<usize> {
+        let entries = self.entries()?;
+        for entry in &entries {
+            fs::remove_file(&entry.path)?;
+        }
+        Ok(entries.len())
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        let entries = self.entries()?;
+        let oldest = entries
+            .iter()
+            .map(|entry| entry.modified)
+            .min()
+            .map(|modified| humantime::format_rfc3339_seconds(modified).to_string());
+        Ok(CacheStats {
+            entries: entries.len(),
+            size: entries.iter().map(|entry| entry.size).sum(),
+            max_size: self.max_size,
+            oldest,
+        })
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                entries.push(CacheEntry::read(&path)?);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    accessed: SystemTime,
+}
+
+impl CacheEntry {
+    fn read(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified,
+            accessed: metadata.accessed().unwrap_or(modified).max(modified),
+        })
+    }
+}
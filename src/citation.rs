@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+use crate::domains;
+use crate::scrap::ScrapedWebpage;
+use crate::stamp::now;
+
+/// biblatex `@online` entries of pages, accessed today
+pub fn format_bibtex(pages: &[ScrapedWebpage]) -> Result<String> {
+    let accessed = today();
+    let mut keys = HashSet::new();
+    let mut entries = vec![];
+    for page in pages {
+        let mut entry = String::new();
+        writeln!(
+            entry,
+            "@online{{{},",
+            unique_key(&cite_key(page), &mut keys)
+        )?;
+        writeln!(entry, "  title = {{{}}},", escape_latex(&page.title))?;
+        if let Some(author) = &page.author {
+            let authors: Vec<String> = authors(author).map(escape_latex).collect();
+            writeln!(entry, "  author = {{{}}},", authors.join(" and "))?;
+        }
+        if let Some(site_name) = &page.open_graph.site_name {
+            writeln!(entry, "  organization = {{{}}},", escape_latex(site_name))?;
+        }
+        if let Some(date) = page.published_at.as_deref().and_then(iso_date) {
+            writeln!(entry, "  date = {{{}}},", date)?;
+        }
+        // biblatex reads urls verbatim
+        writeln!(entry, "  url = {{{}}},", page.url)?;
+        writeln!(entry, "  urldate = {{{}}},", accessed)?;
+        writeln!(entry, "}}")?;
+        entries.push(entry);
+    }
+    Ok(entries.join("\n"))
+}
+
+/// CSL-JSON `webpage` items of pages, for Zotero and pandoc
+pub fn format_csl_json(pages: &[ScrapedWebpage]) -> Result<String> {
+    let accessed = today();
+    let mut keys = HashSet::new();
+    let mut items = vec![];
+    for page in pages {
+        let mut item = Map::new();
+        item.insert(
+            "id".to_string(),
+            json!(unique_key(&cite_key(page), &mut keys)),
+        );
+        item.insert("type".to_string(), json!("webpage"));
+        item.insert("title".to_string(), json!(page.title));
+        if let Some(author) = &page.author {
+            let authors: Vec<Value> = authors(author).map(csl_name).collect();
+            item.insert("author".to_string(), Value::Array(authors));
+        }
+        if let Some(site_name) = &page.open_graph.site_name {
+            item.insert("container-title".to_string(), json!(site_name));
+        }
+        if let Some(date) = page.published_at.as_deref().and_then(iso_date) {
+            item.insert("issued".to_string(), date_parts(date));
+        }
+        item.insert("accessed".to_string(), date_parts(&accessed));
+        item.insert("URL".to_string(), json!(page.url));
+        if let Some(description) = &page.description {
+            item.insert("abstract".to_string(), json!(description));
+        }
+        if let Some(language) = &page.language {
+            item.insert("language".to_string(), json!(language));
+        }
+        items.push(Value::Object(item));
+    }
+    Ok(format!("{}\n", serde_json::to_string_pretty(&items)?))
+}
+
+/// `doe2024borrowing`: the author's last name, the year and the first word of the title,
+/// the domain standing for a missing author
+fn cite_key(page: &ScrapedWebpage) -> String {
+    let name = page
+        .author
+        .as_deref()
+        .and_then(|author| authors(author).next())
+        .and_then(|author| author.split_whitespace().last())
+        .map(str::to_string)
+        .or_else(|| {
+            domains::url_domain(&page.url)
+                .map(|domain| domain.split('.').next().unwrap_or_default().to_string())
+        })
+        .unwrap_or_default();
+    let year = page
+        .published_at
+        .as_deref()
+        .and_then(iso_date)
+        .map(|date| &date[..4])
+        .unwrap_or_default();
+    let word = page
+        .title
+        .split(|c: char| !c.is_alphanumeric())
+        .find(|word| word.chars().count() > 3)
+        .unwrap_or_default();
+    let key: String = format!("{}{}{}", name, year, word)
+        .to_lowercase()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    match key.is_empty() {
+        true => "page".to_string(),
+        false => key,
+    }
+}
+
+/// Names of the `author` field, comma separated
+fn authors(author: &str) -> impl Iterator<Item = &str> {
+    author
+        .split(", ")
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// The key, suffixed with `a`, `b`... `z`, then `27`, `28`... when already taken
+fn unique_key(key: &str, keys: &mut HashSet<String>) -> String {
+    let letters = ('a'..='z').map(|suffix| format!("{}{}", key, suffix));
+    let numbers = (27..).map(|suffix| format!("{}{}", key, suffix));
+    let unique = std::iter::once(key.to_string())
+        .chain(letters)
+        .chain(numbers)
+        .find(|candidate| !keys.contains(candidate))
+        .expect("unbounded suffixes");
+    keys.insert(unique.clone());
+    unique
+}
+
+/// `YYYY-MM-DD` of an ISO 8601 date or time
+fn iso_date(date: &str) -> Option<&str> {
+    let day = date.get(..10)?;
+    let valid = day.char_indices().all(|(index, c)| match index {
+        4 | 7 => c == '-',
+        _ => c.is_ascii_digit(),
+    });
+    valid.then_some(day)
+}
+
+fn today() -> String {
+    now()[..10].to_string()
+}
+
+fn date_parts(date: &str) -> Value {
+    let parts: Vec<u32> = date
+        .split('-')
+        .filter_map(|part| part.parse().ok())
+        .collect();
+    json!({ "date-parts": [parts] })
+}
+
+/// `Jane Doe` as family and given names; a one word name, like an organization, as is
+fn csl_name(author: &str) -> Value {
+    match author.rsplit_once(' ') {
+        Some((given, family)) => json!({ "family": family, "given": given.trim() }),
+        None => json!({ "literal": author }),
+    }
+}
+
+/// LaTeX special characters escaped for a field value
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,248 @@
+use anyhow::{anyhow, Result};
+use reqwest::redirect::Policy;
+use reqwest::{header, Client};
+use url::{form_urlencoded, Url};
+
+use crate::domains;
+use crate::scrap::{with_network, ScrapOptions, ScraperError};
+
+/// Query parameters of analytics and ad click ids, besides the `utm_*` ones
+const TRACKING_PARAMS: [&str; 20] = [
+    "fbclid",
+    "gclid",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "msclkid",
+    "yclid",
+    "twclid",
+    "ttclid",
+    "li_fat_id",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_ga",
+    "_gl",
+    "_hsenc",
+    "_hsmi",
+    "mkt_tok",
+    "oly_anon_id",
+    "oly_enc_id",
+];
+
+/// Parameters tracking on some sites only, `ref` being a git ref on github. Hosts match
+/// their subdomains, `NAME.*` any registrable domain `NAME.SUFFIX`; a trailing `*` in a
+/// parameter is a prefix.
+const HOST_PARAMS: [(&str, &[&str]); 8] = [
+    (
+        "amazon.*",
+        &[
+            "ref",
+            "ref_",
+            "pf_rd_*",
+            "pd_rd_*",
+            "_encoding",
+            "psc",
+            "qid",
+            "sr",
+            "crid",
+            "sprefix",
+            "content-id",
+            "dib",
+            "dib_tag",
+        ],
+    ),
+    ("open.spotify.com", &["si", "context", "nd"]),
+    ("youtube.com", &["si", "feature", "pp"]),
+    ("youtu.be", &["si", "feature"]),
+    ("twitter.com", &["s", "t", "ref_src", "ref_url"]),
+    ("x.com", &["s", "t", "ref_src", "ref_url"]),
+    ("instagram.com", &["igsh", "img_index"]),
+    (
+        "linkedin.com",
+        &["trk", "trackingId", "lipi", "midToken", "midSig"],
+    ),
+];
+
+/// Outbound link wrappers: host (as in `HOST_PARAMS`), path (any when empty) and the
+/// parameters holding the destination
+const REDIRECTORS: [(&str, &str, &[&str]); 12] = [
+    ("google.*", "/url", &["q", "url"]),
+    ("l.facebook.com", "/l.php", &["u"]),
+    ("lm.facebook.com", "/l.php", &["u"]),
+    ("l.instagram.com", "/", &["u"]),
+    ("out.reddit.com", "", &["url"]),
+    ("youtube.com", "/redirect", &["q"]),
+    ("t.umblr.com", "/redirect", &["z"]),
+    ("slack-redir.net", "/link", &["url"]),
+    ("steamcommunity.com", "/linkfilter/", &["url", "u"]),
+    ("disq.us", "/url", &["url"]),
+    ("linkedin.com", "/redir/redirect", &["url"]),
+    ("vk.com", "/away.php", &["to"]),
+];
+
+/// Destination of an outbound redirector link like `https://www.google.com/url?q=URL`
+pub fn unwrap_redirector(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    let (_, _, params) = REDIRECTORS.iter().find(|(pattern, path, _)| {
+        host_matches(host, pattern) && (path.is_empty() || parsed.path() == *path)
+    })?;
+    let target = parsed
+        .query_pairs()
+        .find(|(name, _)| params.contains(&name.as_ref()))
+        .map(|(_, target)| target.into_owned())?;
+    Url::parse(&target)
+        .ok()
+        .filter(|target| matches!(target.scheme(), "http" | "https"))
+        .map(|_| target)
+}
+
+/// The destination of redirector links, without its tracking parameters
+pub fn clean_url(url: &str) -> String {
+    let mut url = url.to_string();
+    // wrapped destinations are shorter, so this ends
+    while let Some(target) = unwrap_redirector(&url) {
+        url = target;
+    }
+    cleanup_tracking_params(&url)
+}
+
+/// Hosts of url shorteners, expanded by `scrapr clean --expand`
+const SHORTENERS: [&str; 20] = [
+    "bit.ly",
+    "bitly.com",
+    "t.co",
+    "goo.gl",
+    "tinyurl.com",
+    "ow.ly",
+    "buff.ly",
+    "is.gd",
+    "v.gd",
+    "lnkd.in",
+    "dlvr.it",
+    "trib.al",
+    "amzn.to",
+    "fb.me",
+    "rebrand.ly",
+    "t.ly",
+    "cutt.ly",
+    "tiny.cc",
+    "rb.gy",
+    "shorturl.at",
+];
+
+pub fn is_shortener(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .is_some_and(|host| SHORTENERS.contains(&host.trim_start_matches("www.")))
+}
+
+/// Canonical form of an absolute url: lowercase scheme and host, default port and dot
+/// segments removed, characters percent-encoded where needed
+pub fn normalize_url(url: &str) -> Result<String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| anyhow!("Invalid url {}: {}", url, e))?;
+    Ok(parsed.to_string())
+}
+
+/// Client of `expand_url`, its redirects followed by hand
+pub fn expand_client(options: &ScrapOptions) -> Result<Client> {
+    let client = Client::builder()
+        .timeout(options.timeout)
+        .user_agent(options.user_agent.as_str())
+        .redirect(Policy::none());
+    Ok(with_network(client, options).build()?)
+}
+
+/// Where a shortened url leads, from the `Location` of HEAD requests; the destination
+/// itself is not requested
+pub async fn expand_url(client: &Client, url: &str, options: &ScrapOptions) -> Result<String> {
+    let mut url = url.to_string();
+    for _ in 0..options.max_redirects {
+        if !is_shortener(&url) {
+            return Ok(url);
+        }
+        let response = client.head(&url).send().await?;
+        if !response.status().is_redirection() {
+            return Ok(url);
+        }
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or(anyhow!("Redirect without location from {}", url))?;
+        url = Url::parse(&url)?.join(location)?.to_string();
+    }
+    match is_shortener(&url) {
+        true => Err(anyhow!(ScraperError::TooManyRedirects(
+            url,
+            options.max_redirects
+        ))),
+        false => Ok(url),
+    }
+}
+
+/// Whether a query parameter of an url on `host` only serves tracking
+pub fn is_tracking_param(host: &str, name: &str) -> bool {
+    let name = name.to_lowercase();
+    if name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str()) {
+        return true;
+    }
+    HOST_PARAMS
+        .iter()
+        .filter(|(pattern, _)| host_matches(host, pattern))
+        .flat_map(|(_, params)| params.iter())
+        .any(|param| match param.strip_suffix('*') {
+            Some(prefix) => name.starts_with(&prefix.to_lowercase()),
+            None => name == param.to_lowercase(),
+        })
+}
+
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let host = host.to_lowercase();
+    match pattern.strip_suffix(".*") {
+        Some(name) => domains::registrable_domain(&host)
+            .is_some_and(|domain| domain.split('.').next() == Some(name)),
+        None => host == pattern || host.ends_with(&format!(".{}", pattern)),
+    }
+}
+
+/// The url without its tracking query parameters. The other parameters are kept as
+/// written, in their order, and the rest of the url is left untouched.
+pub fn cleanup_tracking_params(url: &str) -> String {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    let Some((base, query)) = rest.split_once('?') else {
+        return url.to_string();
+    };
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    // names are compared decoded, `utm%5Fsource` being `utm_source`; kept parameters are
+    // copied, `query_pairs_mut` re-encoding `%20` as `+` and raw utf-8
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            form_urlencoded::parse(param.as_bytes())
+                .next()
+                .is_none_or(|(name, _)| !is_tracking_param(&host, &name))
+        })
+        .collect();
+    if kept.len() == query.split('&').count() {
+        return url.to_string();
+    }
+    let mut cleaned = base.to_string();
+    if kept.iter().any(|param| !param.is_empty()) {
+        cleaned.push('?');
+        cleaned.push_str(&kept.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        cleaned.push('#');
+        cleaned.push_str(fragment);
+    }
+    cleaned
+}
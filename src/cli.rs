@@ -0,0 +1,1254 @@
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use clap::{ArgGroup, Parser, Subcommand};
+use reqwest::cookie::Jar;
+use reqwest::{Method, NoProxy, Proxy};
+use serde_json::Value;
+use url::Url;
+
+use crate::analysis::{self, Analysis};
+use crate::bandwidth::BandwidthLimiter;
+use crate::cache::HttpCache;
+use crate::dns::DnsCache;
+use crate::filter::Filter;
+use crate::format::{
+    format_domains, format_json, format_jsonl, format_response, format_responses, Format, Shape,
+    Style,
+};
+use crate::impersonate::Browser;
+use crate::paths::Paths;
+use crate::pluck::PluckPath;
+use crate::redirectmap::{self, RedirectRules};
+use crate::results::Arrange;
+use crate::scrap::{grab_url, ScrapOptions, Scraped, ScrapedWebpage, ACCEPT_HTML, ACCEPT_JSON};
+use crate::secret::resolve_secrets;
+use crate::store::{OnConflict, Store};
+use crate::sync::{SyncConfig, SyncStrategy};
+use crate::template::PageTemplate;
+use crate::timeout::{AdaptiveTimeout, TimeoutSetting};
+use crate::watch::WatchSettings;
+use crate::{
+    bandwidth, batch, cleanup, compare, config, daemon, domains, download, editor, export,
+    fixtures, flow, format, forms, history, hook, imagemeta, lint, notify, paginate, podcast,
+    resources, retry, secret, sitemap, store, sync, timeout, units, watch, xml,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("urls").args(["url", "positional_urls", "stdin", "input_file", "sitemap"]).required(true).multiple(true)))]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Url to scrape (repeatable), several urls give a list
+    #[arg(short, long)]
+    url: Vec<String>,
+    /// Urls to scrape, as with --url; `-` reads them from stdin
+    #[arg(value_name = "URL")]
+    positional_urls: Vec<String>,
+    /// Read newline separated urls from stdin
+    #[arg(long)]
+    stdin: bool,
+    /// Read newline separated urls from a file, reporting each line on stderr
+    #[arg(long)]
+    input_file: Option<PathBuf>,
+    /// Scrape the urls of a sitemap, skipping those whose `<lastmod>` is not newer
+    /// than their latest snapshot in the link store
+    #[arg(long)]
+    sitemap: Option<String>,
+    /// Client defaults from a configured profile
+    #[arg(short, long)]
+    profile: Option<String>,
+    /// Keep config, cache and data under this directory instead of the XDG directories
+    #[arg(long, global = true, env = "SCRAPR_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value = "full")]
+    style: Style,
+    #[arg(short, long, value_enum, default_value = "markdown")]
+    format: Format,
+    /// Accept header sent to content negotiating endpoints (default depends on mode)
+    #[arg(long)]
+    accept: Option<String>,
+    /// Extract values from a JSON response with a jq-like path (e.g. `.data.items[].title`)
+    #[arg(long)]
+    pluck: Option<String>,
+    /// Extract values from a XML response with a xpath expression (e.g. `//item/name`)
+    #[arg(long)]
+    xpath: Option<String>,
+    /// Follow a next page pointer of a JSON API (e.g. `next=.links.next`)
+    #[arg(long)]
+    paginate: Option<String>,
+    /// Maximum number of pages fetched with --paginate
+    #[arg(long, default_value_t = 10)]
+    max_pages: usize,
+    /// HTTP method of the request
+    #[arg(long, default_value = "GET", value_parser = parse_method)]
+    method: Method,
+    /// Request body, `@file` reads it from a file
+    #[arg(long)]
+    body: Option<String>,
+    /// Content-Type header of the request body
+    #[arg(long)]
+    content_type: Option<String>,
+    /// Submit the Nth form of the page (see `--style forms`) with its cookies and scrape the result
+    #[arg(long)]
+    submit_form: Option<usize>,
+    /// Form field value used with --submit-form (`key=value`, repeatable)
+    #[arg(long = "field", value_parser = forms::parse_field)]
+    fields: Vec<(String, String)>,
+    /// Response header copied into the JSON output (repeatable)
+    #[arg(long = "capture-header")]
+    capture_headers: Vec<String>,
+    /// Cap on the download rate shared by all requests (e.g. `2MBps`)
+    #[arg(long, value_parser = bandwidth::parse_bandwidth)]
+    max_bandwidth: Option<u64>,
+    /// Reuse cached responses of GET requests
+    #[arg(long)]
+    cache: bool,
+    /// How long cached responses without caching headers stay fresh
+    #[arg(long, default_value = "1h", value_parser = humantime::parse_duration)]
+    cache_ttl: Duration,
+    /// Keep every cached response for --cache-ttl, ignoring Cache-Control and Expires
+    #[arg(long)]
+    cache_ignore_headers: bool,
+    /// Cache size above which least recently used responses are evicted
+    #[arg(long, default_value = "100MB", value_parser = units::parse_bytes)]
+    cache_max_size: u64,
+    /// Fail on homograph hostnames (mixed scripts, latin look-alikes) instead of warning
+    #[arg(long)]
+    deny_homographs: bool,
+    /// Request timeout, `auto` derives it from the past latencies of each host
+    #[arg(long, value_parser = timeout::parse_timeout)]
+    timeout: Option<TimeoutSetting>,
+    /// Lower bound of `--timeout auto`
+    #[arg(long, default_value = "1s", value_parser = humantime::parse_duration)]
+    timeout_min: Duration,
+    /// Upper bound of `--timeout auto`, also used for hosts without history
+    #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+    timeout_max: Duration,
+    /// Redirects followed before failing
+    #[arg(long, default_value_t = 10)]
+    max_redirects: usize,
+    /// Retry a GET fetch failing on a timeout, a connection error or a 5xx status up to N times
+    #[arg(long, default_value_t = 0)]
+    retries: usize,
+    /// Wait before the first retry, doubled at each of the next ones, with jitter
+    #[arg(long, default_value = "1s", value_parser = humantime::parse_duration)]
+    retry_delay: Duration,
+    /// Keep DNS lookups this long instead of the TTL of their records
+    #[arg(long, value_parser = humantime::parse_duration)]
+    dns_cache_ttl: Option<Duration>,
+    /// Send requests through a proxy (`http://`, `https://`, `socks5://` or `socks5h://`),
+    /// instead of the `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` variables
+    #[arg(long, value_parser = parse_proxy)]
+    proxy: Option<Proxy>,
+    /// Use the canonical url of pages declaring one as their `url`, in links and the store
+    #[arg(long)]
+    prefer_canonical: bool,
+    /// Remove tracking query parameters (`utm_*`, click ids) and unwrap outbound redirectors,
+    /// in the urls before fetching and in the links of pages
+    #[arg(long)]
+    strip_tracking: bool,
+    /// Send the full request headers of a browser, in its order (not its TLS fingerprint)
+    #[arg(long, value_enum)]
+    impersonate: Option<Browser>,
+    /// Report a page whose extraction panics as malformed instead of aborting
+    #[arg(long)]
+    robust: bool,
+    /// Tags kept on a page, from `article:tag` then keywords
+    #[arg(long)]
+    max_tags: Option<usize>,
+    /// Urls fetched at once when several are given, on one shared client
+    #[arg(long, default_value_t = 1)]
+    concurrency: usize,
+    /// Urls of one host fetched at once, within `--concurrency`
+    #[arg(long, default_value_t = 2)]
+    per_host_concurrency: usize,
+    /// Download the audio files of podcast episodes into this directory
+    #[arg(long)]
+    download_audio: Option<PathBuf>,
+    /// Download the hero image (`og:image`, first image) into this directory, adding its
+    /// EXIF/IPTC metadata to the output
+    #[arg(long)]
+    download_image: Option<PathBuf>,
+    /// Download the page icon into this directory
+    #[arg(long)]
+    download_favicon: Option<PathBuf>,
+    /// Save the scraped page into the link store
+    #[arg(long)]
+    save: bool,
+    /// Only output these JSON fields, in this order (e.g. `title,url,http.version`)
+    #[arg(long = "fields", value_delimiter = ',')]
+    output_fields: Vec<String>,
+    /// Rename a JSON output field (`old=new`, repeatable)
+    #[arg(long = "rename", value_parser = format::parse_rename)]
+    renames: Vec<(String, String)>,
+    /// Render each page with a Tera template instead of `--style` and `--format`
+    #[arg(long)]
+    template: Option<PathBuf>,
+    /// Report over all the pages instead of a record per page
+    #[arg(long, conflicts_with = "template")]
+    analyze: Option<Analysis>,
+    /// Terms and bigrams reported by `--analyze keywords`
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+    /// Similarity from 0 to 1 from which `--analyze duplicates` clusters pages
+    #[arg(long, default_value = "0.9", value_parser = units::parse_ratio)]
+    min_similarity: f64,
+    /// With `--format html`, a preview card (image, title, description) instead of a link
+    #[arg(long)]
+    html_card: bool,
+    /// Sort collected results on a field, `-` prefixed for descending (e.g. `title`, `-status`, `domain`)
+    #[arg(long)]
+    sort: Option<String>,
+    /// Keep one result per value of a field (e.g. `domain`)
+    #[arg(long)]
+    unique_by: Option<String>,
+    /// Maximum number of collected results
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Only output results matching an expression (e.g. `language == "en" && description != null`)
+    #[arg(long, global = true, value_parser = Filter::parse)]
+    filter: Option<Filter>,
+    /// Only report watch checks matching an expression over the page and its
+    /// `<field>_changed` flags (e.g. `title_changed || status != 200`)
+    #[arg(long, global = true, value_parser = Filter::parse)]
+    emit_when: Option<Filter>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a multi-step scrape flow from a YAML script
+    Run { script: String },
+    /// Manage secrets stored in the OS keyring, referenced as `{{secret:NAME}}`
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommand,
+    },
+    /// Manage client profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Print the directories used for config, cache and data
+    Paths,
+    /// Manage the link store
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Snapshots of a stored url over time, with their changes
+    History { url: String },
+    /// Clean the urls of stdin without fetching them: normalized, redirectors unwrapped,
+    /// tracking parameters removed
+    Clean {
+        /// Follow the redirects of url shorteners (bit.ly, t.co...) with HEAD requests
+        #[arg(long)]
+        expand: bool,
+    },
+    /// Clean the links of markdown files, as a git pre-commit hook: tracking parameters
+    /// removed, exiting with 1 on dead links
+    Hook {
+        /// Markdown files, fixed in place
+        #[arg(required_unless_present = "staged")]
+        files: Vec<PathBuf>,
+        /// Review the staged markdown files and stage their fixes
+        #[arg(long, conflicts_with = "files")]
+        staged: bool,
+        /// Use the page title as the text of bare links: `<url>`, `[](url)`, `[url](url)`
+        #[arg(long)]
+        fill_titles: bool,
+    },
+    /// Answer json requests of stdin with formatted snippets, a line each, for editor plugins
+    EditorServer,
+    /// Contributor tools
+    Dev {
+        #[command(subcommand)]
+        command: DevCommand,
+    },
+    /// Export the stored snapshots of an url
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+    /// Inspect and clean the response cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Run the `[job.NAME]` scrapes of config.toml at their interval
+    Daemon {
+        /// Run every job once and exit
+        #[arg(long)]
+        once: bool,
+    },
+    /// Check a page at an interval and report its changes
+    Watch {
+        url: String,
+        /// Interval between checks
+        #[arg(long, default_value = "5m", value_parser = humantime::parse_duration)]
+        every: Duration,
+        /// Stop after this many checks
+        #[arg(long)]
+        max_checks: Option<usize>,
+        /// Send changes to a notifier: `desktop`, `email`, `slack:URL`, `discord:URL`, `matrix`, `ntfy:TOPIC`, `gotify` (repeatable)
+        #[arg(long)]
+        notify: Vec<String>,
+        /// Only report changes lowering the price of the page
+        #[arg(long)]
+        price_drops: bool,
+    },
+    /// Scrape several urls and compare their key fields side by side
+    Compare {
+        #[arg(required = true, num_args = 2..)]
+        urls: Vec<String>,
+    },
+    /// Match the pages of an old site with those of a new one, by title and content,
+    /// into suggested 301 redirects
+    RedirectMap {
+        /// Url list of the old site
+        old: PathBuf,
+        /// Url list of the new site
+        new: PathBuf,
+        #[arg(long, value_enum, default_value_t)]
+        rules: RedirectRules,
+        /// Score from 0 to 1 below which an old page is left without redirect
+        #[arg(long, default_value = "0.5", value_parser = units::parse_ratio)]
+        min_score: f64,
+    },
+    /// Check the metadata of a page against rules, exiting with 1 on errors
+    Lint {
+        url: String,
+        /// TOML rules, instead of the `[lint]` rules of config.toml or the default ones
+        #[arg(long)]
+        rules: Option<PathBuf>,
+        /// Exit with 1 on warnings too
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Retry the urls which failed in jobs, with adjusted settings
+    RetryFailed {
+        /// Request timeout of the retries
+        #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+        timeout: Duration,
+        /// User agent of the retries
+        #[arg(long)]
+        user_agent: Option<String>,
+        /// Only list the queued urls
+        #[arg(long)]
+        list: bool,
+    },
+    /// Manage the public suffix list used for registrable domains
+    Psl {
+        #[command(subcommand)]
+        command: PslCommand,
+    },
+    /// Pull and push the link store to the `[sync]` git remote or S3 bucket of config.toml
+    Sync {
+        /// Remote url, instead of the configured one
+        #[arg(long)]
+        remote: Option<String>,
+        #[arg(long, value_enum)]
+        strategy: Option<SyncStrategy>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbCommand {
+    /// Upgrade the store schema
+    Migrate {
+        /// Only list pending migrations
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Export links, tags and snapshots as a JSON archive (gzip for `.gz` files)
+    Export { file: PathBuf },
+    /// Import a JSON archive
+    Import {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value = "newest")]
+        on_conflict: OnConflict,
+    },
+    /// Remove the snapshots left out by the `[retention]` policy of config.toml
+    Prune {
+        /// Only count the snapshots to remove
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DevCommand {
+    /// Rewrite the expected records of the fixture corpus from the current extraction
+    Bless {
+        #[arg(default_value = "tests/fixtures")]
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ExportCommand {
+    /// Commit the text of each snapshot in a git repository, dated by its scrape time
+    Git { url: String, dir: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+enum PslCommand {
+    /// Download the current list into the data directory, used instead of the embedded one
+    Update,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// Entries count and size
+    Stats,
+    /// Remove all entries
+    Clear,
+    /// Remove entries older than a duration, and the least recently used above the size limit
+    Prune {
+        #[arg(long, value_parser = humantime::parse_duration)]
+        older_than: Option<Duration>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SecretCommand {
+    /// Store a secret, its value read from stdin
+    Set { name: String },
+    /// Remove a secret
+    Delete { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileCommand {
+    /// List profiles of config.toml and encrypted profiles
+    List,
+    /// Encrypt a profile TOML file with the SCRAPR_PASSPHRASE passphrase
+    Encrypt { file: String, name: String },
+}
+
+impl Args {
+    fn default_accept(&self) -> &'static str {
+        if self.pluck.is_some() || self.paginate.is_some() {
+            ACCEPT_JSON
+        } else {
+            ACCEPT_HTML
+        }
+    }
+
+    fn shape(&self) -> Shape {
+        Shape {
+            fields: self.output_fields.clone(),
+            renames: self.renames.clone(),
+            html_card: self.html_card,
+        }
+    }
+
+    /// Urls to scrape, with their `--input-file` line
+    fn inputs(&self) -> Result<Vec<(String, Option<String>)>> {
+        let mut inputs = vec![];
+        let mut stdin = self.stdin;
+        for url in self.url.iter().chain(&self.positional_urls) {
+            match url.as_str() {
+                "-" => stdin = true,
+                url => inputs.push((url.to_string(), None)),
+            }
+        }
+        if stdin {
+            let urls = batch::read_urls(std::io::stdin().lock())?;
+            inputs.extend(urls.into_iter().map(|url| (url, None)));
+        }
+        if let Some(path) = &self.input_file {
+            let file = std::fs::File::open(path)
+                .map_err(|e| anyhow!("Cannot read url list {}: {}", path.display(), e))?;
+            let urls = batch::read_numbered_urls(std::io::BufReader::new(file))?;
+            inputs.extend(urls.into_iter().map(|(line, url)| {
+                let source = format!("{}:{}", path.display(), line);
+                (url, Some(source))
+            }));
+        }
+        Ok(inputs)
+    }
+
+    fn arrange(&self) -> Arrange {
+        Arrange {
+            sort: self.sort.clone(),
+            unique_by: self.unique_by.clone(),
+            limit: self.limit,
+        }
+    }
+
+    fn paths(&self) -> Result<Paths> {
+        Paths::resolve(self.data_dir.as_deref())
+    }
+
+    fn http_cache(&self) -> Result<HttpCache> {
+        Ok(HttpCache::new(
+            self.paths()?.http_cache(),
+            self.cache_ttl,
+            self.cache_max_size,
+            !self.cache_ignore_headers,
+        ))
+    }
+
+    fn scrap_options(&self) -> Result<ScrapOptions> {
+        let body = match self.body.as_deref() {
+            Some(body) => match body.strip_prefix('@') {
+                Some(path) => Some(resolve_secrets(&std::fs::read_to_string(path)?)?),
+                None => Some(resolve_secrets(body)?),
+            },
+            None => None,
+        };
+        let mut options = ScrapOptions {
+            accept: self.default_accept().to_string(),
+            method: self.method.clone(),
+            body,
+            content_type: self.content_type.clone(),
+            capture_headers: self.capture_headers.clone(),
+            cache: self.cache.then(|| self.http_cache()).transpose()?,
+            bandwidth: self
+                .max_bandwidth
+                .map(|rate| Arc::new(BandwidthLimiter::new(rate))),
+            deny_homographs: self.deny_homographs,
+            max_redirects: self.max_redirects,
+            prefer_canonical: self.prefer_canonical,
+            impersonate: self.impersonate,
+            robust: self.robust,
+            max_tags: self.max_tags,
+            strip_tracking: self.strip_tracking,
+            retries: self.retries,
+            retry_delay: self.retry_delay,
+            // the system resolver of the client otherwise
+            dns_cache: DnsCache::from_system_conf(self.dns_cache_ttl)
+                .ok()
+                .map(Arc::new),
+            proxy: self.proxy.clone(),
+            per_host_concurrency: self.per_host_concurrency,
+            ..Default::default()
+        };
+        // a profile or --accept still override them
+        if let Some(browser) = self.impersonate {
+            options.user_agent = browser.user_agent().to_string();
+            if options.accept == ACCEPT_HTML {
+                options.accept = browser.accept().to_string();
+            }
+        }
+        if let Some(profile) = &self.profile {
+            config::load_profile(&self.paths()?, profile)?.apply(&mut options)?;
+        }
+        if let Some(accept) = &self.accept {
+            options.accept = accept.clone();
+        }
+        match self.timeout {
+            Some(TimeoutSetting::Fixed(timeout)) => options.timeout = timeout,
+            Some(TimeoutSetting::Auto) => {
+                options.adaptive_timeout = Some(Arc::new(AdaptiveTimeout {
+                    p95: Store::open(&self.paths()?.data)?.latency_p95()?,
+                    min: self.timeout_min,
+                    max: self.timeout_max,
+                }))
+            }
+            None => {}
+        }
+        Ok(options)
+    }
+}
+
+/// A proxy url, bypassed for the hosts of `NO_PROXY`
+fn parse_proxy(proxy: &str) -> Result<Proxy> {
+    let parsed = Url::parse(proxy).map_err(|e| anyhow!("Invalid proxy {}: {}", proxy, e))?;
+    if !matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(anyhow!(
+            "Invalid proxy {}: expected http, https, socks5 or socks5h",
+            proxy
+        ));
+    }
+    Ok(Proxy::all(proxy)?.no_proxy(NoProxy::from_env()))
+}
+
+fn parse_method(method: &str) -> Result<Method> {
+    Ok(Method::from_bytes(method.to_uppercase().as_bytes())?)
+}
+
+/// The `scrapr` command line
+pub async fn run() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let args = Args::parse();
+    let options = args.scrap_options()?;
+    if let Ok(paths) = args.paths() {
+        domains::load_public_suffixes(&paths.data)?;
+    }
+    match &args.command {
+        Some(Command::Run { script }) => {
+            let result = flow::run_flow(flow::read_flow(script)?, &options).await?;
+            println!("{}", serde_json::to_string_pretty(&result)?);
+            return Ok(());
+        }
+        Some(Command::Secret { command }) => return run_secret_command(command),
+        Some(Command::Profile { command }) => return run_profile_command(command, &args.paths()?),
+        Some(Command::Db { command }) => return run_db_command(command, &args.paths()?),
+        Some(Command::Paths) => {
+            println!("{}", serde_json::to_string_pretty(&args.paths()?)?);
+            return Ok(());
+        }
+        Some(Command::History { url }) => {
+            let store = Store::open(&args.paths()?.data)?;
+            let snapshots = store
+                .url_snapshots(url)?
+                .ok_or(anyhow!("{} is not in the link store", url))?;
+            let entries = history::timeline(snapshots);
+            print!(
+                "{}",
+                history::format_history(&entries, args.format, &args.shape())?
+            );
+            return Ok(());
+        }
+        Some(Command::Clean { expand }) => return clean_urls(*expand, &options).await,
+        Some(Command::Hook {
+            files,
+            staged,
+            fill_titles,
+        }) => {
+            if !hook::run_hook(files, *staged, *fill_titles, &options).await? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::EditorServer) => return editor::serve(&options).await,
+        Some(Command::Dev {
+            command: DevCommand::Bless { dir },
+        }) => {
+            let changed = fixtures::bless(dir).await?;
+            for name in &changed {
+                eprintln!("blessed {}", name);
+            }
+            eprintln!("{} fixtures changed", changed.len());
+            return Ok(());
+        }
+        Some(Command::Export {
+            command: ExportCommand::Git { url, dir },
+        }) => {
+            let store = Store::open(&args.paths()?.data)?;
+            let commits = export::export_git(&store, url, dir)?;
+            eprintln!("{} commits in {}", commits, dir.display());
+            return Ok(());
+        }
+        Some(Command::Cache { command }) => return run_cache_command(command, &args.http_cache()?),
+        Some(Command::Daemon { once }) => {
+            return daemon::run_daemon(&args.paths()?, &options, *once).await
+        }
+        Some(Command::Watch {
+            url,
+            every,
+            max_checks,
+            notify,
+            price_drops,
+        }) => {
+            let notifiers = notify::notifiers(notify, &config::read_config(&args.paths()?)?)?;
+            let filter = args.filter.as_ref();
+            let settings = WatchSettings {
+                every: *every,
+                max_checks: *max_checks,
+                format: args.format,
+                price_drops: *price_drops,
+                emit_when: args.emit_when.clone(),
+            };
+            return watch::watch(url, &settings, &options, &notifiers, filter).await;
+        }
+        Some(Command::Compare { urls }) => {
+            let outcomes = batch::grab_all(urls, &options, None, args.concurrency).await;
+            let comparisons = compare::compare(&outcomes);
+            print!(
+                "{}",
+                compare::format_comparisons(&comparisons, args.format, &args.shape())?
+            );
+            return Ok(());
+        }
+        Some(Command::RedirectMap {
+            old,
+            new,
+            rules,
+            min_score,
+        }) => {
+            let (old, new) = (daemon::read_url_list(old)?, daemon::read_url_list(new)?);
+            let map =
+                redirectmap::redirect_map(&old, &new, *min_score, &options, args.concurrency).await;
+            for url in &map.unmatched {
+                eprintln!("{}: no match", url);
+            }
+            print!("{}", redirectmap::format_redirect_map(&map, *rules)?);
+            return Ok(());
+        }
+        Some(Command::Lint {
+            url,
+            rules,
+            deny_warnings,
+        }) => {
+            let rules = match rules {
+                Some(path) => lint::read_rules(path)?,
+                None => config::read_config(&args.paths()?)?
+                    .lint
+                    .unwrap_or_else(lint::default_rules),
+            };
+            let page = grab_url(url, &options)
+                .await
+                .and_then(|scraped| match scraped {
+                    Scraped::Webpage(page) => Ok(page),
+                    _ => Err(anyhow!("{} is not an html page", url)),
+                });
+            // 2 tells a page which could not be linted from a failing one
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    std::process::exit(2);
+                }
+            };
+            let report = lint::lint(&page, url, &rules);
+            print!("{}", lint::format_lint(&report, args.format)?);
+            if report.has_errors() || (*deny_warnings && report.has_warnings()) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::RetryFailed {
+            timeout,
+            user_agent,
+            list,
+        }) => {
+            let store = Store::open(&args.paths()?.data)?;
+            if *list {
+                println!("{}", serde_json::to_string_pretty(&store.failures()?)?);
+                return Ok(());
+            }
+            let report =
+                retry::retry_failed(&store, &options, *timeout, user_agent.as_deref()).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        Some(Command::Psl {
+            command: PslCommand::Update,
+        }) => {
+            let path = domains::update_public_suffixes(&args.paths()?.data, &options).await?;
+            println!("{}", path.display());
+            return Ok(());
+        }
+        Some(Command::Sync { remote, strategy }) => {
+            let paths = args.paths()?;
+            let mut sync_config = match (config::read_config(&paths)?.sync, remote) {
+                (Some(sync_config), _) => sync_config,
+                (None, Some(remote)) => SyncConfig::new(remote),
+                (None, None) => return Err(anyhow!("No [sync] remote in config.toml")),
+            };
+            if let Some(remote) = remote {
+                sync_config.remote = remote.clone();
+            }
+            if let Some(strategy) = strategy {
+                sync_config.strategy = *strategy;
+            }
+            let report = sync::sync(&paths, &sync_config, &options).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        None => {}
+    }
+    let mut inputs = args.inputs()?;
+    if let Some(sitemap) = &args.sitemap {
+        let entries = sitemap::fetch_sitemap(sitemap, &options).await?;
+        let total = entries.len();
+        let store = Store::open(&args.paths()?.data)?;
+        let (changed, unchanged) = sitemap::changed_entries(entries, &store)?;
+        eprintln!(
+            "{}: {} urls, {} unchanged since the last scrape skipped",
+            sitemap, total, unchanged
+        );
+        inputs.extend(
+            changed
+                .into_iter()
+                .map(|entry| (entry.url, Some(sitemap.clone()))),
+        );
+        if inputs.is_empty() {
+            return Ok(());
+        }
+    }
+    if args.strip_tracking {
+        for (url, _) in &mut inputs {
+            *url = cleanup::clean_url(url);
+        }
+    }
+    let urls: Vec<String> = inputs.iter().map(|(url, _)| url.clone()).collect();
+    let url = urls.first().cloned().ok_or(anyhow!("no url to scrape"))?;
+    let batch = urls.len() > 1 || args.input_file.is_some() || args.sitemap.is_some();
+    let single_page_options = [
+        ("--paginate", args.paginate.is_some()),
+        ("--pluck", args.pluck.is_some()),
+        ("--xpath", args.xpath.is_some()),
+        ("--submit-form", args.submit_form.is_some()),
+        ("--download-image", args.download_image.is_some()),
+        ("--download-audio", args.download_audio.is_some()),
+    ];
+    if let Some((option, _)) = single_page_options.iter().find(|(_, set)| batch && *set) {
+        return Err(anyhow!("{} applies to a single url", option));
+    }
+    let pluck = args.pluck.as_deref().map(PluckPath::parse).transpose()?;
+    if let Some(paginate) = &args.paginate {
+        let next = paginate::parse_next_path(paginate)?;
+        let pages = paginate::grab_pages(&url, &next, args.max_pages, &options).await?;
+        let pages: Vec<Value> = pages
+            .into_iter()
+            .filter(|page| {
+                args.filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.matches(page))
+            })
+            .collect();
+        let pages = args.arrange().apply(pages);
+        match pluck {
+            Some(pluck) => pages.iter().for_each(|page| print_plucked(page, &pluck)),
+            None => {
+                let shape = args.shape();
+                let pages: Vec<Value> = pages.into_iter().map(|page| shape.apply(page)).collect();
+                println!("{}", serde_json::to_string_pretty(&pages)?)
+            }
+        }
+        return Ok(());
+    }
+    if args.style == Style::Domains {
+        let outcomes = batch::grab_all(&urls, &options, None, args.concurrency).await;
+        print!(
+            "{}",
+            format_domains(&domains::domain_stats(&outcomes), args.format)?
+        );
+        return Ok(());
+    }
+    let template = args
+        .template
+        .as_deref()
+        .map(PageTemplate::read)
+        .transpose()?;
+    if batch {
+        return run_batch(&args, &inputs, &options, template.as_ref()).await;
+    }
+    // a form page and its submission share a cookie jar for the session and csrf cookies,
+    // the page being fetched fresh since a cached one would not set them
+    let page_options = match args.submit_form {
+        Some(_) => ScrapOptions {
+            cookie_jar: Some(Arc::new(Jar::default())),
+            cache: None,
+            ..options.clone()
+        },
+        None => options.clone(),
+    };
+    let mut scraped = grab_url(url.as_str(), &page_options).await?;
+    if let Some(index) = args.submit_form {
+        let form = match &scraped {
+            Scraped::Webpage(page) => index.checked_sub(1).and_then(|i| page.forms.get(i)),
+            _ => None,
+        }
+        .ok_or(anyhow!("No form {} found on {}", index, url))?;
+        let fields = args
+            .fields
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), resolve_secrets(value)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let (action, submit_options) = form.submission(&fields, &page_options)?;
+        scraped = grab_url(&action, &submit_options).await?;
+    }
+    if let Some(filter) = &args.filter {
+        if !filter.matches(&scraped.to_value()?) {
+            return Ok(());
+        }
+    }
+    if let (Some(directory), Scraped::Webpage(page)) = (&args.download_image, &mut scraped) {
+        page.hero_image = imagemeta::download_hero_image(page, directory, &options).await?;
+    }
+    if let (Some(directory), Scraped::Webpage(page)) = (&args.download_favicon, &mut scraped) {
+        // the `/favicon.ico` fallback is a guess
+        if let Some(favicon) = &page.favicon {
+            match download::download_file(favicon, directory, &options).await {
+                Ok(path) => {
+                    eprintln!("Downloaded {}", path.display());
+                    page.favicon_file = Some(path.display().to_string());
+                }
+                Err(e) => eprintln!("No favicon downloaded: {}", e),
+            }
+        }
+    }
+    let items_with_icons = matches!(args.style, Style::Full | Style::Link);
+    if let (Format::ScriptFilter, true, Scraped::Webpage(page)) =
+        (args.format, items_with_icons, &mut scraped)
+    {
+        cache_favicons(std::slice::from_mut(page), &args.paths()?, &options).await;
+    }
+    if let Some(directory) = &args.download_audio {
+        let episodes = match &scraped {
+            Scraped::Webpage(page) => podcast::episodes(page),
+            Scraped::Xml(content) => podcast::feed_episodes(content),
+            Scraped::Json(_) => vec![],
+        };
+        for episode in episodes {
+            let path = download::download_file(&episode.audio_url, directory, &options).await?;
+            eprintln!("Downloaded {}", path.display());
+        }
+    }
+    match scraped {
+        Scraped::Json(value) => match pluck {
+            Some(pluck) => print_plucked(&value, &pluck),
+            None => println!("{}", format_json(&value, &args.shape())?),
+        },
+        Scraped::Xml(content) if args.style == Style::Podcast => print!(
+            "{}",
+            podcast::format_episodes(
+                &podcast::feed_episodes(&content),
+                args.format,
+                &args.shape()
+            )?
+        ),
+        Scraped::Xml(content) => match &args.xpath {
+            Some(xpath) => xml::select_xpath(&content, xpath)?
+                .iter()
+                .for_each(|value| println!("{}", value)),
+            None => println!("{}", content),
+        },
+        Scraped::Webpage(_) if pluck.is_some() => {
+            return Err(anyhow!("--pluck requires a JSON response from {}", url))
+        }
+        Scraped::Webpage(_) if args.xpath.is_some() => {
+            return Err(anyhow!("--xpath requires a XML response from {}", url))
+        }
+        Scraped::Webpage(scraped) => {
+            if args.save {
+                Store::open(&args.paths()?.data)?.save(&scraped)?;
+            }
+            if let Some(template) = &template {
+                print!("{}", template.render(&scraped)?);
+                return Ok(());
+            }
+            if let Some(analysis) = args.analyze {
+                let pages = std::slice::from_ref(&*scraped);
+                print!("{}", analysis_report(analysis, pages, &args)?);
+                return Ok(());
+            }
+            if args.style == Style::Resources {
+                let groups = resources::resource_report(&scraped, &options).await?;
+                print!(
+                    "{}",
+                    resources::format_resources(&groups, args.format, &args.shape())?
+                );
+                return Ok(());
+            }
+            if args.style == Style::Weight {
+                let weight = resources::page_weight(&scraped, &options).await?;
+                print!(
+                    "{}",
+                    resources::format_weight(&weight, args.format, &args.shape())?
+                );
+                return Ok(());
+            }
+            print!(
+                "{}",
+                format_response(&scraped, args.style, args.format, &args.shape())?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Scrape several urls into one list, failed ones reported on stderr with their
+/// `--input-file` line, as are successful ones
+async fn run_batch(
+    args: &Args,
+    inputs: &[(String, Option<String>)],
+    options: &ScrapOptions,
+    template: Option<&PageTemplate>,
+) -> Result<()> {
+    let urls: Vec<String> = inputs.iter().map(|(url, _)| url.clone()).collect();
+    // json lines are printed as urls complete, unless they have to be sorted or trimmed
+    if args.format == Format::Jsonl
+        && matches!(
+            args.style,
+            Style::Full | Style::Link | Style::Article | Style::FullContent | Style::Links
+        )
+        && args.arrange().is_empty()
+        && template.is_none()
+        && args.analyze.is_none()
+    {
+        return stream_batch(args, inputs, &urls, options).await;
+    }
+    let outcomes = batch::grab_all(&urls, options, None, args.concurrency).await;
+    let mut pages = vec![];
+    for (outcome, (_, source)) in outcomes.into_iter().zip(inputs) {
+        if let Some(page) = batch_page(outcome, source) {
+            pages.push((serde_json::to_value(&page)?, page));
+        }
+    }
+    print_summary(urls.len(), pages.len(), options);
+    if let Some(filter) = &args.filter {
+        pages.retain(|(value, _)| filter.matches(value));
+    }
+    let mut pages: Vec<ScrapedWebpage> = args
+        .arrange()
+        .apply_by(pages, |(value, _)| value)
+        .into_iter()
+        .map(|(_, page)| page)
+        .collect();
+    if args.format == Format::ScriptFilter && matches!(args.style, Style::Full | Style::Link) {
+        cache_favicons(&mut pages, &args.paths()?, options).await;
+    }
+    if args.save {
+        let store = Store::open(&args.paths()?.data)?;
+        for page in &pages {
+            store.save(page)?;
+        }
+    }
+    if let Some(template) = template {
+        for page in &pages {
+            print!("{}", template.render(page)?);
+        }
+        return Ok(());
+    }
+    if let Some(analysis) = args.analyze {
+        print!("{}", analysis_report(analysis, &pages, args)?);
+        return Ok(());
+    }
+    print!(
+        "{}",
+        format_responses(&pages, args.style, args.format, &args.shape())?
+    );
+    Ok(())
+}
+
+/// The `--analyze` report of pages
+fn analysis_report(analysis: Analysis, pages: &[ScrapedWebpage], args: &Args) -> Result<String> {
+    analysis::report(
+        analysis,
+        pages,
+        args.top,
+        args.min_similarity,
+        args.format,
+        &args.shape(),
+    )
+}
+
+/// Print the json lines of each page as soon as its url completes, saving it on the way
+async fn stream_batch(
+    args: &Args,
+    inputs: &[(String, Option<String>)],
+    urls: &[String],
+    options: &ScrapOptions,
+) -> Result<()> {
+    let store = match args.save {
+        true => Some(Store::open(&args.paths()?.data)?),
+        false => None,
+    };
+    let shape = args.shape();
+    let mut failure = None;
+    let mut scraped = 0;
+    batch::grab_each(urls, options, None, args.concurrency, |index, outcome| {
+        let Some(page) = batch_page(outcome, &inputs[index].1) else {
+            return;
+        };
+        scraped += 1;
+        if failure.is_none() {
+            failure = stream_page(&page, args, store.as_ref(), &shape).err();
+        }
+    })
+    .await;
+    print_summary(urls.len(), scraped, options);
+    failure.map_or(Ok(()), Err)
+}
+
+/// Scraped pages of a batch and lookups of the dns cache, on stderr
+fn print_summary(urls: usize, scraped: usize, options: &ScrapOptions) {
+    let mut summary = format!("{} of {} urls scraped", scraped, urls);
+    if let Some(dns_cache) = &options.dns_cache {
+        let stats = dns_cache.stats();
+        summary.push_str(&format!(
+            ", dns cache: {} hits, {} lookups of {} hosts",
+            stats.hits, stats.misses, stats.hosts
+        ));
+    }
+    eprintln!("{}", summary);
+}
+
+fn stream_page(
+    page: &ScrapedWebpage,
+    args: &Args,
+    store: Option<&Store>,
+    shape: &Shape,
+) -> Result<()> {
+    if let Some(filter) = &args.filter {
+        if !filter.matches(&serde_json::to_value(page)?) {
+            return Ok(());
+        }
+    }
+    if let Some(store) = store {
+        store.save(page)?;
+    }
+    print!(
+        "{}",
+        format_jsonl(std::slice::from_ref(page), args.style, shape)?
+    );
+    Ok(())
+}
+
+/// The page of a batch outcome, with the source of its url; other outcomes are reported
+fn batch_page(outcome: batch::Outcome, source: &Option<String>) -> Option<ScrapedWebpage> {
+    let origin = match source {
+        Some(source) => format!("{}: {}", source, outcome.url),
+        None => outcome.url.clone(),
+    };
+    match outcome.result {
+        Ok(Scraped::Webpage(mut page)) => {
+            if source.is_some() {
+                eprintln!("{}: ok", origin);
+            }
+            page.source = source.clone();
+            Some(*page)
+        }
+        Ok(_) => {
+            eprintln!("{}: not an html page, skipped", origin);
+            None
+        }
+        Err(e) => {
+            eprintln!("{}: {}", origin, e);
+            None
+        }
+    }
+}
+
+/// Download the favicons of pages once into the cache, the icons of script filter items
+async fn cache_favicons(pages: &mut [ScrapedWebpage], paths: &Paths, options: &ScrapOptions) {
+    for page in pages.iter_mut().filter(|page| page.favicon_file.is_none()) {
+        let Some(favicon) = &page.favicon else {
+            continue;
+        };
+        match download::cached_favicon(favicon, &paths.favicons(), options).await {
+            Ok(path) => page.favicon_file = Some(path.display().to_string()),
+            Err(e) => eprintln!("No favicon downloaded: {}", e),
+        }
+    }
+}
+
+fn run_secret_command(command: &SecretCommand) -> Result<()> {
+    match command {
+        SecretCommand::Set { name } => {
+            let mut value = String::new();
+            std::io::stdin().read_line(&mut value)?;
+            secret::set_secret(name, value.trim_end_matches(['\r', '\n']))?;
+        }
+        SecretCommand::Delete { name } => secret::delete_secret(name)?,
+    }
+    Ok(())
+}
+
+fn run_profile_command(command: &ProfileCommand, paths: &Paths) -> Result<()> {
+    match command {
+        ProfileCommand::List => {
+            config::read_config(paths)?
+                .profile
+                .keys()
+                .for_each(|name| println!("{}", name));
+            config::encrypted_profiles(paths)?
+                .iter()
+                .for_each(|name| println!("{} (encrypted)", name));
+        }
+        ProfileCommand::Encrypt { file, name } => {
+            let path = config::encrypt_profile(paths, file, name)?;
+            println!("{}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn run_db_command(command: &DbCommand, paths: &Paths) -> Result<()> {
+    match command {
+        DbCommand::Migrate { dry_run } => {
+            let store = Store::open_without_migrations(&paths.data)?;
+            let migrations = if *dry_run {
+                store.pending_migrations()?
+            } else {
+                store.migrate()?
+            };
+            for migration in &migrations {
+                println!("{} {}", migration.version, migration.description);
+            }
+            println!("schema version {}", store.version()?);
+        }
+        DbCommand::Export { file } => {
+            let archive = Store::open(&paths.data)?.export()?;
+            store::write_archive(&archive, file)?;
+            println!("{} links exported", archive.links.len());
+        }
+        DbCommand::Import { file, on_conflict } => {
+            let archive = store::read_archive(file)?;
+            let report = Store::open(&paths.data)?.import(&archive, *on_conflict)?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        DbCommand::Prune { dry_run } => {
+            let retention = config::read_config(paths)?
+                .retention
+                .ok_or(anyhow!("No [retention] policy in config.toml"))?;
+            let report = Store::open(&paths.data)?.prune_snapshots(&retention, *dry_run)?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(())
+}
+
+/// Clean the urls of stdin as they come, invalid ones reported on stderr
+async fn clean_urls(expand: bool, options: &ScrapOptions) -> Result<()> {
+    let client = cleanup::expand_client(options)?;
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut url = match cleanup::normalize_url(line) {
+            Ok(url) => cleanup::clean_url(&url),
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        if expand && cleanup::is_shortener(&url) {
+            match cleanup::expand_url(&client, &url, options).await {
+                Ok(target) => url = cleanup::clean_url(&target),
+                Err(e) => eprintln!("Cannot expand {}: {}", url, e),
+            }
+        }
+        println!("{}", url);
+    }
+    Ok(())
+}
+
+fn run_cache_command(command: &CacheCommand, cache: &HttpCache) -> Result<()> {
+    match command {
+        CacheCommand::Stats => println!("{}", serde_json::to_string_pretty(&cache.stats()?)?),
+        CacheCommand::Clear => println!("{} entries removed", cache.clear()?),
+        CacheCommand::Prune { older_than } => {
+            let expired = match older_than {
+                Some(age) => cache.prune(*age)?,
+                None => 0,
+            };
+            let evicted = cache.evict()?;
+            println!("{} entries removed", expired + evicted);
+        }
+    }
+    Ok(())
+}
+
+/// One value per line, strings unquoted so they can be piped
+fn print_plucked(value: &Value, pluck: &PluckPath) {
+    for selected in pluck.select(value) {
+        match selected {
+            Value::String(text) => println!("{}", text),
+            other => println!("{}", other),
+        }
+    }
+}
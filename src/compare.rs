@@ -0,0 +1,150 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use scraper::Html;
+use serde::Serialize;
+
+use crate::batch::Outcome;
+use crate::format::{format_json, format_json_line, text_line, Format, Shape};
+use crate::scrap::Scraped;
+use crate::structured;
+
+/// Open graph properties whose presence is compared
+const OPEN_GRAPH: [&str; 4] = ["og:title", "og:description", "og:image", "og:type"];
+
+/// Key fields of one compared url
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub url: String,
+    pub status: Option<u16>,
+    pub title: Option<String>,
+    pub description_length: Option<usize>,
+    pub language: Option<String>,
+    /// Open graph properties present on the page
+    pub open_graph: Vec<String>,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn compare(outcomes: &[Outcome]) -> Vec<Comparison> {
+    outcomes
+        .iter()
+        .map(|outcome| {
+            let mut comparison = Comparison {
+                url: outcome.url.clone(),
+                status: None,
+                title: None,
+                description_length: None,
+                language: None,
+                open_graph: vec![],
+                latency_ms: outcome.latency.as_millis(),
+                error: None,
+            };
+            match &outcome.result {
+                Ok(Scraped::Webpage(page)) => {
+                    let document = Html::parse_document(&page.html);
+                    comparison.status = Some(page.status);
+                    comparison.title = Some(page.title.clone());
+                    comparison.description_length = page
+                        .description
+                        .as_ref()
+                        .map(|description| description.chars().count());
+                    comparison.language = page.language.clone();
+                    comparison.open_graph = OPEN_GRAPH
+                        .iter()
+                        .filter(|property| structured::meta_content(&document, property).is_some())
+                        .map(|property| property.to_string())
+                        .collect();
+                }
+                Ok(_) => comparison.error = Some("not an html page".to_string()),
+                Err(e) => comparison.error = Some(e.to_string()),
+            }
+            comparison
+        })
+        .collect()
+}
+
+/// A table with a column per url, or a json array
+pub fn format_comparisons(
+    comparisons: &[Comparison],
+    format: Format,
+    shape: &Shape,
+) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => {
+            writeln!(output, "{}", format_json(&comparisons, shape)?)?;
+            return Ok(output);
+        }
+        Format::Jsonl => {
+            for comparison in comparisons {
+                writeln!(output, "{}", format_json_line(comparison, shape)?)?;
+            }
+            return Ok(output);
+        }
+        Format::Ics => bail!("--format ics applies to event pages"),
+        Format::Html => bail!("--format html applies to the full, link and links styles"),
+        Format::ScriptFilter => {
+            bail!("--format script-filter applies to the full, link and links styles")
+        }
+        Format::Org => bail!("--format org applies to the full, link and links styles"),
+        Format::Bibtex | Format::CslJson => {
+            bail!("--format bibtex and csl-json apply to the full and link styles")
+        }
+        Format::Markdown | Format::Text => {}
+    }
+    let row = |output: &mut String, name: &str, cell: &dyn Fn(&Comparison) -> String| {
+        let cells: Vec<String> = comparisons.iter().map(cell).collect();
+        if format == Format::Text {
+            let mut fields = vec![name];
+            fields.extend(cells.iter().map(String::as_str));
+            return writeln!(output, "{}", text_line(&fields));
+        }
+        let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        writeln!(output, "| {} | {} |", name, cells.join(" | "))
+    };
+    let optional = |value: Option<String>| value.unwrap_or("-".to_string());
+    row(&mut output, "", &|comparison| comparison.url.clone())?;
+    if format == Format::Markdown {
+        writeln!(output, "| --- |{}", " --- |".repeat(comparisons.len()))?;
+    }
+    row(&mut output, "status", &|comparison| {
+        optional(comparison.status.map(|status| status.to_string()))
+    })?;
+    row(&mut output, "title", &|comparison| {
+        optional(comparison.title.clone())
+    })?;
+    row(&mut output, "description length", &|comparison| {
+        optional(
+            comparison
+                .description_length
+                .map(|length| length.to_string()),
+        )
+    })?;
+    row(&mut output, "language", &|comparison| {
+        optional(comparison.language.clone())
+    })?;
+    for property in OPEN_GRAPH {
+        row(&mut output, property, &|comparison| {
+            let present = comparison.open_graph.iter().any(|known| known == property);
+            match (&comparison.error, present) {
+                (Some(_), _) => "-".to_string(),
+                (None, true) => "yes".to_string(),
+                (None, false) => "no".to_string(),
+            }
+        })?;
+    }
+    row(&mut output, "latency", &|comparison| {
+        format!("{} ms", comparison.latency_ms)
+    })?;
+    if comparisons
+        .iter()
+        .any(|comparison| comparison.error.is_some())
+    {
+        row(&mut output, "error", &|comparison| {
+            optional(comparison.error.clone())
+        })?;
+    }
+    Ok(output)
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use age::secrecy::SecretString;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::daemon::Job;
+use crate::lint::LintRules;
+use crate::notify::{GotifyConfig, MatrixConfig, SmtpConfig};
+use crate::paths::Paths;
+use crate::scrap::ScrapOptions;
+use crate::secret::resolve_secrets;
+use crate::store::Retention;
+use crate::sync::SyncConfig;
+
+const PASSPHRASE_VAR: &str = "SCRAPR_PASSPHRASE";
+
+/// `config.toml` in the scrapr config directory (see `scrapr paths`)
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub profile: BTreeMap<String, Profile>,
+    pub sync: Option<SyncConfig>,
+    #[serde(default)]
+    pub job: BTreeMap<String, Job>,
+    pub smtp: Option<SmtpConfig>,
+    pub matrix: Option<MatrixConfig>,
+    pub gotify: Option<GotifyConfig>,
+    /// Rules of `scrapr lint`
+    pub lint: Option<LintRules>,
+    /// Snapshots kept by `scrapr db prune`
+    pub retention: Option<Retention>,
+}
+
+/// Named client defaults, selected with `--profile`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub user_agent: Option<String>,
+    pub accept: Option<String>,
+    pub timeout_secs: Option<u64>,
+    /// Value of the Cookie header
+    pub cookies: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Profile {
+    /// Set the client defaults, cookies and headers resolving their `{{secret:NAME}}`
+    pub fn apply(&self, options: &mut ScrapOptions) -> Result<()> {
+        if let Some(user_agent) = &self.user_agent {
+            options.user_agent = user_agent.clone();
+        }
+        if let Some(accept) = &self.accept {
+            options.accept = accept.clone();
+        }
+        if let Some(timeout_secs) = self.timeout_secs {
+            options.timeout = Duration::from_secs(timeout_secs);
+        }
+        if let Some(cookies) = &self.cookies {
+            options
+                .headers
+                .push(("cookie".to_string(), resolve_secrets(cookies)?));
+        }
+        for (name, value) in &self.headers {
+            options
+                .headers
+                .push((name.clone(), resolve_secrets(value)?));
+        }
+        Ok(())
+    }
+}
+
+pub fn read_config(paths: &Paths) -> Result<Config> {
+    let path = paths.config_file();
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Profile from `config.toml`, or else from `profiles/NAME.toml.age` decrypted
+/// with the `SCRAPR_PASSPHRASE` passphrase
+pub fn load_profile(paths: &Paths, name: &str) -> Result<Profile> {
+    if let Some(profile) = read_config(paths)?.profile.remove(name) {
+        return Ok(profile);
+    }
+    let path = paths.profiles().join(format!("{}.toml.age", name));
+    if !path.exists() {
+        return Err(anyhow!("Profile {} not found", name));
+    }
+    let identity = age::scrypt::Identity::new(passphrase()?);
+    let content = age::decrypt(&identity, &std::fs::read(&path)?)
+        .map_err(|e| anyhow!("Cannot decrypt profile {}: {}", name, e))?;
+    Ok(toml::from_str(std::str::from_utf8(&content)?)?)
+}
+
+pub fn encrypted_profiles(paths: &Paths) -> Result<Vec<String>> {
+    let dir = paths.profiles();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut names = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(name) = file_name.strip_suffix(".toml.age") {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Encrypt a plain profile file into the profiles directory, returning its new path
+pub fn encrypt_profile(paths: &Paths, file: &str, name: &str) -> Result<PathBuf> {
+    let content = std::fs::read_to_string(file)?;
+    // refuse to encrypt something which would not load
+    toml::from_str::<Profile>(&content)?;
+    let recipient = age::scrypt::Recipient::new(passphrase()?);
+    let encrypted = age::encrypt(&recipient, content.as_bytes())?;
+    let dir = paths.profiles();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.toml.age", name));
+    std::fs::write(&path, encrypted)?;
+    Ok(path)
+}
+
+fn passphrase() -> Result<SecretString> {
+    let passphrase = std::env::var(PASSPHRASE_VAR)
+        .map_err(|_| anyhow!("{} is required for encrypted profiles", PASSPHRASE_VAR))?;
+    Ok(SecretString::from(resolve_secrets(&passphrase)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Profile;
+    use crate::scrap::ScrapOptions;
+
+    #[test]
+    fn profile_secrets_are_resolved_not_sent() {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let plain = Profile {
+            cookies: Some("lang=en".to_string()),
+            headers: BTreeMap::from([("x-team".to_string(), "news".to_string())]),
+            ..Default::default()
+        };
+        let mut options = ScrapOptions::default();
+        plain.apply(&mut options).unwrap();
+        assert_eq!(
+            options.headers,
+            [
+                ("cookie".to_string(), "lang=en".to_string()),
+                ("x-team".to_string(), "news".to_string())
+            ]
+        );
+
+        let missing = Profile {
+            headers: BTreeMap::from([(
+                "authorization".to_string(),
+                "Bearer {{secret:TOKEN}}".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let error = missing.apply(&mut ScrapOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("Secret TOKEN not found"));
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Deserializer};
+use tokio::time::{sleep_until, Instant};
+
+use crate::batch::{self, CircuitBreaker};
+use crate::config::{self, Config};
+use crate::domains::{self, DomainStats};
+use crate::filter::Filter;
+use crate::format::{format_domains, format_response, Format, Shape, Style};
+use crate::notify::{self, notify_all, Notification};
+use crate::paths::Paths;
+use crate::scrap::{ScrapOptions, Scraped};
+use crate::service::{self, Signal, Signals};
+use crate::stamp::content_hash;
+use crate::store::Store;
+use crate::watch;
+
+/// A scheduled scrape, `[job.NAME]` in `config.toml`
+///
+/// ```toml
+/// [job.hn]
+/// url_list = "hn.txt"
+/// every = "6h"
+/// format = "jsonl"
+/// output = "hn.jsonl"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// File of urls, one per line, `#` comments; relative to the config directory
+    pub url_list: PathBuf,
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub every: Duration,
+    #[serde(default)]
+    pub format: JobFormat,
+    /// `full` records, or `domains` stats of the urls
+    #[serde(default = "default_style")]
+    pub style: Style,
+    /// Output file, relative to the data directory; json is rewritten, others appended
+    pub output: PathBuf,
+    /// Client profile used by the job requests
+    pub profile: Option<String>,
+    /// Notifiers told about job failures, see `notify::notifiers`
+    #[serde(default)]
+    pub notify: Vec<String>,
+    /// Only keep records matching this expression, see `Filter`
+    #[serde(default, deserialize_with = "deserialize_filter")]
+    pub filter: Option<Filter>,
+    /// Only keep records matching this expression over the page and its
+    /// `<field>_changed` flags since the previous run, see `watch::with_changes`;
+    /// pages are saved to the link store to compare runs
+    #[serde(default, deserialize_with = "deserialize_filter")]
+    pub emit_when: Option<Filter>,
+    /// Also notify the records count of successful runs
+    #[serde(default)]
+    pub notify_results: bool,
+    /// Failures in a row after which the remaining urls of a host are skipped
+    pub circuit_breaker: Option<usize>,
+    /// How long a host is skipped once its circuit is open
+    #[serde(
+        default = "default_circuit_cooldown",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub circuit_cooldown: Duration,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobFormat {
+    Markdown,
+    Json,
+    #[default]
+    Jsonl,
+}
+
+fn default_style() -> Style {
+    Style::Full
+}
+
+fn default_circuit_cooldown() -> Duration {
+    Duration::from_secs(600)
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    humantime::parse_duration(&text).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_filter<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Filter>, D::Error> {
+    let expression = String::deserialize(deserializer)?;
+    Filter::parse(&expression)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Run the configured jobs at their interval, all of them at startup; `once` exits after it
+///
+/// SIGHUP reloads the jobs from `config.toml`, SIGTERM and SIGINT stop once the running job
+/// is done, before the next due one; readiness and state changes are sent to systemd when
+/// `NOTIFY_SOCKET` is set.
+pub async fn run_daemon(paths: &Paths, options: &ScrapOptions, once: bool) -> Result<()> {
+    let mut config = read_jobs(paths)?;
+    let mut next_runs: BTreeMap<String, Instant> = BTreeMap::new();
+    let mut signals = Signals::new()?;
+    service::notify("READY=1");
+    loop {
+        let mut reload = false;
+        for (name, job) in &config.job {
+            let next_run = *next_runs.entry(name.clone()).or_insert_with(Instant::now);
+            if next_run > Instant::now() {
+                continue;
+            }
+            // signals received during the previous job
+            match signals.pending().await {
+                Some(Signal::Shutdown) => {
+                    shut_down();
+                    return Ok(());
+                }
+                Some(Signal::Reload) => reload = true,
+                None => {}
+            }
+            // a failing job is reported and retried at its next interval
+            let notification = match run_job(paths, name, job, options).await {
+                Ok(count) => {
+                    eprintln!("job {}: {} records", name, count);
+                    job.notify_results.then(|| Notification {
+                        title: format!("job {} done", name),
+                        body: format!("{} records written to {}", count, job.output.display()),
+                        url: job.url_list.display().to_string(),
+                    })
+                }
+                Err(e) => {
+                    eprintln!("job {} failed: {}", name, e);
+                    Some(Notification {
+                        title: format!("job {} failed", name),
+                        body: e.to_string(),
+                        url: job.url_list.display().to_string(),
+                    })
+                }
+            };
+            if let Some(notification) = notification {
+                match notify::notifiers(&job.notify, &config) {
+                    Ok(notifiers) => notify_all(&notifiers, &notification, options).await,
+                    Err(e) => eprintln!("job {}: {}", name, e),
+                }
+            }
+            next_runs.insert(name.clone(), Instant::now() + job.every);
+        }
+        if once {
+            return Ok(());
+        }
+        let next_run = next_runs
+            .values()
+            .min()
+            .copied()
+            .unwrap_or_else(Instant::now);
+        let signal = match reload {
+            true => Signal::Reload,
+            false => tokio::select! {
+                _ = sleep_until(next_run) => continue,
+                signal = signals.recv() => signal,
+            },
+        };
+        match signal {
+            Signal::Reload => {
+                service::notify("RELOADING=1");
+                // a broken config keeps the running jobs
+                match read_jobs(paths) {
+                    Ok(reloaded) => {
+                        next_runs.retain(|name, _| reloaded.job.contains_key(name));
+                        config = reloaded;
+                        eprintln!("reloaded {} jobs", config.job.len());
+                    }
+                    Err(e) => eprintln!("reload failed, keeping jobs: {}", e),
+                }
+                service::notify("READY=1");
+            }
+            Signal::Shutdown => {
+                shut_down();
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn shut_down() {
+    service::notify("STOPPING=1");
+    eprintln!("shutting down");
+}
+
+/// Config with at least one job
+fn read_jobs(paths: &Paths) -> Result<Config> {
+    let config = config::read_config(paths)?;
+    if config.job.is_empty() {
+        return Err(anyhow!(
+            "No [job.NAME] in {}",
+            paths.config_file().display()
+        ));
+    }
+    Ok(config)
+}
+
+/// Scrape the job urls and write the records, returning their count
+pub async fn run_job(
+    paths: &Paths,
+    name: &str,
+    job: &Job,
+    options: &ScrapOptions,
+) -> Result<usize> {
+    let mut options = options.clone();
+    if let Some(profile) = &job.profile {
+        config::load_profile(paths, profile)?.apply(&mut options)?;
+    }
+    let urls = read_url_list(&paths.config.join(&job.url_list))?;
+    let breaker = job.circuit_breaker.map(|threshold| CircuitBreaker {
+        threshold,
+        cooldown: job.circuit_cooldown,
+    });
+    let outcomes = batch::grab_all(&urls, &options, breaker.as_ref(), 1).await;
+    let store = Store::open(&paths.data)?;
+    batch::record_failures(&store, &outcomes, &format!("job:{}", name))?;
+    batch::record_latencies(&store, &outcomes)?;
+    let output = paths.data.join(&job.output);
+    if job.style == Style::Domains {
+        let stats = domains::domain_stats(&outcomes);
+        write_domain_stats(&output, &stats, job.format)?;
+        return Ok(stats.len());
+    }
+    let mut records = vec![];
+    for outcome in outcomes {
+        match outcome.result {
+            Ok(scraped) => {
+                let matches = match &job.filter {
+                    Some(filter) => filter.matches(&scraped.to_value()?),
+                    None => true,
+                };
+                let emit = match &job.emit_when {
+                    Some(emit_when) => emit_when.matches(&record_changes(&store, &scraped)?),
+                    None => true,
+                };
+                if matches && emit {
+                    records.push(scraped);
+                }
+            }
+            Err(e) => eprintln!("{}: {}", outcome.url, e),
+        }
+    }
+    write_records(&output, &records, job.format)?;
+    Ok(records.len())
+}
+
+/// A record with its changes since the latest stored snapshot of the page, saved
+/// as the next one
+fn record_changes(store: &Store, scraped: &Scraped) -> Result<serde_json::Value> {
+    let Scraped::Webpage(page) = scraped else {
+        return Ok(watch::with_changes(scraped.to_value()?, &[]));
+    };
+    let previous = store
+        .url_snapshots(&page.url)?
+        .and_then(|mut snapshots| snapshots.pop());
+    let mut changed = vec![];
+    if let Some(previous) = previous {
+        let fields = [
+            ("status", previous.status != page.status),
+            ("title", previous.title != page.title),
+            ("description", previous.description != page.description),
+            ("content", previous.content_hash != content_hash(&page.html)),
+        ];
+        changed = fields
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(field, _)| field)
+            .collect();
+    }
+    store.save(page)?;
+    Ok(watch::with_changes(scraped.to_value()?, &changed))
+}
+
+/// Non blank, non comment lines of a file
+pub fn read_url_list(path: &Path) -> Result<Vec<String>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Cannot read url list {}: {}", path.display(), e))?;
+    batch::read_urls(std::io::BufReader::new(file))
+}
+
+fn write_domain_stats(path: &Path, stats: &[DomainStats], format: JobFormat) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if format == JobFormat::Json {
+        std::fs::write(path, format_domains(stats, Format::Json)?)?;
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    match format {
+        JobFormat::Markdown => write!(file, "{}", format_domains(stats, Format::Markdown)?)?,
+        _ => {
+            for stat in stats {
+                writeln!(file, "{}", serde_json::to_string(stat)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_records(path: &Path, records: &[Scraped], format: JobFormat) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if format == JobFormat::Json {
+        let values = records
+            .iter()
+            .map(Scraped::to_value)
+            .collect::<Result<Vec<_>>>()?;
+        std::fs::write(path, serde_json::to_string_pretty(&values)?)?;
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for record in records {
+        match (format, record) {
+            (JobFormat::Markdown, Scraped::Webpage(page)) => writeln!(
+                file,
+                "{}",
+                format_response(page, Style::Full, Format::Markdown, &Shape::default())?.trim_end()
+            )?,
+            // json and xml documents have no markdown rendering
+            (JobFormat::Markdown, _) => {}
+            _ => writeln!(file, "{}", serde_json::to_string(&record.to_value()?)?)?,
+        }
+    }
+    Ok(())
+}
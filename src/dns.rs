@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use hickory_resolver::TokioAsyncResolver;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Serialize;
+
+/// DNS lookups of a run, kept for the TTL of their records
+pub struct DnsCache {
+    resolver: TokioAsyncResolver,
+    /// Lifetime of the entries instead of the TTLs, `--dns-cache-ttl`
+    ttl: Option<Duration>,
+    entries: Mutex<HashMap<String, Entry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct Entry {
+    addresses: Vec<IpAddr>,
+    expires_at: Instant,
+}
+
+/// Lookups answered from the cache, and those sent to the resolver
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DnsStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hosts: usize,
+}
+
+impl DnsCache {
+    /// A cache in front of the resolvers of the system configuration
+    pub fn from_system_conf(ttl: Option<Duration>) -> Result<DnsCache> {
+        Ok(DnsCache {
+            resolver: TokioAsyncResolver::tokio_from_system_conf()?,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// Addresses of a host, looked up again once expired; failures are not cached
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let cached = self
+            .entries
+            .lock()
+            .expect("dns cache lock")
+            .get(host)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.addresses.clone());
+        if let Some(addresses) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(addresses);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let lookup = self.resolver.lookup_ip(host).await?;
+        let addresses: Vec<IpAddr> = lookup.iter().collect();
+        let expires_at = match self.ttl {
+            Some(ttl) => Instant::now() + ttl,
+            None => lookup.valid_until(),
+        };
+        self.entries.lock().expect("dns cache lock").insert(
+            host.to_string(),
+            Entry {
+                addresses: addresses.clone(),
+                expires_at,
+            },
+        );
+        Ok(addresses)
+    }
+
+    pub fn stats(&self) -> DnsStats {
+        DnsStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            hosts: self.entries.lock().expect("dns cache lock").len(),
+        }
+    }
+}
+
+impl fmt::Debug for DnsCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsCache")
+            .field("ttl", &self.ttl)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// The resolver of the http clients, through the cache
+pub struct CachedResolver(pub Arc<DnsCache>);
+
+impl Resolve for CachedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.0.clone();
+        Box::pin(async move {
+            let addresses = cache.lookup(name.as_str()).await?;
+            // the port is set by the connector
+            let addresses: Addrs = Box::new(
+                addresses
+                    .into_iter()
+                    .map(|address| SocketAddr::new(address, 0)),
+            );
+            Ok(addresses)
+        })
+    }
+}
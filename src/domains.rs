@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use publicsuffix::{List, Psl};
+use reqwest::Client;
+use serde::Serialize;
+use url::{Host, Url};
+
+use crate::batch::Outcome;
+use crate::scrap::{with_network, ScrapOptions};
+
+const PUBLIC_SUFFIX_URL: &str = "https://publicsuffix.org/list/public_suffix_list.dat";
+const PUBLIC_SUFFIX_FILE: &str = "public_suffix_list.dat";
+
+/// Public suffix list: the one downloaded with `scrapr psl update`, or else the one
+/// embedded at build time
+static PUBLIC_SUFFIXES: OnceLock<List> = OnceLock::new();
+
+fn public_suffixes() -> &'static List {
+    PUBLIC_SUFFIXES.get_or_init(|| {
+        include_str!("../data/public_suffix_list.dat")
+            .parse()
+            .expect("embedded public suffix list")
+    })
+}
+
+/// Use the updated list of a data directory, if any; called before any lookup.
+/// An unusable list is reported and the embedded one kept.
+pub fn load_public_suffixes(data_dir: &Path) -> Result<()> {
+    let path = data_dir.join(PUBLIC_SUFFIX_FILE);
+    if path.exists() {
+        match validate_public_suffixes(&std::fs::read_to_string(&path)?) {
+            Ok(list) => {
+                let _ = PUBLIC_SUFFIXES.set(list);
+            }
+            Err(e) => eprintln!(
+                "Invalid public suffix list {}, using the embedded one: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Download the current list into a data directory, returning its path; fetched with
+/// the user agent, timeout, proxy and dns cache of `options`
+pub async fn update_public_suffixes(data_dir: &Path, options: &ScrapOptions) -> Result<PathBuf> {
+    let client = Client::builder()
+        .user_agent(&options.user_agent)
+        .timeout(options.timeout);
+    let content = with_network(client, options)
+        .build()?
+        .get(PUBLIC_SUFFIX_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    // refuse to replace the list with something which would not work, like an error page
+    validate_public_suffixes(&content).map_err(|e| {
+        anyhow!(
+            "Invalid public suffix list from {}: {}",
+            PUBLIC_SUFFIX_URL,
+            e
+        )
+    })?;
+    std::fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(PUBLIC_SUFFIX_FILE);
+    // renamed into place so an interrupted write keeps the previous list
+    let partial = path.with_extension("dat.part");
+    std::fs::write(&partial, content)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// List parsed from `content` when it has both sections and knows well established suffixes
+fn validate_public_suffixes(content: &str) -> Result<List> {
+    for marker in ["===BEGIN ICANN DOMAINS===", "===BEGIN PRIVATE DOMAINS==="] {
+        if !content.contains(marker) {
+            return Err(anyhow!("no {} section", marker));
+        }
+    }
+    let list: List = content.parse().map_err(|e| anyhow!("{}", e))?;
+    for (host, expected) in [
+        ("www.example.com", "example.com"),
+        ("www.example.co.uk", "example.co.uk"),
+        ("me.github.io", "me.github.io"),
+    ] {
+        let domain = list.domain(host.as_bytes());
+        if domain.is_none_or(|domain| domain.as_bytes() != expected.as_bytes()) {
+            return Err(anyhow!("{} is not registrable as {}", host, expected));
+        }
+    }
+    Ok(list)
+}
+
+/// Domain a user can register for a host (`www.example.co.uk` gives `example.co.uk`)
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = host.to_lowercase();
+    let domain = public_suffixes().domain(host.as_bytes())?;
+    String::from_utf8(domain.as_bytes().to_vec()).ok()
+}
+
+/// Host and registrable domain of an url, none for relative urls and ips
+pub fn domain_fields(url: &str) -> (Option<String>, Option<String>) {
+    let Ok(url) = Url::parse(url) else {
+        return (None, None);
+    };
+    match url.host() {
+        Some(Host::Domain(host)) => (Some(host.to_string()), registrable_domain(host)),
+        Some(ip) => (Some(ip.to_string()), None),
+        None => (None, None),
+    }
+}
+
+/// Registrable domain of an url, or its host for ips and unknown suffixes
+pub fn url_domain(url: &str) -> Option<String> {
+    let (host, registrable) = domain_fields(url);
+    registrable.or(host)
+}
+
+/// How a batch of urls spreads over domains
+#[derive(Debug, Serialize)]
+pub struct DomainStats {
+    pub domain: String,
+    pub urls: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub average_latency_ms: u128,
+}
+
+/// Stats per registrable domain, most linked first
+pub fn domain_stats(outcomes: &[Outcome]) -> Vec<DomainStats> {
+    let mut by_domain: BTreeMap<String, Vec<&Outcome>> = BTreeMap::new();
+    for outcome in outcomes {
+        let domain = url_domain(&outcome.url).unwrap_or(outcome.url.clone());
+        by_domain.entry(domain).or_default().push(outcome);
+    }
+    let mut stats: Vec<DomainStats> = by_domain
+        .into_iter()
+        .map(|(domain, outcomes)| {
+            let errors = outcomes.iter().filter(|o| o.result.is_err()).count();
+            let total_latency: u128 = outcomes.iter().map(|o| o.latency.as_millis()).sum();
+            DomainStats {
+                domain,
+                urls: outcomes.len(),
+                errors,
+                error_rate: errors as f64 / outcomes.len() as f64,
+                average_latency_ms: total_latency / outcomes.len() as u128,
+            }
+        })
+        .collect();
+    stats.sort_by_key(|stat| std::cmp::Reverse(stat.urls));
+    stats
+}
@@ -0,0 +1,82 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use reqwest::{header, Client};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+use crate::scrap::{with_network, ScrapOptions};
+use crate::stamp::content_hash;
+
+/// Save a file into `directory` under the last segment of its url path, through the
+/// bandwidth limit; no timeout as media files may be large
+pub async fn download_file(url: &str, directory: &Path, options: &ScrapOptions) -> Result<PathBuf> {
+    let name = Url::parse(url)?
+        .path_segments()
+        .and_then(|mut segments| segments.next_back().map(str::to_string))
+        .filter(|name| !name.is_empty() && name != "." && name != "..")
+        .ok_or(anyhow!("No file name in {}", url))?;
+    let path = directory.join(name);
+    fetch_file(url, &path, options).await?;
+    Ok(path)
+}
+
+/// Favicon of a page in `directory`, named after the hash of its url, downloaded once
+pub async fn cached_favicon(
+    url: &str,
+    directory: &Path,
+    options: &ScrapOptions,
+) -> Result<PathBuf> {
+    let extension = Path::new(Url::parse(url)?.path())
+        .extension()
+        .and_then(OsStr::to_str)
+        .filter(|extension| extension.len() <= 4)
+        .unwrap_or("ico")
+        .to_lowercase();
+    let name = format!("{}.{}", &content_hash(url)[..16], extension);
+    let path = directory.join(name);
+    if !path.exists() {
+        fetch_file(url, &path, options).await?;
+    }
+    Ok(path)
+}
+
+/// Stream the body into `path`, written under a `.part` name until complete so an
+/// interrupted download is not mistaken for the file
+async fn fetch_file(url: &str, path: &Path, options: &ScrapOptions) -> Result<()> {
+    let client = with_network(Client::builder(), options).build()?;
+    let mut response = client
+        .get(url)
+        .header(header::USER_AGENT, &options.user_agent)
+        .send()
+        .await?
+        .error_for_status()?;
+    if let Some(directory) = path.parent() {
+        tokio::fs::create_dir_all(directory).await?;
+    }
+    let mut partial_name = path.as_os_str().to_owned();
+    partial_name.push(".part");
+    let partial = PathBuf::from(partial_name);
+    let mut file = File::create(&partial).await?;
+    let written: Result<()> = async {
+        while let Some(chunk) = response.chunk().await? {
+            if let Some(bandwidth) = &options.bandwidth {
+                bandwidth.acquire(chunk.len()).await;
+            }
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+    .await;
+    drop(file);
+    match written {
+        Ok(()) => Ok(tokio::fs::rename(&partial, path).await?),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::format::{format_json, format_json_line, text_line, Format, Shape};
+use crate::scrap::ScrapedWebpage;
+use crate::simhash::similarity;
+
+/// Clusters of near-duplicate pages, the largest first
+#[derive(Debug, Serialize)]
+pub struct DuplicateReport {
+    pub pages: usize,
+    pub clusters: Vec<Cluster>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cluster {
+    /// Lowest similarity of a page to the first one
+    pub similarity: f64,
+    pub pages: Vec<Duplicate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Duplicate {
+    pub url: String,
+    pub simhash: String,
+    /// Similarity to the first page of the cluster, from 0 to 1
+    pub similarity: f64,
+}
+
+/// Pages whose simhashes are at least `min_similarity` alike, clustered with the pages
+/// they are alike to, in the order of the pages; pages without simhash are left out
+pub fn find_duplicates(pages: &[ScrapedWebpage], min_similarity: f64) -> DuplicateReport {
+    let hashed: Vec<(&ScrapedWebpage, &str)> = pages
+        .iter()
+        .filter_map(|page| page.simhash.as_deref().map(|simhash| (page, simhash)))
+        .collect();
+    // union-find over the pairs of similar pages
+    let mut parents: Vec<usize> = (0..hashed.len()).collect();
+    for a in 0..hashed.len() {
+        for b in a + 1..hashed.len() {
+            if similarity(hashed[a].1, hashed[b].1).unwrap_or_default() >= min_similarity {
+                let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                parents[root_a.max(root_b)] = root_a.min(root_b);
+            }
+        }
+    }
+    let mut members: Vec<Vec<usize>> = vec![vec![]; hashed.len()];
+    for index in 0..hashed.len() {
+        let root = root(&mut parents, index);
+        members[root].push(index);
+    }
+    let mut clusters: Vec<Cluster> = members
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let first = hashed[members[0]].1;
+            let pages: Vec<Duplicate> = members
+                .iter()
+                .map(|index| {
+                    let (page, simhash) = hashed[*index];
+                    Duplicate {
+                        url: page.url.clone(),
+                        simhash: simhash.to_string(),
+                        similarity: similarity(first, simhash).unwrap_or_default(),
+                    }
+                })
+                .collect();
+            Cluster {
+                similarity: pages.iter().map(|page| page.similarity).fold(1.0, f64::min),
+                pages,
+            }
+        })
+        .collect();
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.pages.len()));
+    DuplicateReport {
+        pages: pages.len(),
+        clusters,
+    }
+}
+
+fn root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// A list per cluster with the similarity of each page to the first one; a json report,
+/// a json line per cluster, or a text line per page
+pub fn format_duplicates(
+    report: &DuplicateReport,
+    format: Format,
+    shape: &Shape,
+) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(report, shape)?)?,
+        Format::Jsonl => {
+            for cluster in &report.clusters {
+                writeln!(output, "{}", format_json_line(cluster, shape)?)?;
+            }
+        }
+        Format::Text => {
+            for (index, cluster) in report.clusters.iter().enumerate() {
+                for page in &cluster.pages {
+                    let line = text_line(&[
+                        &(index + 1).to_string(),
+                        &page.url,
+                        &page.similarity.to_string(),
+                    ]);
+                    writeln!(output, "{}", line)?;
+                }
+            }
+        }
+        Format::Markdown => {
+            let duplicates: usize = report
+                .clusters
+                .iter()
+                .map(|cluster| cluster.pages.len() - 1)
+                .sum();
+            writeln!(
+                output,
+                "{} near-duplicates of {} pages in {} clusters",
+                duplicates,
+                report.pages,
+                report.clusters.len()
+            )?;
+            for (index, cluster) in report.clusters.iter().enumerate() {
+                writeln!(output)?;
+                writeln!(
+                    output,
+                    "## {}. {} pages, {} similar",
+                    index + 1,
+                    cluster.pages.len(),
+                    percent(cluster.similarity)
+                )?;
+                writeln!(output)?;
+                for page in &cluster.pages {
+                    writeln!(output, "- <{}> {}", page.url, percent(page.similarity))?;
+                }
+            }
+        }
+        _ => bail!("--analyze duplicates applies to the markdown, json, jsonl and text formats"),
+    }
+    Ok(output)
+}
+
+fn percent(similarity: f64) -> String {
+    format!("{:.0}%", similarity * 100.0)
+}
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::task::JoinSet;
+
+use crate::cleanup::clean_url;
+use crate::format::{format_response, Format, Shape, Style};
+use crate::scrap::{grab_url, ScrapOptions, Scraped};
+
+/// A line of `scrapr editor-server` input
+///
+/// ```json
+/// {"id": 1, "url": "https://example.com", "format": "markdown", "style": "link"}
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditorRequest {
+    /// Echoed in the response, to match it with its request
+    #[serde(default)]
+    pub id: Value,
+    pub url: String,
+    #[serde(default = "default_format")]
+    pub format: Format,
+    #[serde(default = "default_style")]
+    pub style: Style,
+    #[serde(default)]
+    pub html_card: bool,
+}
+
+fn default_format() -> Format {
+    Format::Markdown
+}
+
+fn default_style() -> Style {
+    Style::Link
+}
+
+/// A line of output: the snippet or the error of a request
+#[derive(Debug, Serialize)]
+pub struct EditorResponse {
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Answer json requests of stdin, a line each, on stdout; requests run concurrently,
+/// so responses come in the order they complete
+pub async fn serve(options: &ScrapOptions) -> Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    let mut pending = JoinSet::new();
+    let mut open = true;
+    while open || !pending.is_empty() {
+        tokio::select! {
+            line = lines.next_line(), if open => match line? {
+                Some(line) if line.trim().is_empty() => {}
+                Some(line) => {
+                    let options = options.clone();
+                    pending.spawn(async move { answer(&line, &options).await });
+                }
+                None => open = false,
+            },
+            Some(response) = pending.join_next() => {
+                let mut line = serde_json::to_string(&response?)?;
+                line.push('\n');
+                stdout.write_all(line.as_bytes()).await?;
+                stdout.flush().await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn answer(line: &str, options: &ScrapOptions) -> EditorResponse {
+    let request: EditorRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return EditorResponse {
+                id: Value::Null,
+                snippet: None,
+                error: Some(format!("Invalid request: {}", e)),
+            }
+        }
+    };
+    let (snippet, error) = match snippet(&request, options).await {
+        Ok(snippet) => (Some(snippet), None),
+        Err(e) => (None, Some(format!("{:#}", e))),
+    };
+    EditorResponse {
+        id: request.id,
+        snippet,
+        error,
+    }
+}
+
+async fn snippet(request: &EditorRequest, options: &ScrapOptions) -> Result<String> {
+    let url = match options.strip_tracking {
+        true => clean_url(&request.url),
+        false => request.url.clone(),
+    };
+    let Scraped::Webpage(page) = grab_url(&url, options).await? else {
+        return Err(anyhow!("{} is not an html page", url));
+    };
+    let shape = Shape {
+        html_card: request.html_card,
+        ..Shape::default()
+    };
+    let snippet = format_response(&page, request.style, request.format, &shape)?;
+    Ok(snippet.trim_end_matches('\n').to_string())
+}
@@ -0,0 +1,241 @@
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use scraper::Html;
+use serde_json::Value;
+
+use crate::stamp::{content_hash, now};
+use crate::structured::{self, has_type};
+
+/// schema.org Event types
+const EVENT_TYPES: [&str; 14] = [
+    "Event",
+    "BusinessEvent",
+    "ComedyEvent",
+    "DanceEvent",
+    "EducationEvent",
+    "ExhibitionEvent",
+    "Festival",
+    "FoodEvent",
+    "LiteraryEvent",
+    "MusicEvent",
+    "ScreeningEvent",
+    "SocialEvent",
+    "SportsEvent",
+    "TheaterEvent",
+];
+
+/// schema.org Event of a page
+#[derive(Debug)]
+pub struct Event {
+    pub name: String,
+    pub start: String,
+    pub end: Option<String>,
+    pub location: Option<String>,
+    pub url: String,
+    pub description: Option<String>,
+}
+
+/// Events with a start date, the page url standing for missing event urls
+pub fn extract_events(content: &str, page_url: &str) -> Vec<Event> {
+    structured::json_ld_entities(&Html::parse_document(content))
+        .iter()
+        .filter(|entity| EVENT_TYPES.iter().any(|kind| has_type(entity, kind)))
+        .filter_map(|entity| {
+            Some(Event {
+                name: structured::text(entity, "name").unwrap_or("Event".to_string()),
+                start: structured::text(entity, "startDate")?,
+                end: structured::text(entity, "endDate"),
+                location: entity.get("location").and_then(location),
+                url: structured::text(entity, "url").unwrap_or(page_url.to_string()),
+                description: structured::text(entity, "description"),
+            })
+        })
+        .collect()
+}
+
+/// Place name and address, or the url of a virtual location
+fn location(value: &Value) -> Option<String> {
+    let place = match value {
+        Value::Array(places) => places.first()?,
+        place => place,
+    };
+    if let Value::String(text) = place {
+        return Some(text.clone());
+    }
+    let address = match place.get("address") {
+        Some(Value::Object(address)) => [
+            "streetAddress",
+            "postalCode",
+            "addressLocality",
+            "addressCountry",
+        ]
+        .iter()
+        .filter_map(|key| structured::value_text(address.get(*key)?))
+        .collect::<Vec<_>>()
+        .join(" "),
+        Some(address) => structured::value_text(address).unwrap_or_default(),
+        None => String::new(),
+    };
+    let parts: Vec<String> = [structured::text(place, "name"), Some(address)]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return structured::text(place, "url");
+    }
+    Some(parts.join(", "))
+}
+
+/// iCalendar of the events, one VEVENT each
+pub fn format_ics(events: &[Event]) -> Result<String> {
+    let stamp = basic_format(&now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//scrapr//EN".to_string(),
+    ];
+    for event in events {
+        // a VEVENT needs a start
+        let Some(start) = IcsTime::parse(&event.start) else {
+            continue;
+        };
+        let end = event
+            .end
+            .as_deref()
+            .and_then(|end| {
+                // an end time without offset is in the offset of the start
+                match (offset(&event.start), offset(end)) {
+                    (Some(offset), Some("")) => {
+                        IcsTime::parse(&format!("{}{}", end.trim(), offset))
+                    }
+                    _ => IcsTime::parse(end),
+                }
+            })
+            .map(|end| start.end(end));
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}@scrapr",
+            &content_hash(&format!("{} {}", event.url, event.start))[..32]
+        ));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART{}", start.format()));
+        if let Some(end) = end {
+            lines.push(format!("DTEND{}", end.format()));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&event.name)));
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        lines.push(format!("URL:{}", event.url));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    let mut output = String::new();
+    for line in lines {
+        write!(output, "{}\r\n", fold(&line))?;
+    }
+    Ok(output)
+}
+
+/// Start or end of an event in iCalendar
+#[derive(Debug, Clone, Copy)]
+enum IcsTime {
+    /// All day, at midnight UTC
+    Date(SystemTime),
+    /// Datetime with an offset
+    Utc(SystemTime),
+    /// Local time without offset, kept as if UTC
+    Floating(SystemTime),
+}
+
+impl IcsTime {
+    fn parse(text: &str) -> Option<Self> {
+        let time = structured::parse_datetime(text)?;
+        Some(match offset(text) {
+            None => IcsTime::Date(time),
+            Some("") => IcsTime::Floating(time),
+            Some(_) => IcsTime::Utc(time),
+        })
+    }
+
+    /// End of an event starting at `self`, of the same value type: an all day end is
+    /// exclusive, the day after the last one
+    fn end(self, end: IcsTime) -> IcsTime {
+        let next_day = |time: SystemTime| time + DAY;
+        match (self, end) {
+            (IcsTime::Date(_), IcsTime::Date(end)) => IcsTime::Date(next_day(end)),
+            (IcsTime::Date(_), IcsTime::Utc(end) | IcsTime::Floating(end)) => {
+                IcsTime::Date(next_day(start_of_day(end)))
+            }
+            (IcsTime::Utc(_), IcsTime::Date(end)) => IcsTime::Utc(next_day(end)),
+            (IcsTime::Floating(_), IcsTime::Date(end)) => IcsTime::Floating(next_day(end)),
+            (IcsTime::Utc(_), IcsTime::Utc(end) | IcsTime::Floating(end)) => IcsTime::Utc(end),
+            (IcsTime::Floating(_), IcsTime::Utc(end) | IcsTime::Floating(end)) => {
+                IcsTime::Floating(end)
+            }
+        }
+    }
+
+    /// `;VALUE=DATE:20250501`, `:20250501T170000Z` or floating `:20250501T170000`
+    fn format(self) -> String {
+        match self {
+            IcsTime::Date(time) => format!(";VALUE=DATE:{}", &format_utc(time)[..8]),
+            IcsTime::Utc(time) => format!(":{}", format_utc(time)),
+            IcsTime::Floating(time) => format!(":{}", format_utc(time).trim_end_matches('Z')),
+        }
+    }
+}
+
+/// Offset of a datetime, empty for a local time and none for a date
+fn offset(text: &str) -> Option<&str> {
+    let (_, clock) = text.trim().split_once(['T', ' '])?;
+    Some(&clock[clock.find(['Z', '+', '-']).unwrap_or(clock.len())..])
+}
+
+const DAY: Duration = Duration::from_secs(86_400);
+
+fn start_of_day(time: SystemTime) -> SystemTime {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    SystemTime::UNIX_EPOCH + Duration::from_secs(since_epoch - since_epoch % DAY.as_secs())
+}
+
+fn format_utc(time: SystemTime) -> String {
+    basic_format(&humantime::format_rfc3339_seconds(time).to_string())
+}
+
+/// `2025-05-01T17:00:00Z` as `20250501T170000Z`
+fn basic_format(text: &str) -> String {
+    text.replace(['-', ':'], "")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Lines longer than 75 octets continue on lines starting with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
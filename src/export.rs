@@ -0,0 +1,66 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+
+use crate::store::{ArchivedSnapshot, Store};
+use crate::sync::{git, git_with_env};
+
+/// File of the repository holding the page text
+const PAGE_FILE: &str = "page.md";
+
+/// Commit each stored snapshot of `url` in the git repository `dir`, created
+/// when missing, dated by its scrape time. Snapshots not newer than the last
+/// commit are already exported. Returns the number of commits
+pub fn export_git(store: &Store, url: &str, dir: &Path) -> Result<usize> {
+    let snapshots = store
+        .url_snapshots(url)?
+        .ok_or(anyhow!("{} is not in the link store", url))?;
+    std::fs::create_dir_all(dir)?;
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "-q"])?;
+    }
+    let last_commit = last_commit_time(dir);
+    let mut commits = 0;
+    for snapshot in snapshots {
+        let fetched_at = humantime::parse_rfc3339(&snapshot.fetched_at)?;
+        if last_commit.is_some_and(|last_commit| fetched_at <= last_commit) {
+            continue;
+        }
+        std::fs::write(dir.join(PAGE_FILE), page_content(&snapshot))?;
+        git(dir, &["add", PAGE_FILE])?;
+        // an unchanged snapshot makes no commit, other files of the repository aside
+        if git(dir, &["diff", "--cached", "--quiet"]).is_ok() {
+            continue;
+        }
+        let message = format!("Snapshot of {} at {}", url, snapshot.fetched_at);
+        let date = snapshot.fetched_at.as_str();
+        git_with_env(
+            dir,
+            &["commit", "-q", "-m", &message],
+            &[("GIT_AUTHOR_DATE", date), ("GIT_COMMITTER_DATE", date)],
+        )?;
+        commits += 1;
+    }
+    Ok(commits)
+}
+
+/// Commit time of HEAD, none in an empty repository
+fn last_commit_time(dir: &Path) -> Option<SystemTime> {
+    let seconds = git(dir, &["log", "-1", "--format=%ct"]).ok()?;
+    let seconds = seconds.trim().parse().ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+fn page_content(snapshot: &ArchivedSnapshot) -> String {
+    let mut content = format!("# {}\n\n", snapshot.title);
+    if let Some(description) = &snapshot.description {
+        content.push_str(&format!("> {}\n\n", description));
+    }
+    match &snapshot.text {
+        Some(text) => content.push_str(text),
+        None => content.push_str("(text not stored)"),
+    }
+    content.push('\n');
+    content
+}
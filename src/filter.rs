@@ -0,0 +1,288 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FilterError {
+    #[error("Invalid filter {0}: {1}")]
+    InvalidExpression(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(Value),
+    Field(Vec<String>),
+    Operator(Operator),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Field(Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Operator, Box<Expr>, Box<Expr>),
+}
+
+/// Boolean expression evaluated against a result, like
+/// `language == "en" && (status >= 400 || title contains "Rust")`
+///
+/// Fields are result keys, dotted for nested ones (`http.version`), missing ones are null.
+/// A bare field is true unless null, false or an empty string.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, FilterError> {
+        let invalid =
+            |reason: String| FilterError::InvalidExpression(expression.to_string(), reason);
+        let tokens = tokenize(expression).map_err(invalid)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let expr = parser.or().map_err(invalid)?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Self { expr })
+    }
+
+    pub fn matches(&self, value: &Value) -> bool {
+        truthy(&evaluate(&self.expr, value))
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '=' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::Equal),
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::NotEqual),
+            '!' => Token::Not,
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::LessOrEqual),
+            '<' => Token::Operator(Operator::Less),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::GreaterOrEqual),
+            '>' => Token::Operator(Operator::Greater),
+            '"' | '\'' => Token::Literal(Value::String(read_string(&mut chars, c)?)),
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                let number: f64 = number
+                    .parse()
+                    .map_err(|_| format!("invalid number {}", number))?;
+                Token::Literal(Value::from(number))
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+                {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "contains" => Token::Operator(Operator::Contains),
+                    _ => Token::Field(word.split('.').map(str::to_string).collect()),
+                }
+            }
+            c => return Err(format!("unexpected character {}", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn read_string(chars: &mut Peekable<Chars>, quote: char) -> Result<String, String> {
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.push(chars.next().ok_or("unterminated string")?),
+            c if c == quote => return Ok(text),
+            c => text.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+/// Nested `!` and parentheses, deeper expressions overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+/// Recursive descent, from the lowest precedence: `||`, `&&`, `!`, comparisons
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, expected: &Token) -> bool {
+        let found = self.tokens.get(self.position) == Some(expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("nested more than {} times", MAX_DEPTH));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.next_if(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.next_if(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.next_if(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.nested(Self::not)?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.operand()?;
+        if let Some(Token::Operator(operator)) = self.tokens.get(self.position) {
+            let operator = *operator;
+            self.position += 1;
+            let right = self.operand()?;
+            return Ok(Expr::Compare(operator, Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn operand(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or("unexpected end")?;
+        self.position += 1;
+        match token {
+            Token::Literal(value) => Ok(Expr::Literal(value)),
+            Token::Field(path) => Ok(Expr::Field(path)),
+            Token::Open => {
+                let expr = self.nested(Self::or)?;
+                if !self.next_if(&Token::Close) {
+                    return Err("missing )".to_string());
+                }
+                Ok(expr)
+            }
+            token => Err(format!("unexpected {:?}", token)),
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, value: &Value) -> Value {
+    match expr {
+        Expr::Literal(literal) => literal.clone(),
+        Expr::Field(path) => path
+            .iter()
+            .try_fold(value, |value, name| value.get(name))
+            .cloned()
+            .unwrap_or(Value::Null),
+        Expr::Not(inner) => Value::Bool(!truthy(&evaluate(inner, value))),
+        Expr::And(left, right) => {
+            Value::Bool(truthy(&evaluate(left, value)) && truthy(&evaluate(right, value)))
+        }
+        Expr::Or(left, right) => {
+            Value::Bool(truthy(&evaluate(left, value)) || truthy(&evaluate(right, value)))
+        }
+        Expr::Compare(operator, left, right) => Value::Bool(compare(
+            *operator,
+            &evaluate(left, value),
+            &evaluate(right, value),
+        )),
+    }
+}
+
+fn compare(operator: Operator, left: &Value, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left
+            .as_f64()
+            .zip(right.as_f64())
+            .and_then(|(left, right)| left.partial_cmp(&right)),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match operator {
+        Operator::Equal => ordering == Some(Ordering::Equal),
+        Operator::NotEqual => ordering != Some(Ordering::Equal),
+        Operator::Less => ordering == Some(Ordering::Less),
+        Operator::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::Greater => ordering == Some(Ordering::Greater),
+        Operator::GreaterOrEqual => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+        Operator::Contains => match (left, right) {
+            (Value::String(left), Value::String(right)) => left.contains(right.as_str()),
+            (Value::Array(items), right) => items
+                .iter()
+                .any(|item| compare(Operator::Equal, item, right)),
+            _ => false,
+        },
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::String(text) => !text.is_empty(),
+        _ => true,
+    }
+}
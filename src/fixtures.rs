@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::scrap::{extract_document, ScrapOptions};
+
+/// Url of the fixture `NAME.html`, relative links resolving against it
+pub const FIXTURE_BASE_URL: &str = "https://fixtures.scrapr.test/";
+
+/// An html page of the corpus and its expected record, `NAME.html` and `NAME.json`
+#[derive(Debug)]
+pub struct Fixture {
+    pub name: String,
+    pub html: PathBuf,
+    pub golden: PathBuf,
+}
+
+impl Fixture {
+    pub fn url(&self) -> String {
+        format!("{}{}.html", FIXTURE_BASE_URL, self.name)
+    }
+
+    /// Record extracted from the html, as written in the golden file
+    pub async fn extract(&self) -> Result<Value> {
+        let content = std::fs::read_to_string(&self.html)
+            .map_err(|e| anyhow!("Cannot read fixture {}: {}", self.html.display(), e))?;
+        let scraped = extract_document(&self.url(), content, &ScrapOptions::default()).await?;
+        scraped.to_value()
+    }
+
+    /// Expected record, `None` before the first bless
+    pub fn expected(&self) -> Result<Option<Value>> {
+        if !self.golden.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&self.golden)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+}
+
+/// `.html` files of a directory, sorted by name
+pub fn discover(dir: &Path) -> Result<Vec<Fixture>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow!("Cannot read fixtures {}: {}", dir.display(), e))?;
+    let mut fixtures = vec![];
+    for entry in entries {
+        let html = entry?.path();
+        if html.extension().is_none_or(|extension| extension != "html") {
+            continue;
+        }
+        let Some(name) = html.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        fixtures.push(Fixture {
+            name: name.to_string(),
+            golden: html.with_extension("json"),
+            html,
+        });
+    }
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+/// Write the golden file of each fixture from its current extraction, returning the
+/// names of those which changed
+pub async fn bless(dir: &Path) -> Result<Vec<String>> {
+    let mut changed = vec![];
+    for fixture in discover(dir)? {
+        let record = fixture.extract().await?;
+        if fixture.expected()?.as_ref() == Some(&record) {
+            continue;
+        }
+        std::fs::write(
+            &fixture.golden,
+            format!("{}\n", serde_json::to_string_pretty(&record)?),
+        )?;
+        changed.push(fixture.name);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::discover;
+
+    /// Every `tests/fixtures/NAME.html` extracts to its `NAME.json`; `scrapr dev bless`
+    /// rewrites them after an intended change
+    #[tokio::test]
+    async fn fixtures_match_golden_records() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let mut failures = vec![];
+        for fixture in discover(&dir).expect("fixtures directory") {
+            let record = fixture.extract().await.expect("extraction");
+            match fixture.expected().expect("golden file") {
+                Some(expected) if expected == record => {}
+                Some(_) => failures.push(format!("{}: record differs", fixture.name)),
+                None => failures.push(format!("{}: no {}", fixture.name, fixture.golden.display())),
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "{}\nrun `cargo run -- dev bless` and review the diff",
+            failures.join("\n")
+        );
+    }
+}
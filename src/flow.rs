@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use reqwest::cookie::Jar;
+use serde::{Deserialize, Serialize};
+
+use crate::scrap::{grab_url, select_text, ScrapOptions, Scraped, ScrapedWebpage, ScraperError};
+use crate::secret::resolve_secrets;
+
+/// Multi-step scrape flow, read from a yaml script
+///
+/// ```yaml
+/// vars:
+///   user: me
+/// steps:
+///   - fetch: https://example.com/login
+///   - submit_form: 1
+///     fields:
+///       login: "{{user}}"
+///       password: "{{secret:example_password}}"
+///   - follow: Dashboard
+///     assert_status: 200
+///     assert_selector: "#account"
+///   - extract:
+///       page_title: title
+///       account: "#account .name"
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct Flow {
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    pub steps: Vec<Step>,
+}
+
+/// A flow step, one action per step
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    /// Load an url
+    pub fetch: Option<String>,
+    /// Submit the Nth form of the current page
+    pub submit_form: Option<usize>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// Follow the first link of the current page whose text or url contains the value
+    pub follow: Option<String>,
+    /// Expected http status of the current page
+    pub assert_status: Option<u16>,
+    /// Css selector which must match on the current page
+    pub assert_selector: Option<String>,
+    /// Named values stored into variables, from a page field (title, url, description,
+    /// language, author) or else the text of a css selector
+    #[serde(default)]
+    pub extract: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlowResult {
+    pub vars: BTreeMap<String, String>,
+    pub extracted: BTreeMap<String, String>,
+    pub page: Option<ScrapedWebpage>,
+}
+
+pub fn read_flow(path: &str) -> Result<Flow> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_yaml::from_str(&content)?)
+}
+
+/// Run the steps in order, all requests sharing one cookie jar
+pub async fn run_flow(flow: Flow, options: &ScrapOptions) -> Result<FlowResult> {
+    let options = ScrapOptions {
+        cookie_jar: Some(Arc::new(Jar::default())),
+        ..options.clone()
+    };
+    let mut vars = flow.vars;
+    let mut extracted = BTreeMap::new();
+    let mut page: Option<ScrapedWebpage> = None;
+    // status of the last request, also known when it failed
+    let mut status: Option<u16> = None;
+
+    for (index, step) in flow.steps.iter().enumerate() {
+        let step_error = |reason: String| anyhow!("Flow step {}: {}", index + 1, reason);
+        if let Some(url) = &step.fetch {
+            page = grab_page(&substitute(url, &vars)?, &options, &mut status).await?;
+        } else if let Some(form_index) = step.submit_form {
+            let current = page
+                .as_ref()
+                .ok_or(step_error("no page loaded".to_string()))?;
+            let form = form_index
+                .checked_sub(1)
+                .and_then(|i| current.forms.get(i))
+                .ok_or(step_error(format!("no form {}", form_index)))?;
+            let fields: Vec<(String, String)> = step
+                .fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), substitute(value, &vars)?)))
+                .collect::<Result<_>>()?;
+            let (action, submit_options) = form.submission(&fields, &options)?;
+            page = grab_page(&action, &submit_options, &mut status).await?;
+        } else if let Some(pattern) = &step.follow {
+            let pattern = substitute(pattern, &vars)?;
+            let current = page
+                .as_ref()
+                .ok_or(step_error("no page loaded".to_string()))?;
+            let link = current
+                .links
+                .iter()
+                .find(|link| link.text.contains(&pattern) || link.url.contains(&pattern))
+                .ok_or(step_error(format!("no link matching {}", pattern)))?;
+            page = grab_page(&link.url.clone(), &options, &mut status).await?;
+        }
+
+        if let Some(expected) = step.assert_status {
+            if status != Some(expected) {
+                return Err(step_error(format!(
+                    "expected status {}, got {}",
+                    expected,
+                    status.map(|s| s.to_string()).unwrap_or("none".to_string())
+                )));
+            }
+        }
+        // a failed request only goes on when its status was asserted
+        let Some(current) = page.as_ref() else {
+            if step.assert_status.is_none() {
+                return Err(step_error(format!(
+                    "request failed with status {}",
+                    status.map(|s| s.to_string()).unwrap_or("none".to_string())
+                )));
+            }
+            continue;
+        };
+        if let Some(selector) = &step.assert_selector {
+            let selector = substitute(selector, &vars)?;
+            if select_text(&current.html, &selector)?.is_none() {
+                return Err(step_error(format!("no {} on {}", selector, current.url)));
+            }
+        }
+        for (name, source) in &step.extract {
+            let value = match page_field(current, source) {
+                Some(value) => value,
+                None => select_text(&current.html, source)?
+                    .ok_or(step_error(format!("no {} on {}", source, current.url)))?,
+            };
+            vars.insert(name.clone(), value.clone());
+            extracted.insert(name.clone(), value);
+        }
+    }
+
+    Ok(FlowResult {
+        vars,
+        extracted,
+        page,
+    })
+}
+
+/// Load a page, keeping its status; http errors leave no page instead of failing
+async fn grab_page(
+    url: &str,
+    options: &ScrapOptions,
+    status: &mut Option<u16>,
+) -> Result<Option<ScrapedWebpage>> {
+    match grab_url(url, options).await {
+        Ok(Scraped::Webpage(page)) => {
+            *status = Some(page.status);
+            Ok(Some(*page))
+        }
+        Ok(_) => Err(anyhow!("Flow steps require html pages: {}", url)),
+        Err(e) => match e.downcast_ref::<ScraperError>() {
+            Some(ScraperError::Client(code, _) | ScraperError::Server(code, _)) => {
+                *status = Some(code.as_u16());
+                Ok(None)
+            }
+            _ => Err(e),
+        },
+    }
+}
+
+fn page_field(page: &ScrapedWebpage, field: &str) -> Option<String> {
+    match field {
+        "title" => Some(page.title.clone()),
+        "url" => Some(page.url.clone()),
+        "description" => page.description.clone(),
+        "language" => page.language.clone(),
+        "author" => page.author.clone(),
+        _ => None,
+    }
+}
+
+/// Replace `{{secret:NAME}}` with keyring secrets, then `{{name}}` placeholders with flow
+/// variables: values scraped from pages must not be able to request a secret
+fn substitute(template: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let text = resolve_secrets(template)?;
+    Ok(vars.iter().fold(text, |text, (name, value)| {
+        text.replace(&format!("{{{{{}}}}}", name), value)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::substitute;
+
+    #[test]
+    fn extracted_values_are_not_resolved_as_secrets() {
+        let vars = BTreeMap::from([("query".to_string(), "{{secret:x}}".to_string())]);
+        let url = substitute("https://example.com/?q={{query}}", &vars).unwrap();
+        assert_eq!(url, "https://example.com/?q={{secret:x}}");
+    }
+}
@@ -0,0 +1,722 @@
+use std::fmt::Write;
+
+use anyhow::{anyhow, bail, Result};
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use webpage::Link;
+
+use crate::altaudit::{audit_alt_texts, format_alt_audit};
+use crate::article::{extract_article, format_article, Article};
+use crate::citation::{format_bibtex, format_csl_json};
+use crate::domains::DomainStats;
+use crate::event::{extract_events, format_ics};
+use crate::forms::Form;
+use crate::jobposting::format_job_posting;
+use crate::markdown::html_to_markdown;
+use crate::podcast::{self, format_episodes};
+use crate::recipe::{extract_recipe, format_recipe};
+use crate::scrap::{extract_html_infos, ScrapedWebpage};
+use crate::stats::{batch_stats, format_stats};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    Full,
+    Link,
+    /// Readable content of an article in markdown, its boilerplate left out
+    Article,
+    /// Markdown of the page content, with its metadata as front matter
+    #[serde(rename = "full-content")]
+    FullContent,
+    /// Outgoing links of a page
+    Links,
+    Forms,
+    /// Counts, latency and errors per registrable domain of the urls
+    Domains,
+    /// Ingredients and steps of a schema.org Recipe
+    Recipe,
+    /// Title, company, location and salary of a schema.org JobPosting
+    Job,
+    /// Audio episodes of a podcast page or feed
+    Podcast,
+    /// Images with their alt text status, and coverage percentages
+    #[serde(rename = "alt-audit")]
+    AltAudit,
+    /// Stylesheets, scripts, fonts and iframes by domain, with their sizes
+    Resources,
+    /// Estimated page weight by kind of resource, and the heaviest assets
+    Weight,
+    /// Counts of words, headings, links, images, tables and forms, totaled over a batch
+    Stats,
+}
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Markdown,
+    Json,
+    /// Compact json records, a line each, printed as the urls of a batch complete
+    Jsonl,
+    /// iCalendar of the schema.org events of a page
+    Ics,
+    /// Tab separated fields, a line per record, for `awk` and `cut`
+    Text,
+    /// `<a>` tags or preview cards, to embed in static sites
+    Html,
+    /// Items of Alfred and Raycast script filters, with the favicon as icon
+    #[serde(rename = "script-filter")]
+    ScriptFilter,
+    /// Org-mode links, with a property drawer in the full style
+    Org,
+    /// biblatex `@online` entries, to cite pages
+    Bibtex,
+    /// CSL-JSON items, to cite pages with Zotero or pandoc
+    #[serde(rename = "csl-json")]
+    CslJson,
+}
+
+/// Field selection and renaming of json records, `--fields` and `--rename`, and the kind
+/// of html snippet, `--html-card`
+#[derive(Debug, Clone, Default)]
+pub struct Shape {
+    /// Kept fields in output order, dotted for nested ones; all when empty
+    pub fields: Vec<String>,
+    /// Old and new names
+    pub renames: Vec<(String, String)>,
+    /// A `<figure>` with image, title and description instead of an `<a>` tag
+    pub html_card: bool,
+}
+
+impl Shape {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.renames.is_empty()
+    }
+
+    /// Reshape an object, other values are left as is
+    pub fn apply(&self, value: Value) -> Value {
+        if !value.is_object() {
+            return value;
+        }
+        let object = if self.fields.is_empty() {
+            value.as_object().cloned().unwrap_or_default()
+        } else {
+            self.fields
+                .iter()
+                .map(|field| {
+                    let selected = field
+                        .split('.')
+                        .try_fold(&value, |value, name| value.get(name));
+                    (field.clone(), selected.cloned().unwrap_or(Value::Null))
+                })
+                .collect()
+        };
+        Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = self
+                        .renames
+                        .iter()
+                        .find(|(old, _)| *old == key)
+                        .map_or(key, |(_, new)| new.clone());
+                    (key, value)
+                })
+                .collect(),
+        )
+    }
+}
+
+pub fn parse_rename(rename: &str) -> Result<(String, String)> {
+    let (old, new) = rename
+        .split_once('=')
+        .ok_or(anyhow!("Invalid rename {}: expected old=new", rename))?;
+    Ok((old.to_string(), new.to_string()))
+}
+
+/// A line of tab separated fields, tabs and line breaks inside them turned into spaces
+pub fn text_line(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| field.replace(['\t', '\r', '\n'], " "))
+        .collect();
+    fields.join("\t")
+}
+
+/// Pretty json of a record, reshaped
+pub fn format_json<T: Serialize>(record: &T, shape: &Shape) -> Result<String> {
+    if shape.is_empty() {
+        return Ok(serde_json::to_string_pretty(record)?);
+    }
+    Ok(serde_json::to_string_pretty(
+        &shape.apply(serde_json::to_value(record)?),
+    )?)
+}
+
+/// A record on one line, for `--format jsonl`
+pub fn format_json_line<T: Serialize>(record: &T, shape: &Shape) -> Result<String> {
+    if shape.is_empty() {
+        return Ok(serde_json::to_string(record)?);
+    }
+    Ok(serde_json::to_string(
+        &shape.apply(serde_json::to_value(record)?),
+    )?)
+}
+
+/// A json line per page, or per outgoing link in the links style
+pub fn format_jsonl(pages: &[ScrapedWebpage], style: Style, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    for page in pages {
+        match style {
+            Style::Full | Style::Link => writeln!(output, "{}", format_json_line(page, shape)?)?,
+            Style::Article => writeln!(output, "{}", format_json_line(&article(page)?, shape)?)?,
+            Style::FullContent => writeln!(
+                output,
+                "{}",
+                format_json_line(&content_record(page)?, shape)?
+            )?,
+            Style::Links => {
+                for link in &page.links {
+                    writeln!(output, "{}", format_json_line(link, shape)?)?;
+                }
+            }
+            _ => bail!(
+                "--format jsonl applies to the full, link, article, full-content and links styles"
+            ),
+        }
+    }
+    Ok(output)
+}
+
+pub fn format_response(
+    infos: &ScrapedWebpage,
+    style: Style,
+    format: Format,
+    shape: &Shape,
+) -> Result<String> {
+    let mut output = String::new();
+    match (style, format) {
+        (Style::Forms, _) => output = format_forms(&infos.forms, format)?,
+        (Style::Stats, _) => {
+            output = format_stats(&batch_stats(std::slice::from_ref(infos)), format, shape)?
+        }
+        (Style::Domains, _) => bail!("--style domains applies to url lists"),
+        (Style::Resources | Style::Weight, _) => {
+            bail!("--style resources and weight apply to single urls")
+        }
+        (_, Format::Jsonl) => output = format_jsonl(std::slice::from_ref(infos), style, shape)?,
+        (Style::Full | Style::Link | Style::FullContent, Format::Ics) => {
+            let events = extract_events(&infos.html, &infos.url);
+            if events.is_empty() {
+                bail!("No schema.org Event found on {}", infos.url);
+            }
+            output = format_ics(&events)?;
+        }
+        (_, Format::Ics) => bail!("--format ics applies to event pages"),
+        (Style::Full | Style::Link, Format::Html) => output = html_snippet(infos, style, shape),
+        (Style::Links, Format::Html) => output = html_links(&infos.links),
+        (_, Format::Html) => bail!("--format html applies to the full, link and links styles"),
+        (_, Format::ScriptFilter) => writeln!(
+            output,
+            "{}",
+            script_filter(std::slice::from_ref(infos), style)?
+        )?,
+        (Style::Full, Format::Org) => {
+            writeln!(output, "* {}", org_link(&infos.url, &infos.title))?;
+            writeln!(output, ":PROPERTIES:")?;
+            writeln!(output, ":URL: {}", infos.url)?;
+            if let Some(description) = &infos.description {
+                writeln!(output, ":DESCRIPTION: {}", org_property(description))?;
+            }
+            if let Some(language) = &infos.language {
+                writeln!(output, ":LANGUAGE: {}", org_property(language))?;
+            }
+            writeln!(output, ":END:")?;
+        }
+        (Style::Link, Format::Org) => writeln!(output, "{}", org_link(&infos.url, &infos.title))?,
+        (Style::Links, Format::Org) => {
+            for link in &infos.links {
+                let text = link.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                match text.is_empty() {
+                    true => writeln!(output, "- {}", org_link(&link.url, &link.url))?,
+                    false => writeln!(output, "- {}", org_link(&link.url, &text))?,
+                }
+            }
+        }
+        (_, Format::Org) => bail!("--format org applies to the full, link and links styles"),
+        (Style::Full | Style::Link, Format::Bibtex) => {
+            output = format_bibtex(std::slice::from_ref(infos))?
+        }
+        (Style::Full | Style::Link, Format::CslJson) => {
+            output = format_csl_json(std::slice::from_ref(infos))?
+        }
+        (_, Format::Bibtex | Format::CslJson) => {
+            bail!("--format bibtex and csl-json apply to the full and link styles")
+        }
+        (Style::Job, _) => {
+            let job = infos
+                .job
+                .as_ref()
+                .ok_or(anyhow!("No schema.org JobPosting found on {}", infos.url))?;
+            output = match format {
+                Format::Json => format!("{}\n", format_json(job, shape)?),
+                _ => format_job_posting(job, &infos.url)?,
+            };
+        }
+        (Style::AltAudit, _) => {
+            output = format_alt_audit(&audit_alt_texts(&infos.html, &infos.url), format, shape)?
+        }
+        (Style::Podcast, _) => {
+            output = format_episodes(&podcast::episodes(infos), format, shape)
+                .map_err(|e| anyhow!("{} on {}", e, infos.url))?
+        }
+        (Style::Recipe, _) => {
+            let recipe = extract_recipe(&infos.html)
+                .ok_or(anyhow!("No schema.org Recipe found on {}", infos.url))?;
+            output = match format {
+                Format::Json => format!("{}\n", format_json(&recipe, shape)?),
+                _ => format_recipe(&recipe)?,
+            };
+        }
+        (Style::Links, Format::Json) => writeln!(output, "{}", format_json(&infos.links, shape)?)?,
+        (Style::Links, Format::Markdown) => {
+            for link in &infos.links {
+                let text = link.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                match text.is_empty() {
+                    true => writeln!(output, "- <{}>", link.url)?,
+                    false => writeln!(output, "- [{}]({})", text, link.url)?,
+                }
+            }
+        }
+        (Style::Links, Format::Text) => {
+            for link in &infos.links {
+                writeln!(output, "{}", text_line(&[&link.url, link.text.trim()]))?;
+            }
+        }
+        (Style::Article, Format::Json) => {
+            writeln!(output, "{}", format_json(&article(infos)?, shape)?)?
+        }
+        (Style::FullContent, Format::Json) => {
+            writeln!(output, "{}", format_json(&content_record(infos)?, shape)?)?
+        }
+        (_, Format::Json) => writeln!(output, "{}", format_json(infos, shape)?)?,
+        (Style::Article, Format::Markdown) => output = format_article(&article(infos)?)?,
+        (Style::FullContent, Format::Markdown) => output = format_full_content(infos)?,
+        (Style::Article, Format::Text) => {
+            let article = article(infos)?;
+            writeln!(output, "{}", text_line(&[&article.title, &article.url]))?;
+            if !article.markdown.is_empty() {
+                writeln!(output)?;
+                writeln!(output, "{}", article.markdown)?;
+            }
+        }
+        (Style::FullContent, Format::Text) => {
+            writeln!(output, "{}", text_line(&[&infos.title, &infos.url]))?;
+            if let Some(text) = page_text(&infos.html).filter(|text| !text.is_empty()) {
+                writeln!(output)?;
+                writeln!(output, "{}", text)?;
+            }
+        }
+        (Style::Full, Format::Text) => {
+            let description = infos.description.as_deref().unwrap_or_default();
+            writeln!(
+                output,
+                "{}",
+                text_line(&[&infos.title, &infos.url, description])
+            )?;
+        }
+        (Style::Link, Format::Text) => {
+            writeln!(output, "{}", text_line(&[&infos.title, &infos.url]))?;
+        }
+        (Style::Full, Format::Markdown) => {
+            write!(output, "- [{}]({})", infos.title, infos.url)?;
+            if let Some(description) = &infos.description {
+                writeln!(output, "\\")?;
+                writeln!(output, "{}", description)?;
+            }
+        }
+        (Style::Link, Format::Markdown) => {
+            writeln!(output, "[{}]({})", infos.title, infos.url)?;
+        }
+    }
+    Ok(output)
+}
+
+/// A list of records: a json array, a markdown bullet list, one calendar of all events
+pub fn format_responses(
+    pages: &[ScrapedWebpage],
+    style: Style,
+    format: Format,
+    shape: &Shape,
+) -> Result<String> {
+    let mut output = String::new();
+    match (style, format) {
+        (Style::Stats, _) => output = format_stats(&batch_stats(pages), format, shape)?,
+        (_, Format::Jsonl) => output = format_jsonl(pages, style, shape)?,
+        (Style::Full | Style::Link | Style::FullContent, Format::Json) => {
+            let values = pages
+                .iter()
+                .map(|page| match style {
+                    Style::FullContent => Ok(shape.apply(content_record(page)?)),
+                    _ => Ok(shape.apply(serde_json::to_value(page)?)),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            writeln!(output, "{}", serde_json::to_string_pretty(&values)?)?;
+        }
+        (Style::Links, Format::Json) => {
+            let links: Vec<_> = pages.iter().flat_map(|page| &page.links).collect();
+            writeln!(output, "{}", format_json(&links, shape)?)?;
+        }
+        (_, Format::ScriptFilter) => writeln!(output, "{}", script_filter(pages, style)?)?,
+        (Style::Links, Format::Html) => {
+            output = html_links(pages.iter().flat_map(|page| &page.links));
+        }
+        (Style::Article, Format::Json) => {
+            let values = pages
+                .iter()
+                .map(|page| Ok(shape.apply(serde_json::to_value(article(page)?)?)))
+                .collect::<Result<Vec<_>>>()?;
+            writeln!(output, "{}", serde_json::to_string_pretty(&values)?)?;
+        }
+        (Style::Full | Style::Link | Style::FullContent, Format::Ics) => {
+            let events: Vec<_> = pages
+                .iter()
+                .flat_map(|page| extract_events(&page.html, &page.url))
+                .collect();
+            output = format_ics(&events)?;
+        }
+        (Style::Link, Format::Markdown) => {
+            for page in pages {
+                writeln!(output, "- [{}]({})", page.title, page.url)?;
+            }
+        }
+        (Style::Full | Style::Link, Format::Bibtex) => output = format_bibtex(pages)?,
+        (Style::Full | Style::Link, Format::CslJson) => output = format_csl_json(pages)?,
+        (Style::Link, Format::Org) => {
+            for page in pages {
+                writeln!(output, "- {}", org_link(&page.url, &page.title))?;
+            }
+        }
+        _ => {
+            let records = pages
+                .iter()
+                // the tab before an empty last text field stays
+                .map(|page| {
+                    Ok(format_response(page, style, format, shape)?
+                        .trim_end_matches('\n')
+                        .to_string())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let separator = match (style, format) {
+                (Style::Full | Style::Link | Style::Links, _) => "\n",
+                _ => "\n\n",
+            };
+            writeln!(output, "{}", records.join(separator))?;
+        }
+    }
+    Ok(output)
+}
+
+fn article(page: &ScrapedWebpage) -> Result<Article> {
+    extract_article(page).ok_or(anyhow!("No readable content found on {}", page.url))
+}
+
+/// An `<a>` tag to a page, titled with its description in the full style, or its card
+fn html_snippet(page: &ScrapedWebpage, style: Style, shape: &Shape) -> String {
+    let url = escape_html(&page.url);
+    let title = escape_html(&page.title);
+    let description = page.description.as_deref().map(escape_html);
+    if !shape.html_card {
+        return match description.filter(|_| style == Style::Full) {
+            Some(description) => format!(
+                "<a href=\"{}\" title=\"{}\">{}</a>\n",
+                url, description, title
+            ),
+            None => format!("<a href=\"{}\">{}</a>\n", url, title),
+        };
+    }
+    let mut card = String::from("<figure class=\"scrapr-card\">\n");
+    card.push_str(&format!("  <a href=\"{}\">\n", url));
+    if let Some(image) = &page.image {
+        card.push_str(&format!(
+            "    <img src=\"{}\" alt=\"\" loading=\"lazy\">\n",
+            escape_html(image)
+        ));
+    }
+    card.push_str("    <figcaption>\n");
+    card.push_str(&format!("      <strong>{}</strong>\n", title));
+    if let Some(description) = description {
+        card.push_str(&format!("      <p>{}</p>\n", description));
+    }
+    card.push_str("    </figcaption>\n  </a>\n</figure>\n");
+    card
+}
+
+/// A `<ul>` of links, the url standing for an empty text
+fn html_links<'a>(links: impl IntoIterator<Item = &'a Link>) -> String {
+    let mut list = String::from("<ul>\n");
+    for link in links {
+        let text = link.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let text = if text.is_empty() { &link.url } else { &text };
+        list.push_str(&format!(
+            "  <li><a href=\"{}\">{}</a></li>\n",
+            escape_html(&link.url),
+            escape_html(text)
+        ));
+    }
+    list.push_str("</ul>\n");
+    list
+}
+
+/// Item of an Alfred script filter, also read by Raycast
+#[derive(Serialize)]
+struct ScriptFilterItem<'a> {
+    uid: &'a str,
+    title: &'a str,
+    subtitle: &'a str,
+    arg: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<ScriptFilterIcon<'a>>,
+    quicklookurl: &'a str,
+}
+
+#[derive(Serialize)]
+struct ScriptFilterIcon<'a> {
+    path: &'a str,
+}
+
+/// `{"items": [...]}` of the pages, or of their outgoing links in the links style
+fn script_filter(pages: &[ScrapedWebpage], style: Style) -> Result<String> {
+    let mut items = vec![];
+    for page in pages {
+        match style {
+            Style::Full | Style::Link => items.push(ScriptFilterItem {
+                uid: &page.url,
+                title: &page.title,
+                subtitle: match style {
+                    Style::Full => page.description.as_deref().unwrap_or(&page.url),
+                    _ => &page.url,
+                },
+                arg: &page.url,
+                icon: page
+                    .favicon_file
+                    .as_deref()
+                    .map(|path| ScriptFilterIcon { path }),
+                quicklookurl: &page.url,
+            }),
+            Style::Links => items.extend(page.links.iter().map(|link| ScriptFilterItem {
+                uid: &link.url,
+                title: match link.text.trim() {
+                    "" => &link.url,
+                    text => text,
+                },
+                subtitle: &link.url,
+                arg: &link.url,
+                icon: None,
+                quicklookurl: &link.url,
+            })),
+            _ => bail!("--format script-filter applies to the full, link and links styles"),
+        }
+    }
+    Ok(serde_json::to_string_pretty(
+        &serde_json::json!({ "items": items }),
+    )?)
+}
+
+/// `[[url][description]]`, brackets of the url escaped and those of the description
+/// turned into braces, as org-mode ends links on them
+fn org_link(url: &str, description: &str) -> String {
+    let url = url
+        .replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]");
+    let description = description.replace('[', "{").replace(']', "}");
+    format!("[[{}][{}]]", url, description.trim())
+}
+
+/// A property value, on one line
+fn org_property(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text of an html document, its lines trimmed and blank ones dropped
+pub fn page_text(content: &str) -> Option<String> {
+    let html = extract_html_infos(content.to_string()).ok()?;
+    let lines: Vec<&str> = html
+        .text_content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Text escaped for html content and quoted attributes
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The record of a page with the markdown of its content
+fn content_record(page: &ScrapedWebpage) -> Result<Value> {
+    let mut record = serde_json::to_value(page)?;
+    if let Value::Object(fields) = &mut record {
+        let markdown = html_to_markdown(&page.html, &page.final_url);
+        fields.insert("markdown".to_string(), Value::String(markdown));
+    }
+    Ok(record)
+}
+
+/// Page metadata kept as front matter of a markdown note
+#[derive(Serialize)]
+struct FrontMatter<'a> {
+    title: &'a str,
+    source: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+}
+
+/// Yaml front matter, the title and the content of a page in markdown
+fn format_full_content(page: &ScrapedWebpage) -> Result<String> {
+    let front_matter = FrontMatter {
+        title: &page.title,
+        source: &page.url,
+        author: page.author.as_deref(),
+        published: page.published_at.as_deref(),
+        description: page.description.as_deref(),
+        tags: &page.tags,
+    };
+    let mut output = String::new();
+    writeln!(output, "---")?;
+    write!(output, "{}", serde_yaml::to_string(&front_matter)?)?;
+    writeln!(output, "---")?;
+    writeln!(output)?;
+    let content = html_to_markdown(&page.html, &page.final_url);
+    // articles usually start with their own title
+    if !content.starts_with("# ") {
+        writeln!(output, "# {}", page.title)?;
+    }
+    if !content.is_empty() {
+        if !content.starts_with("# ") {
+            writeln!(output)?;
+        }
+        writeln!(output, "{}", content)?;
+    }
+    Ok(output)
+}
+
+pub fn format_domains(stats: &[DomainStats], format: Format) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", serde_json::to_string_pretty(stats)?)?,
+        Format::Jsonl => {
+            for stat in stats {
+                writeln!(output, "{}", serde_json::to_string(stat)?)?;
+            }
+        }
+        Format::Ics => bail!("--format ics applies to event pages"),
+        Format::Html => bail!("--format html applies to the full, link and links styles"),
+        Format::ScriptFilter => {
+            bail!("--format script-filter applies to the full, link and links styles")
+        }
+        Format::Org => bail!("--format org applies to the full, link and links styles"),
+        Format::Bibtex | Format::CslJson => {
+            bail!("--format bibtex and csl-json apply to the full and link styles")
+        }
+        Format::Text => {
+            for stat in stats {
+                let line = text_line(&[
+                    &stat.domain,
+                    &stat.urls.to_string(),
+                    &stat.errors.to_string(),
+                    &stat.average_latency_ms.to_string(),
+                ]);
+                writeln!(output, "{}", line)?;
+            }
+        }
+        Format::Markdown => {
+            writeln!(output, "| domain | urls | errors | avg latency |")?;
+            writeln!(output, "| --- | ---: | ---: | ---: |")?;
+            for stat in stats {
+                writeln!(
+                    output,
+                    "| {} | {} | {} ({:.0}%) | {} ms |",
+                    stat.domain,
+                    stat.urls,
+                    stat.errors,
+                    stat.error_rate * 100.0,
+                    stat.average_latency_ms
+                )?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn format_forms(forms: &[Form], format: Format) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", serde_json::to_string_pretty(forms)?)?,
+        Format::Jsonl => {
+            for form in forms {
+                writeln!(output, "{}", serde_json::to_string(form)?)?;
+            }
+        }
+        Format::Ics => bail!("--format ics applies to event pages"),
+        Format::Html => bail!("--format html applies to the full, link and links styles"),
+        Format::ScriptFilter => {
+            bail!("--format script-filter applies to the full, link and links styles")
+        }
+        Format::Org => bail!("--format org applies to the full, link and links styles"),
+        Format::Bibtex | Format::CslJson => {
+            bail!("--format bibtex and csl-json apply to the full and link styles")
+        }
+        // a line per field
+        Format::Text => {
+            for (index, form) in forms.iter().enumerate() {
+                for field in &form.fields {
+                    let line = text_line(&[
+                        &(index + 1).to_string(),
+                        &form.method,
+                        &form.action,
+                        &field.name,
+                        &field.kind,
+                        &field.value,
+                    ]);
+                    writeln!(output, "{}", line)?;
+                }
+            }
+        }
+        Format::Markdown => {
+            for (index, form) in forms.iter().enumerate() {
+                writeln!(output, "{}. {} {}", index + 1, form.method, form.action)?;
+                for field in &form.fields {
+                    writeln!(
+                        output,
+                        "   - `{}` ({}) = \"{}\"",
+                        field.name, field.kind, field.value
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use reqwest::Method;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use url::{form_urlencoded, Url};
+
+use crate::scrap::ScrapOptions;
+
+const CONTENT_TYPE_FORM: &str = "application/x-www-form-urlencoded";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Form {
+    pub action: String,
+    pub method: String,
+    pub fields: Vec<FormField>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FormField {
+    pub name: String,
+    pub kind: String,
+    pub value: String,
+}
+
+/// List the forms of a html page, with actions resolved against the page url
+pub fn extract_forms(content: &str, page_url: &str) -> Vec<Form> {
+    let document = Html::parse_document(content);
+    let form_selector = Selector::parse("form").expect("valid selector");
+    let field_selector = Selector::parse("input, select, textarea").expect("valid selector");
+    let base = Url::parse(page_url).ok();
+
+    document
+        .select(&form_selector)
+        .map(|form| {
+            let action = form.value().attr("action").unwrap_or_default();
+            let action = base
+                .as_ref()
+                .and_then(|base| base.join(action).ok())
+                .map(|url| url.to_string())
+                .unwrap_or(action.to_string());
+            let method = form.value().attr("method").unwrap_or("get").to_uppercase();
+            let fields = form
+                .select(&field_selector)
+                .filter_map(extract_field)
+                .collect();
+            Form {
+                action,
+                method,
+                fields,
+            }
+        })
+        .collect()
+}
+
+/// Named field with its default value, skipping buttons and unchecked boxes
+fn extract_field(element: ElementRef) -> Option<FormField> {
+    let name = element.value().attr("name")?.to_string();
+    let tag = element.value().name();
+    let (kind, value) = match tag {
+        "select" => {
+            let option_selector = Selector::parse("option").expect("valid selector");
+            let options: Vec<ElementRef> = element.select(&option_selector).collect();
+            let selected = options
+                .iter()
+                .find(|option| option.value().attr("selected").is_some())
+                .or(options.first());
+            let value = selected
+                .map(|option| {
+                    option
+                        .value()
+                        .attr("value")
+                        .map(|value| value.to_string())
+                        .unwrap_or(option.text().collect::<String>().trim().to_string())
+                })
+                .unwrap_or_default();
+            ("select".to_string(), value)
+        }
+        "textarea" => ("textarea".to_string(), element.text().collect()),
+        _ => {
+            let kind = element
+                .value()
+                .attr("type")
+                .unwrap_or("text")
+                .to_lowercase();
+            if ["submit", "button", "image", "reset", "file"].contains(&kind.as_str()) {
+                return None;
+            }
+            let is_checkable = kind == "checkbox" || kind == "radio";
+            if is_checkable && element.value().attr("checked").is_none() {
+                return None;
+            }
+            let default = if is_checkable { "on" } else { "" };
+            let value = element.value().attr("value").unwrap_or(default).to_string();
+            (kind, value)
+        }
+    };
+    Some(FormField { name, kind, value })
+}
+
+impl Form {
+    /// Url and request options submitting the form, `overrides` replacing or adding field values
+    pub fn submission(
+        &self,
+        overrides: &[(String, String)],
+        options: &ScrapOptions,
+    ) -> Result<(String, ScrapOptions)> {
+        let mut values: Vec<(String, String)> = self
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.value.clone()))
+            .collect();
+        for (key, value) in overrides {
+            match values.iter_mut().find(|(name, _)| name == key) {
+                Some(existing) => existing.1 = value.clone(),
+                None => values.push((key.clone(), value.clone())),
+            }
+        }
+        let encoded = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&values)
+            .finish();
+
+        if self.method == "POST" {
+            let submit_options = ScrapOptions {
+                method: Method::POST,
+                body: Some(encoded),
+                content_type: Some(CONTENT_TYPE_FORM.to_string()),
+                ..options.clone()
+            };
+            Ok((self.action.clone(), submit_options))
+        } else {
+            let mut url = Url::parse(&self.action)
+                .map_err(|e| anyhow!("Invalid form action {}: {}", self.action, e))?;
+            url.set_query(Some(&encoded));
+            let submit_options = ScrapOptions {
+                method: Method::GET,
+                body: None,
+                content_type: None,
+                ..options.clone()
+            };
+            Ok((url.to_string(), submit_options))
+        }
+    }
+}
+
+/// Parse a `key=value` field override
+pub fn parse_field(field: &str) -> Result<(String, String)> {
+    let (key, value) = field
+        .split_once('=')
+        .ok_or(anyhow!("Invalid field {}: expected key=value", field))?;
+    Ok((key.to_string(), value.to_string()))
+}
@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::format::{format_json, format_json_line, text_line, Format, Shape};
+use crate::store::ArchivedSnapshot;
+
+/// A snapshot of a stored url, with what changed since the previous one
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub fetched_at: String,
+    pub status: u16,
+    pub title: String,
+    pub description: Option<String>,
+    pub content_hash: String,
+    /// `status`, `title`, `description` and `content`, empty for the first snapshot
+    pub changes: Vec<String>,
+}
+
+pub fn timeline(snapshots: Vec<ArchivedSnapshot>) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = vec![];
+    for snapshot in snapshots {
+        let mut changes = vec![];
+        if let Some(previous) = entries.last() {
+            let fields = [
+                ("status", previous.status != snapshot.status),
+                ("title", previous.title != snapshot.title),
+                ("description", previous.description != snapshot.description),
+                ("content", previous.content_hash != snapshot.content_hash),
+            ];
+            changes = fields
+                .into_iter()
+                .filter(|(_, changed)| *changed)
+                .map(|(field, _)| field.to_string())
+                .collect();
+        }
+        entries.push(HistoryEntry {
+            fetched_at: snapshot.fetched_at,
+            status: snapshot.status,
+            title: snapshot.title,
+            description: snapshot.description,
+            content_hash: snapshot.content_hash,
+            changes,
+        });
+    }
+    entries
+}
+
+/// A table of the snapshots, or a json array
+pub fn format_history(entries: &[HistoryEntry], format: Format, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(&entries, shape)?)?,
+        Format::Jsonl => {
+            for entry in entries {
+                writeln!(output, "{}", format_json_line(entry, shape)?)?;
+            }
+        }
+        Format::Ics => bail!("--format ics applies to event pages"),
+        Format::Html => bail!("--format html applies to the full, link and links styles"),
+        Format::ScriptFilter => {
+            bail!("--format script-filter applies to the full, link and links styles")
+        }
+        Format::Org => bail!("--format org applies to the full, link and links styles"),
+        Format::Bibtex | Format::CslJson => {
+            bail!("--format bibtex and csl-json apply to the full and link styles")
+        }
+        Format::Text => {
+            for entry in entries {
+                let line = text_line(&[
+                    &entry.fetched_at,
+                    &entry.status.to_string(),
+                    &entry.title,
+                    &entry.content_hash,
+                    &entry.changes.join(","),
+                ]);
+                writeln!(output, "{}", line)?;
+            }
+        }
+        Format::Markdown => {
+            writeln!(
+                output,
+                "| fetched at | status | title | content | changes |"
+            )?;
+            writeln!(output, "| --- | --- | --- | --- | --- |")?;
+            for entry in entries {
+                writeln!(
+                    output,
+                    "| {} | {} | {} | {} | {} |",
+                    entry.fetched_at,
+                    entry.status,
+                    entry.title.replace('|', "\\|"),
+                    // enough to tell versions apart
+                    entry.content_hash.get(..12).unwrap_or(&entry.content_hash),
+                    entry.changes.join(", ")
+                )?;
+            }
+        }
+    }
+    Ok(output)
+}
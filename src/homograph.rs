@@ -0,0 +1,32 @@
+use unicode_security::{skeleton, RestrictionLevel, RestrictionLevelDetection};
+use url::Url;
+
+/// Warnings about a hostname built to pass for another one: labels mixing scripts
+/// (`pаypal` with a cyrillic `а`) or whole labels of latin look-alikes (`аррӏе`)
+pub fn host_warnings(url: &str) -> Vec<String> {
+    let Some(host) = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+    else {
+        return vec![];
+    };
+    // punycode `xn--` labels are decoded to what a reader sees
+    let (unicode_host, _) = idna::domain_to_unicode(&host);
+    let mut warnings = vec![];
+    for label in unicode_host.split('.') {
+        if label.is_ascii() {
+            continue;
+        }
+        if label.detect_restriction_level() > RestrictionLevel::HighlyRestrictive {
+            warnings.push(format!("host {} mixes scripts in {}", host, label));
+        }
+        let look_alike: String = skeleton(label).collect();
+        if look_alike.is_ascii() {
+            warnings.push(format!(
+                "host {} label {} is confusable with {}",
+                host, label, look_alike
+            ));
+        }
+    }
+    warnings
+}
@@ -0,0 +1,520 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::future::Future;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use reqwest::{Client, StatusCode};
+use tokio::task::JoinSet;
+
+use crate::cleanup::clean_url;
+use crate::resources::head_client;
+use crate::scrap::{grab_url, ScrapOptions, Scraped};
+use crate::sync::git;
+
+const CHECK_CONCURRENCY: usize = 8;
+
+/// Link of a markdown file, `[text](url)`, `![alt](url)` or the autolink `<url>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownLink {
+    /// 1-based
+    pub line: usize,
+    /// Byte range of the whole link in the file
+    pub span: Range<usize>,
+    /// Byte range of the url in the file
+    pub url_span: Range<usize>,
+    pub text: String,
+    pub url: String,
+    pub image: bool,
+    pub autolink: bool,
+}
+
+impl MarkdownLink {
+    /// Whether the text tells nothing more than the url
+    fn is_bare(&self) -> bool {
+        let text = self.text.trim();
+        !self.image && (self.autolink || text.is_empty() || text == self.url)
+    }
+}
+
+/// Inline links, images and autolinks of markdown, code blocks and code spans aside
+pub fn markdown_links(content: &str) -> Vec<MarkdownLink> {
+    let mut links = vec![];
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            (None, None) => line_links(line, offset, index + 1, &mut links),
+            _ => {}
+        }
+        offset += line.len();
+    }
+    links
+}
+
+fn line_links(line: &str, offset: usize, number: usize, links: &mut Vec<MarkdownLink>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let ticks = &line[i..i + run];
+                i = match line[i + run..].find(ticks) {
+                    Some(end) => i + run + end + run,
+                    None => i + run,
+                };
+            }
+            b'<' => match autolink(line, i) {
+                Some(end) => {
+                    links.push(MarkdownLink {
+                        line: number,
+                        span: offset + i..offset + end,
+                        url_span: offset + i + 1..offset + end - 1,
+                        text: String::new(),
+                        url: line[i + 1..end - 1].to_string(),
+                        image: false,
+                        autolink: true,
+                    });
+                    i = end;
+                }
+                None => i += 1,
+            },
+            b'[' => match inline_link(line, i) {
+                Some((text, url, end)) => {
+                    let image = i > 0 && bytes[i - 1] == b'!';
+                    let start = if image { i - 1 } else { i };
+                    links.push(MarkdownLink {
+                        line: number,
+                        span: offset + start..offset + end,
+                        url_span: offset + url.start..offset + url.end,
+                        text: line[text.clone()].to_string(),
+                        url: line[url].to_string(),
+                        image,
+                        autolink: false,
+                    });
+                    // images inside the text of a link
+                    line_links(&line[text.clone()], offset + text.start, number, links);
+                    i = end;
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+}
+
+/// `<http...>` at `start`, with its end
+fn autolink(line: &str, start: usize) -> Option<usize> {
+    let rest = &line[start + 1..];
+    if !rest.starts_with("http://") && !rest.starts_with("https://") {
+        return None;
+    }
+    let end = rest.find(|c: char| c == '>' || c == '<' || c.is_whitespace())?;
+    (rest[end..].starts_with('>')).then_some(start + 1 + end + 1)
+}
+
+/// `[text](url "title")` at `start`: the ranges of its text and url, and its end
+fn inline_link(line: &str, start: usize) -> Option<(Range<usize>, Range<usize>, usize)> {
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    let close = loop {
+        match bytes.get(i)? {
+            b'\\' => i += 1,
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    };
+    if bytes.get(close + 1) != Some(&b'(') {
+        return None;
+    }
+    let mut url_start = close + 2;
+    while bytes.get(url_start).is_some_and(|b| *b == b' ') {
+        url_start += 1;
+    }
+    let (url, after) = match bytes.get(url_start)? {
+        b'<' => {
+            let end = url_start + 1 + line[url_start + 1..].find('>')?;
+            (url_start + 1..end, end + 1)
+        }
+        _ => {
+            // balanced parentheses belong to the url, like in wikipedia links
+            let mut parens = 0;
+            let mut end = url_start;
+            while let Some(b) = bytes.get(end) {
+                match b {
+                    b'(' => parens += 1,
+                    b')' if parens == 0 => break,
+                    b')' => parens -= 1,
+                    b if b.is_ascii_whitespace() => break,
+                    _ => {}
+                }
+                end += 1;
+            }
+            (url_start..end, end)
+        }
+    };
+    // past an optional title
+    let end = after + line[after..].find(')')? + 1;
+    Some((start + 1..close, url, end))
+}
+
+#[derive(Debug)]
+pub struct DeadLink {
+    pub line: usize,
+    pub url: String,
+    /// Status or error of the request
+    pub reason: String,
+}
+
+/// Links of a markdown file after review
+#[derive(Debug)]
+pub struct Review {
+    /// The content with its links fixed
+    pub content: String,
+    pub cleaned: usize,
+    pub titled: usize,
+    pub dead: Vec<DeadLink>,
+}
+
+/// Clean the tracking parameters of the http(s) links of markdown files, fill the titles
+/// of bare links with `fill_titles`, and find the dead ones
+pub async fn review(
+    contents: &[String],
+    fill_titles: bool,
+    options: &ScrapOptions,
+) -> Result<Vec<Review>> {
+    let links: Vec<Vec<MarkdownLink>> = contents
+        .iter()
+        .map(|content| {
+            markdown_links(content)
+                .into_iter()
+                .filter(|link| link.url.starts_with("http://") || link.url.starts_with("https://"))
+                .collect()
+        })
+        .collect();
+    let urls: BTreeSet<String> = links
+        .iter()
+        .flatten()
+        .map(|link| clean_url(&link.url))
+        .collect();
+    let client = head_client(options)?;
+    let dead = for_each_url(urls, move |url| {
+        let client = client.clone();
+        async move { dead_reason(&client, &url).await }
+    })
+    .await?;
+    let mut titles = HashMap::new();
+    if fill_titles {
+        let bare: BTreeSet<String> = links
+            .iter()
+            .flatten()
+            .filter(|link| link.is_bare())
+            .map(|link| clean_url(&link.url))
+            .filter(|url| dead.get(url).is_some_and(Option::is_none))
+            .collect();
+        let options = options.clone();
+        titles = for_each_url(bare, move |url| {
+            let options = options.clone();
+            async move { page_title(&url, &options).await }
+        })
+        .await?;
+    }
+
+    let mut reviews = vec![];
+    for (content, links) in contents.iter().zip(links) {
+        let mut review = Review {
+            content: content.clone(),
+            cleaned: 0,
+            titled: 0,
+            dead: vec![],
+        };
+        let mut edits = vec![];
+        for link in links {
+            let url = clean_url(&link.url);
+            if url != link.url {
+                review.cleaned += 1;
+            }
+            if let Some(Some(reason)) = dead.get(&url) {
+                review.dead.push(DeadLink {
+                    line: link.line,
+                    url: url.clone(),
+                    reason: reason.clone(),
+                });
+            }
+            let title = titles.get(&url).cloned().flatten();
+            match title.filter(|_| link.is_bare()) {
+                Some(title) => {
+                    review.titled += 1;
+                    edits.push((link.span, format!("[{}]({})", title, url)));
+                }
+                None if url != link.url => edits.push((link.url_span, url)),
+                None => {}
+            }
+        }
+        // from the end, so the ranges before stay valid
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        for (range, replacement) in edits {
+            review.content.replace_range(range, &replacement);
+        }
+        reviews.push(review);
+    }
+    Ok(reviews)
+}
+
+/// Run a task for each url, a few at a time
+async fn for_each_url<T, F, Fut>(urls: BTreeSet<String>, task: F) -> Result<HashMap<String, T>>
+where
+    T: Send + 'static,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let mut requests = JoinSet::new();
+    let mut pending = urls.into_iter();
+    let mut spawn = |requests: &mut JoinSet<(String, T)>| {
+        let Some(url) = pending.next() else {
+            return;
+        };
+        let work = task(url.clone());
+        requests.spawn(async move { (url, work.await) });
+    };
+    for _ in 0..CHECK_CONCURRENCY {
+        spawn(&mut requests);
+    }
+    let mut results = HashMap::new();
+    while let Some(result) = requests.join_next().await {
+        let (url, value) = result?;
+        results.insert(url, value);
+        spawn(&mut requests);
+    }
+    Ok(results)
+}
+
+/// Why a link is dead, none when it answers; restricted (401, 403) and rate limited (429)
+/// pages exist
+async fn dead_reason(client: &Client, url: &str) -> Option<String> {
+    let mut response = client.head(url).send().await;
+    // some servers only refuse HEAD
+    let refused = response.as_ref().is_ok_and(|response| {
+        matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED | StatusCode::FORBIDDEN
+        )
+    });
+    if refused {
+        response = client.get(url).send().await;
+    }
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            let alive = status.is_success()
+                || status.is_redirection()
+                || matches!(
+                    status,
+                    StatusCode::UNAUTHORIZED
+                        | StatusCode::FORBIDDEN
+                        | StatusCode::TOO_MANY_REQUESTS
+                );
+            (!alive).then(|| status.to_string())
+        }
+        Err(e) if e.is_timeout() => Some("timeout".to_string()),
+        Err(e) => {
+            // the root cause, like a dns or connection error
+            let mut cause: &dyn Error = &e;
+            while let Some(source) = cause.source() {
+                cause = source;
+            }
+            Some(cause.to_string())
+        }
+    }
+}
+
+/// Title of an html page, its brackets escaped for a link text
+async fn page_title(url: &str, options: &ScrapOptions) -> Option<String> {
+    let Ok(Scraped::Webpage(page)) = grab_url(url, options).await else {
+        return None;
+    };
+    let title = page.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then(|| title.replace('[', "\\[").replace(']', "\\]"))
+}
+
+fn is_markdown(path: &str) -> bool {
+    path.ends_with(".md") || path.ends_with(".markdown")
+}
+
+/// Review markdown files as a pre-commit hook: the given `files`, or the staged ones with
+/// `staged`, their fixes staged back. False when dead links are found or a file could not
+/// be fixed
+pub async fn run_hook(
+    files: &[PathBuf],
+    staged: bool,
+    fill_titles: bool,
+    options: &ScrapOptions,
+) -> Result<bool> {
+    let root = match staged {
+        true => PathBuf::from(git(Path::new("."), &["rev-parse", "--show-toplevel"])?.trim()),
+        false => PathBuf::new(),
+    };
+    let files: Vec<PathBuf> = match staged {
+        true => git(
+            &root,
+            &[
+                "diff",
+                "--cached",
+                "--name-only",
+                "--diff-filter=ACMR",
+                "-z",
+            ],
+        )?
+        .split('\0')
+        .filter(|path| is_markdown(path))
+        .map(PathBuf::from)
+        .collect(),
+        false => files.to_vec(),
+    };
+    let mut contents = vec![];
+    for path in &files {
+        let content = match staged {
+            true => git(&root, &["show", &format!(":{}", path.display())])?,
+            false => std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?,
+        };
+        contents.push(content);
+    }
+
+    let reviews = review(&contents, fill_titles, options).await?;
+    let mut passed = true;
+    for ((path, original), review) in files.iter().zip(&contents).zip(reviews) {
+        if review.content != *original {
+            let file = root.join(path);
+            // a partially staged file would get its unstaged changes committed
+            if staged && std::fs::read_to_string(&file)? != *original {
+                eprintln!(
+                    "{}: links to fix but unstaged changes, run `scrapr hook {}`",
+                    path.display(),
+                    path.display()
+                );
+                passed = false;
+                continue;
+            }
+            std::fs::write(&file, &review.content)?;
+            if staged {
+                git(&root, &["add", "--", &path.to_string_lossy()])?;
+            }
+            eprintln!(
+                "{}: {} links cleaned, {} titles filled",
+                path.display(),
+                review.cleaned,
+                review.titled
+            );
+        }
+        for dead in &review.dead {
+            eprintln!(
+                "{}:{}: dead link {} ({})",
+                path.display(),
+                dead.line,
+                dead.url,
+                dead.reason
+            );
+            passed = false;
+        }
+    }
+    Ok(passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::markdown_links;
+
+    fn urls(content: &str) -> Vec<String> {
+        markdown_links(content)
+            .into_iter()
+            .map(|link| link.url)
+            .collect()
+    }
+
+    #[test]
+    fn inline_links_images_and_autolinks() {
+        let content = "See [the book](https://doc.rust-lang.org/book/ \"Book\"), ![logo](logo.png)\nand <https://example.com/a>.\n";
+        let links = markdown_links(content);
+        assert_eq!(links.len(), 3);
+
+        assert_eq!(links[0].text, "the book");
+        assert_eq!(links[0].url, "https://doc.rust-lang.org/book/");
+        assert_eq!(&content[links[0].url_span.clone()], links[0].url);
+        assert_eq!(
+            &content[links[0].span.clone()],
+            "[the book](https://doc.rust-lang.org/book/ \"Book\")"
+        );
+        assert!(!links[0].image);
+
+        assert!(links[1].image);
+        assert_eq!(&content[links[1].span.clone()], "![logo](logo.png)");
+
+        assert!(links[2].autolink);
+        assert_eq!(links[2].line, 2);
+        assert_eq!(links[2].url, "https://example.com/a");
+        assert_eq!(&content[links[2].url_span.clone()], links[2].url);
+    }
+
+    #[test]
+    fn code_is_skipped() {
+        let content = "`[no](https://a.example)` [yes](https://b.example)\n```\n[no](https://c.example)\n~~~\n```\n~~~\n<https://d.example>\n~~~\n``a ` [no](https://e.example)``\n";
+        assert_eq!(urls(content), ["https://b.example"]);
+    }
+
+    #[test]
+    fn parentheses_and_brackets() {
+        let content = "[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language)) [a [nested] text](<https://x.example/a b>)\n";
+        assert_eq!(
+            urls(content),
+            [
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "https://x.example/a b"
+            ]
+        );
+    }
+
+    #[test]
+    fn image_inside_a_link() {
+        let content = "[![badge](https://img.example/b.svg)](https://ci.example)";
+        let links = markdown_links(content);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].url, "https://ci.example");
+        assert_eq!(links[1].url, "https://img.example/b.svg");
+        assert!(links[1].image);
+        assert_eq!(&content[links[1].url_span.clone()], links[1].url);
+    }
+
+    #[test]
+    fn not_links() {
+        let content = "\\[escaped](https://a.example) [reference][1] [unclosed](https://b.example <http://no space> <ftp://x>\n";
+        assert!(markdown_links(content).is_empty());
+    }
+
+    #[test]
+    fn multibyte_text() {
+        let content = "\\é [café ☕](https://x.example/é) `ü` <https://x.example/日本>";
+        let links = markdown_links(content);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].text, "café ☕");
+        assert_eq!(&content[links[0].url_span.clone()], "https://x.example/é");
+        assert_eq!(links[1].url, "https://x.example/日本");
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::Result;
+use exif::{Context, Exif, In, Reader, Tag};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use url::Url;
+
+use crate::download;
+use crate::scrap::{ScrapOptions, ScrapedWebpage};
+
+/// Downloaded hero image and what its EXIF/IPTC data tells
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageMetadata {
+    pub url: String,
+    pub path: String,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    /// Make and model
+    pub camera: Option<String>,
+    pub copyright: Option<String>,
+    pub artist: Option<String>,
+    pub taken_at: Option<String>,
+    /// Whether the image carries a location
+    pub has_gps: bool,
+}
+
+/// The lead image of the page or else its first image, absolute
+pub fn hero_image_url(page: &ScrapedWebpage) -> Option<String> {
+    if let Some(image) = &page.image {
+        return Some(image.clone());
+    }
+    let document = Html::parse_document(&page.html);
+    let selector = Selector::parse("img[src]").expect("valid selector");
+    let image = document.select(&selector).next()?.value().attr("src")?;
+    Url::parse(&page.final_url)
+        .and_then(|base| base.join(image))
+        .map(String::from)
+        .ok()
+}
+
+/// Download the hero image of a page into `directory` and read its metadata
+pub async fn download_hero_image(
+    page: &ScrapedWebpage,
+    directory: &Path,
+    options: &ScrapOptions,
+) -> Result<Option<ImageMetadata>> {
+    let Some(url) = hero_image_url(page) else {
+        return Ok(None);
+    };
+    let path = download::download_file(&url, directory, options).await?;
+    let content = std::fs::read(&path)?;
+    let size = imagesize::blob_size(&content).ok();
+    // images without EXIF are common
+    let exif = Reader::new()
+        .read_from_container(&mut Cursor::new(&content))
+        .ok();
+    let field = |tag| exif.as_ref().and_then(|exif| ascii_field(exif, tag));
+    let camera = [field(Tag::Make), field(Tag::Model)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(Some(ImageMetadata {
+        url,
+        path: path.display().to_string(),
+        width: size.as_ref().map(|size| size.width),
+        height: size.as_ref().map(|size| size.height),
+        camera: Some(camera).filter(|camera| !camera.is_empty()),
+        copyright: field(Tag::Copyright).or_else(|| iptc_dataset(&content, IPTC_COPYRIGHT)),
+        artist: field(Tag::Artist).or_else(|| iptc_dataset(&content, IPTC_BYLINE)),
+        taken_at: field(Tag::DateTimeOriginal).or(field(Tag::DateTime)),
+        has_gps: exif.as_ref().is_some_and(|exif| {
+            exif.fields()
+                .any(|field| field.tag.context() == Context::Gps)
+        }),
+    }))
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    let text = match &field.value {
+        exif::Value::Ascii(values) => values
+            .iter()
+            .map(|value| String::from_utf8_lossy(value).trim().to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        value => value.display_as(tag).to_string(),
+    };
+    Some(text.trim_matches(char::from(0)).trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// IPTC record 2 datasets
+const IPTC_BYLINE: u8 = 80;
+const IPTC_COPYRIGHT: u8 = 116;
+
+/// Value of an IPTC-IIM dataset of a JPEG, from the IPTC resource of its APP13
+/// Photoshop segments
+fn iptc_dataset(content: &[u8], dataset: u8) -> Option<String> {
+    let resources = app13_data(content);
+    let iptc = photoshop_resource(&resources, IPTC_RESOURCE)?;
+    // datasets are `0x1C record dataset length value`
+    let mut position = 0;
+    while let Some(header) = iptc.get(position..position + 5) {
+        if header[0] != 0x1C {
+            return None;
+        }
+        let length = u16::from_be_bytes([header[3], header[4]]) as usize;
+        // extended lengths are for binary data, not texts
+        if length & 0x8000 != 0 {
+            return None;
+        }
+        let value = iptc.get(position + 5..position + 5 + length)?;
+        if header[1] == 2 && header[2] == dataset {
+            return Some(String::from_utf8_lossy(value).trim().to_string())
+                .filter(|text| !text.is_empty());
+        }
+        position += 5 + length;
+    }
+    None
+}
+
+const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+/// Image resource holding the IPTC-NAA record
+const IPTC_RESOURCE: u16 = 0x0404;
+
+/// Photoshop image resources of the APP13 segments of a JPEG, a large block spanning
+/// several segments
+fn app13_data(content: &[u8]) -> Vec<u8> {
+    let mut data = vec![];
+    if !content.starts_with(&[0xFF, 0xD8]) {
+        return data;
+    }
+    let mut position = 2;
+    while let Some(&[0xFF, marker]) = content.get(position..position + 2) {
+        match marker {
+            // fill byte
+            0xFF => position += 1,
+            // markers without length
+            0x01 | 0xD0..=0xD7 => position += 2,
+            // the image data follows the start of scan
+            0xDA | 0xD9 => break,
+            _ => {
+                let Some(length) = content.get(position + 2..position + 4) else {
+                    break;
+                };
+                let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+                let Some(segment) = content.get(position + 4..position + 2 + length) else {
+                    break;
+                };
+                if marker == 0xED {
+                    if let Some(resources) = segment.strip_prefix(PHOTOSHOP_SIGNATURE) {
+                        data.extend_from_slice(resources);
+                    }
+                }
+                position += 2 + length;
+            }
+        }
+    }
+    data
+}
+
+/// Data of a Photoshop image resource: `8BIM id name size data`, the pascal string name
+/// and the data padded to an even length
+fn photoshop_resource(resources: &[u8], id: u16) -> Option<&[u8]> {
+    let mut position = 0;
+    while resources.get(position..position + 4)? == b"8BIM" {
+        let resource_id =
+            u16::from_be_bytes(resources.get(position + 4..position + 6)?.try_into().ok()?);
+        let name_length = *resources.get(position + 6)? as usize;
+        let size_start = position + 6 + (name_length + 1).next_multiple_of(2);
+        let size = u32::from_be_bytes(resources.get(size_start..size_start + 4)?.try_into().ok()?)
+            as usize;
+        let data = resources.get(size_start + 4..size_start + 4 + size)?;
+        if resource_id == id {
+            return Some(data);
+        }
+        position = size_start + 4 + size.next_multiple_of(2);
+    }
+    None
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+/// Browser whose navigation request headers are sent, names and order included; the
+/// TLS fingerprint stays the one of the http client
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Browser {
+    Chrome,
+    Firefox,
+    Safari,
+}
+
+impl Browser {
+    pub fn user_agent(self) -> &'static str {
+        match self {
+            Browser::Chrome => "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36",
+            Browser::Firefox => "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:131.0) Gecko/20100101 Firefox/131.0",
+            Browser::Safari => "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.0 Safari/605.1.15",
+        }
+    }
+
+    pub fn accept(self) -> &'static str {
+        match self {
+            Browser::Chrome => "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+            Browser::Firefox => "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/png,image/svg+xml,*/*;q=0.8",
+            Browser::Safari => "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+        }
+    }
+
+    /// Request headers in the order the browser sends them; `user-agent`, `accept` and
+    /// `accept-encoding` values are set by the client
+    pub fn headers(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Browser::Chrome => &[
+                (
+                    "sec-ch-ua",
+                    "\"Chromium\";v=\"130\", \"Google Chrome\";v=\"130\", \"Not?A_Brand\";v=\"99\"",
+                ),
+                ("sec-ch-ua-mobile", "?0"),
+                ("sec-ch-ua-platform", "\"Windows\""),
+                ("upgrade-insecure-requests", "1"),
+                ("user-agent", ""),
+                ("accept", ""),
+                ("sec-fetch-site", "none"),
+                ("sec-fetch-mode", "navigate"),
+                ("sec-fetch-user", "?1"),
+                ("sec-fetch-dest", "document"),
+                ("accept-encoding", ""),
+                ("accept-language", "en-US,en;q=0.9"),
+                ("priority", "u=0, i"),
+            ],
+            Browser::Firefox => &[
+                ("user-agent", ""),
+                ("accept", ""),
+                ("accept-language", "en-US,en;q=0.5"),
+                ("accept-encoding", ""),
+                ("upgrade-insecure-requests", "1"),
+                ("sec-fetch-dest", "document"),
+                ("sec-fetch-mode", "navigate"),
+                ("sec-fetch-site", "none"),
+                ("sec-fetch-user", "?1"),
+                ("priority", "u=0, i"),
+            ],
+            Browser::Safari => &[
+                ("accept", ""),
+                ("sec-fetch-site", "none"),
+                ("sec-fetch-mode", "navigate"),
+                ("user-agent", ""),
+                ("accept-language", "en-US,en;q=0.9"),
+                ("sec-fetch-dest", "document"),
+                ("accept-encoding", ""),
+                ("priority", "u=0, i"),
+            ],
+        }
+    }
+}
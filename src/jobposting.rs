@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::structured::{self, find_entity};
+
+/// schema.org JobPosting of a page
+#[derive(Debug, Clone, Serialize)]
+pub struct JobPosting {
+    pub title: String,
+    pub company: Option<String>,
+    pub location: Option<String>,
+    /// Amount or range with its currency and unit, like `50000-60000 EUR/YEAR`
+    pub salary: Option<String>,
+    pub date_posted: Option<String>,
+}
+
+pub fn extract_job_posting(entities: &[Value]) -> Option<JobPosting> {
+    let posting = find_entity(entities, &["JobPosting"])?;
+    Some(JobPosting {
+        title: structured::text(posting, "title")
+            .or(structured::text(posting, "name"))
+            .unwrap_or("Job".to_string()),
+        company: structured::text(posting, "hiringOrganization"),
+        location: location(posting),
+        salary: posting.get("baseSalary").and_then(salary),
+        date_posted: structured::text(posting, "datePosted"),
+    })
+}
+
+/// Locality, region and country of the first job location, `Remote` for telecommuting
+fn location(posting: &Value) -> Option<String> {
+    let place = match posting.get("jobLocation") {
+        Some(Value::Array(places)) => places.first(),
+        place => place,
+    };
+    let address = place.and_then(|place| place.get("address"));
+    let text = match address {
+        Some(Value::Object(address)) => Some(
+            ["addressLocality", "addressRegion", "addressCountry"]
+                .iter()
+                .filter_map(|key| structured::value_text(address.get(*key)?))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+        .filter(|text| !text.is_empty()),
+        Some(address) => structured::value_text(address),
+        None => None,
+    };
+    let remote = structured::text(posting, "jobLocationType")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("TELECOMMUTE"));
+    match (text, remote) {
+        (Some(text), true) => Some(format!("{} (remote)", text)),
+        (None, true) => Some("Remote".to_string()),
+        (text, false) => text,
+    }
+}
+
+/// A MonetaryAmount with a value or a min/max QuantitativeValue
+fn salary(salary: &Value) -> Option<String> {
+    let Value::Object(amount) = salary else {
+        return structured::value_text(salary);
+    };
+    let value = amount.get("value")?;
+    let (range, unit) = match value {
+        Value::Object(quantity) => {
+            let amount = |key: &str| quantity.get(key).and_then(structured::value_text);
+            let range = match (amount("minValue"), amount("maxValue")) {
+                (Some(min), Some(max)) => format!("{}-{}", min, max),
+                (min, max) => amount("value").or(min).or(max)?,
+            };
+            (range, amount("unitText"))
+        }
+        value => (structured::value_text(value)?, None),
+    };
+    let mut text = range;
+    if let Some(currency) = amount.get("currency").and_then(structured::value_text) {
+        write!(text, " {}", currency).ok()?;
+    }
+    if let Some(unit) = unit {
+        write!(text, "/{}", unit).ok()?;
+    }
+    Some(text)
+}
+
+pub fn format_job_posting(job: &JobPosting, url: &str) -> Result<String> {
+    let mut output = String::new();
+    writeln!(output, "# {}", job.title)?;
+    writeln!(output)?;
+    let details = [
+        ("Company", &job.company),
+        ("Location", &job.location),
+        ("Salary", &job.salary),
+        ("Posted", &job.date_posted),
+    ];
+    for (label, value) in details {
+        if let Some(value) = value {
+            writeln!(output, "- {}: {}", label, value)?;
+        }
+    }
+    writeln!(output)?;
+    writeln!(output, "<{}>", url)?;
+    Ok(output)
+}
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Serialize;
+
+use crate::format::{format_json, format_json_line, text_line, Format, Shape};
+use crate::markdown::content_root;
+use crate::readability::in_chrome;
+use crate::scrap::ScrapedWebpage;
+
+/// Text blocks of the main content, counted once when nested
+const BLOCKS: &str = "p, li, h1, h2, h3, h4, h5, h6, blockquote, td, dt, dd";
+
+/// English words too common to tell pages apart
+const STOP_WORDS: [&str; 126] = [
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "also",
+    "and",
+    "any",
+    "are",
+    "aren't",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "can",
+    "cannot",
+    "could",
+    "couldn't",
+    "did",
+    "didn't",
+    "does",
+    "doesn't",
+    "doing",
+    "don't",
+    "down",
+    "during",
+    "each",
+    "even",
+    "few",
+    "for",
+    "from",
+    "further",
+    "get",
+    "got",
+    "had",
+    "has",
+    "hasn't",
+    "have",
+    "haven't",
+    "having",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i'm",
+    "into",
+    "isn't",
+    "it's",
+    "its",
+    "itself",
+    "just",
+    "let's",
+    "like",
+    "may",
+    "more",
+    "most",
+    "much",
+    "must",
+    "myself",
+    "not",
+    "now",
+    "off",
+    "once",
+    "one",
+    "only",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "same",
+    "she",
+    "should",
+    "some",
+    "such",
+    "than",
+    "that",
+    "that's",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "there's",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "too",
+    "under",
+    "until",
+    "use",
+    "used",
+    "very",
+    "was",
+    "wasn't",
+    "way",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "why",
+    "will",
+    "with",
+    "won't",
+    "would",
+    "you",
+    "your",
+    "yours",
+];
+
+/// Terms and bigrams of the pages, the most frequent first
+#[derive(Debug, Serialize)]
+pub struct KeywordReport {
+    pub pages: usize,
+    pub terms: Vec<Keyword>,
+    pub bigrams: Vec<Keyword>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Keyword {
+    pub term: String,
+    /// Occurrences over all the pages
+    pub count: usize,
+    /// Pages using it
+    pub pages: usize,
+    /// Occurrences per page, the most first
+    pub frequencies: Vec<PageFrequency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageFrequency {
+    pub url: String,
+    pub count: usize,
+}
+
+/// A json line of a term or a bigram
+#[derive(Serialize)]
+struct KeywordLine<'a> {
+    kind: &'static str,
+    #[serde(flatten)]
+    keyword: &'a Keyword,
+}
+
+/// The `top` terms and bigrams of the main content of pages, stop words left out
+pub fn analyze_keywords(pages: &[ScrapedWebpage], top: usize) -> KeywordReport {
+    let mut terms = Counter::default();
+    let mut bigrams = Counter::default();
+    for (index, page) in pages.iter().enumerate() {
+        for segment in content_segments(&Html::parse_document(&page.html)) {
+            let words: Vec<&str> = segment
+                .iter()
+                .map(String::as_str)
+                .filter(|word| is_term(word))
+                .collect();
+            for word in &words {
+                terms.add(word, index);
+            }
+            // only words next to each other in the text make a bigram
+            for pair in segment.windows(2) {
+                if is_term(&pair[0]) && is_term(&pair[1]) {
+                    bigrams.add(&format!("{} {}", pair[0], pair[1]), index);
+                }
+            }
+        }
+    }
+    KeywordReport {
+        pages: pages.len(),
+        terms: terms.top(top, pages),
+        bigrams: bigrams.top(top, pages),
+    }
+}
+
+/// Occurrences of terms per page index
+#[derive(Default)]
+struct Counter(HashMap<String, HashMap<usize, usize>>);
+
+impl Counter {
+    fn add(&mut self, term: &str, page: usize) {
+        *self
+            .0
+            .entry(term.to_string())
+            .or_default()
+            .entry(page)
+            .or_default() += 1;
+    }
+
+    fn top(self, top: usize, pages: &[ScrapedWebpage]) -> Vec<Keyword> {
+        let mut keywords: Vec<Keyword> = self
+            .0
+            .into_iter()
+            .map(|(term, counts)| {
+                let mut counts: Vec<(usize, usize)> = counts.into_iter().collect();
+                counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                Keyword {
+                    term,
+                    count: counts.iter().map(|(_, count)| count).sum(),
+                    pages: counts.len(),
+                    frequencies: counts
+                        .into_iter()
+                        .map(|(page, count)| PageFrequency {
+                            url: pages[page].url.clone(),
+                            count,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        keywords.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.pages.cmp(&a.pages))
+                .then(a.term.cmp(&b.term))
+        });
+        keywords.truncate(top);
+        keywords
+    }
+}
+
+/// Lowercased words of the main content, in runs not broken by punctuation
+pub fn content_segments(document: &Html) -> Vec<Vec<String>> {
+    let Some(root) = content_root(document) else {
+        return vec![];
+    };
+    let selector = Selector::parse(BLOCKS).expect("valid selector");
+    let mut segments = vec![];
+    for block in root.select(&selector) {
+        if in_chrome(block, root) || in_block(block, root, &selector) {
+            continue;
+        }
+        let mut text = String::new();
+        block_text(block, &selector, &mut text);
+        for segment in text.split(|c: char| {
+            c.is_ascii_punctuation() && !matches!(c, '\'' | '-') || matches!(c, '\n' | '“' | '”')
+        }) {
+            let words: Vec<String> = segment
+                .split(|c: char| !c.is_alphanumeric() && !matches!(c, '\'' | '’' | '-'))
+                .map(|word| word.trim_matches(['\'', '’', '-']).replace('’', "'"))
+                .map(|word| word.to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect();
+            if !words.is_empty() {
+                segments.push(words);
+            }
+        }
+    }
+    segments
+}
+
+/// Text of a block, its nested blocks and `<br>` on their own lines
+fn block_text(element: ElementRef, blocks: &Selector, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(content) => text.push_str(content),
+            Node::Element(child_element) if child_element.name() == "br" => text.push('\n'),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let block = blocks.matches(&child);
+                if block {
+                    text.push('\n');
+                }
+                block_text(child, blocks, text);
+                if block {
+                    text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether an ancestor is itself a text block, which counts its words
+fn in_block(element: ElementRef, root: ElementRef, selector: &Selector) -> bool {
+    element
+        .ancestors()
+        .take_while(|ancestor| ancestor.id() != root.id())
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| selector.matches(&ancestor))
+}
+
+/// Words of three letters or more, neither stop words nor numbers
+fn is_term(word: &str) -> bool {
+    word.chars().filter(|c| c.is_alphabetic()).count() >= 3 && !STOP_WORDS.contains(&word)
+}
+
+/// Tables of the terms and bigrams, with the pages using them most; a json report, or a
+/// json or text line per keyword
+pub fn format_keywords(report: &KeywordReport, format: Format, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    let kinds = [("term", &report.terms), ("bigram", &report.bigrams)];
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(report, shape)?)?,
+        Format::Jsonl => {
+            for (kind, keywords) in kinds {
+                for keyword in keywords {
+                    writeln!(
+                        output,
+                        "{}",
+                        format_json_line(&KeywordLine { kind, keyword }, shape)?
+                    )?;
+                }
+            }
+        }
+        Format::Text => {
+            for (kind, keywords) in kinds {
+                for keyword in keywords {
+                    let frequencies: Vec<String> = keyword
+                        .frequencies
+                        .iter()
+                        .map(|frequency| format!("{}={}", frequency.url, frequency.count))
+                        .collect();
+                    let line = text_line(&[
+                        kind,
+                        &keyword.term,
+                        &keyword.count.to_string(),
+                        &keyword.pages.to_string(),
+                        &frequencies.join(" "),
+                    ]);
+                    writeln!(output, "{}", line)?;
+                }
+            }
+        }
+        Format::Markdown => {
+            for (title, keywords) in [("Terms", &report.terms), ("Bigrams", &report.bigrams)] {
+                if !output.is_empty() {
+                    writeln!(output)?;
+                }
+                writeln!(output, "## {}", title)?;
+                writeln!(output)?;
+                writeln!(
+                    output,
+                    "| term | count | pages of {} | most in |",
+                    report.pages
+                )?;
+                writeln!(output, "| --- | ---: | ---: | --- |")?;
+                for keyword in keywords {
+                    writeln!(
+                        output,
+                        "| {} | {} | {} | {} |",
+                        keyword.term.replace('|', "\\|"),
+                        keyword.count,
+                        keyword.pages,
+                        most_in(keyword)
+                    )?;
+                }
+            }
+        }
+        _ => bail!("--analyze keywords applies to the markdown, json, jsonl and text formats"),
+    }
+    Ok(output)
+}
+
+/// The three pages using a keyword most, and how many others
+fn most_in(keyword: &Keyword) -> String {
+    let mut pages: Vec<String> = keyword
+        .frequencies
+        .iter()
+        .take(3)
+        .map(|frequency| format!("{} ({})", frequency.url, frequency.count))
+        .collect();
+    if keyword.frequencies.len() > 3 {
+        pages.push(format!("{} more", keyword.frequencies.len() - 3));
+    }
+    pages.join(", ")
+}
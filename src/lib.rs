@@ -0,0 +1,110 @@
+//! Scrape metadata of web pages: title, description, structured data, forms, links
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use scrapr::{grab_url, ScrapOptions, Scraped};
+//!
+//! if let Scraped::Webpage(page) = grab_url("https://example.com", &ScrapOptions::default()).await? {
+//!     println!("{}: {:?}", page.title, page.description);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The link store is behind the `store` feature and page templates behind `templates`.
+//! The default `cli` feature adds the `scrapr` binary with its private plumbing (daemon,
+//! watch, sync, notifiers, secrets) and clap support of the option enums.
+
+pub mod activitypub;
+pub mod altaudit;
+pub mod analysis;
+pub mod antibot;
+pub mod article;
+pub mod author;
+pub mod bandwidth;
+pub mod batch;
+pub mod cache;
+pub mod citation;
+pub mod cleanup;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod compare;
+#[cfg(feature = "cli")]
+mod config;
+#[cfg(feature = "cli")]
+mod daemon;
+pub mod dns;
+pub mod domains;
+pub mod download;
+pub mod duplicates;
+#[cfg(feature = "cli")]
+mod editor;
+pub mod event;
+#[cfg(feature = "cli")]
+mod export;
+pub mod filter;
+#[cfg(feature = "cli")]
+mod fixtures;
+#[cfg(feature = "cli")]
+mod flow;
+pub mod format;
+pub mod forms;
+#[cfg(feature = "store")]
+pub mod history;
+pub mod homograph;
+#[cfg(feature = "cli")]
+mod hook;
+pub mod imagemeta;
+pub mod impersonate;
+pub mod jobposting;
+pub mod keywords;
+pub mod lint;
+pub mod markdown;
+#[cfg(feature = "cli")]
+mod notify;
+pub mod opengraph;
+pub mod paginate;
+#[cfg(feature = "cli")]
+mod paths;
+pub mod pluck;
+pub mod podcast;
+pub mod price;
+pub mod published;
+pub mod rating;
+pub mod readability;
+pub mod recipe;
+pub mod redirectmap;
+pub mod relations;
+pub mod resources;
+pub mod results;
+pub mod retry;
+pub mod reviews;
+#[cfg(feature = "cli")]
+mod s3;
+pub mod scrap;
+#[cfg(feature = "cli")]
+mod secret;
+#[cfg(feature = "cli")]
+mod service;
+pub mod simhash;
+pub mod sitemap;
+mod stamp;
+pub mod stats;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod structured;
+#[cfg(feature = "cli")]
+mod sync;
+pub mod tags;
+#[cfg(feature = "templates")]
+pub mod template;
+pub mod timeout;
+pub mod transfer;
+pub mod twitter;
+pub mod units;
+pub mod video;
+#[cfg(feature = "cli")]
+mod watch;
+pub mod xml;
+
+pub use scrap::{grab_url, ScrapOptions, Scraped, ScrapedWebpage, ScraperError};
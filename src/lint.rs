@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::format::Format;
+use crate::scrap::ScrapedWebpage;
+use crate::structured;
+
+/// Page field checked by a lint rule
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintField {
+    Title,
+    Description,
+    Language,
+    Canonical,
+    OgTitle,
+    OgDescription,
+    OgImage,
+    TwitterCard,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Warning,
+    #[default]
+    Error,
+}
+
+/// Checks of one field, as `[lint.FIELD]` in TOML
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldRule {
+    #[serde(default)]
+    pub required: bool,
+    /// Length in characters
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// The value, an url, must be the linted url
+    #[serde(default)]
+    pub matches_url: bool,
+    #[serde(default)]
+    pub level: Level,
+}
+
+pub type LintRules = BTreeMap<LintField, FieldRule>;
+
+/// Title of 60 characters at most, description of 50 to 160, open graph image
+/// and a canonical url matching the page
+pub fn default_rules() -> LintRules {
+    BTreeMap::from([
+        (
+            LintField::Title,
+            FieldRule {
+                required: true,
+                max_length: Some(60),
+                ..FieldRule::default()
+            },
+        ),
+        (
+            LintField::Description,
+            FieldRule {
+                required: true,
+                min_length: Some(50),
+                max_length: Some(160),
+                level: Level::Warning,
+                ..FieldRule::default()
+            },
+        ),
+        (
+            LintField::Language,
+            FieldRule {
+                required: true,
+                level: Level::Warning,
+                ..FieldRule::default()
+            },
+        ),
+        (
+            LintField::Canonical,
+            FieldRule {
+                required: true,
+                matches_url: true,
+                level: Level::Warning,
+                ..FieldRule::default()
+            },
+        ),
+        (
+            LintField::OgImage,
+            FieldRule {
+                required: true,
+                ..FieldRule::default()
+            },
+        ),
+    ])
+}
+
+/// `[lint]` rules of a TOML file, a `lint` table or the rules at the top level
+pub fn read_rules(path: &Path) -> Result<LintRules> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Cannot read lint rules {}: {}", path.display(), e))?;
+    let mut table: toml::Table = toml::from_str(&content)?;
+    let rules = match table.remove("lint") {
+        Some(rules) => rules,
+        None => toml::Value::Table(table),
+    };
+    Ok(rules.try_into()?)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub level: Level,
+    pub field: LintField,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintReport {
+    pub url: String,
+    pub findings: Vec<Finding>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.level == Level::Error)
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.level == Level::Warning)
+    }
+}
+
+/// Check the fields of a page against rules, `url` being the linted url
+pub fn lint(page: &ScrapedWebpage, url: &str, rules: &LintRules) -> LintReport {
+    let document = Html::parse_document(&page.html);
+    let mut findings = vec![];
+    for (field, rule) in rules {
+        let value = field_value(*field, page, &document);
+        let mut finding = |message: String| {
+            findings.push(Finding {
+                level: rule.level,
+                field: *field,
+                message,
+            })
+        };
+        let Some(value) = value else {
+            if rule.required {
+                finding("missing".to_string());
+            }
+            continue;
+        };
+        let length = value.chars().count();
+        if let Some(min_length) = rule.min_length.filter(|min_length| length < *min_length) {
+            finding(format!("{} characters, less than {}", length, min_length));
+        }
+        if let Some(max_length) = rule.max_length.filter(|max_length| length > *max_length) {
+            finding(format!("{} characters, more than {}", length, max_length));
+        }
+        if rule.matches_url && !same_url(&value, url) {
+            finding(format!("{} is not {}", value, url));
+        }
+    }
+    LintReport {
+        url: url.to_string(),
+        findings,
+    }
+}
+
+fn field_value(field: LintField, page: &ScrapedWebpage, document: &Html) -> Option<String> {
+    let value = match field {
+        LintField::Title => {
+            let selector = Selector::parse("title").expect("valid selector");
+            document
+                .select(&selector)
+                .next()
+                .map(|title| title.text().collect::<String>())
+        }
+        // the page description may come from open graph
+        LintField::Description => structured::meta_content(document, "description"),
+        LintField::Language => page.language.clone(),
+        LintField::Canonical => page.canonical_url.clone(),
+        LintField::OgTitle => page.open_graph.title.clone(),
+        LintField::OgDescription => page.open_graph.description.clone(),
+        LintField::OgImage => page.open_graph.image.clone(),
+        LintField::TwitterCard => page.twitter.card.clone(),
+    };
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn same_url(value: &str, url: &str) -> bool {
+    match (Url::parse(value), Url::parse(url)) {
+        (Ok(value), Ok(url)) => value == url,
+        _ => value == url,
+    }
+}
+
+pub fn format_lint(report: &LintReport, format: Format) -> Result<String> {
+    if format == Format::Json {
+        return Ok(format!("{}\n", serde_json::to_string_pretty(report)?));
+    }
+    let mut output = String::new();
+    for finding in &report.findings {
+        let level = match finding.level {
+            Level::Warning => "warning",
+            Level::Error => "error",
+        };
+        let field = serde_json::to_value(finding.field)?;
+        writeln!(
+            output,
+            "{}: {} {}: {}",
+            report.url,
+            level,
+            field.as_str().unwrap_or_default(),
+            finding.message
+        )?;
+    }
+    Ok(output)
+}
@@ -0,0 +1,325 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use url::Url;
+
+/// Elements left out of the content: code, chrome and controls
+pub(crate) const SKIPPED: [&str; 14] = [
+    "script", "style", "noscript", "template", "nav", "header", "footer", "aside", "form",
+    "button", "iframe", "svg", "select", "textarea",
+];
+
+const BLOCKS: [&str; 30] = [
+    "address",
+    "article",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+    "html",
+    "fieldset",
+];
+
+/// Hard line break of `<br>`
+const LINE_BREAK: &str = "\\\n";
+
+/// Markdown of the main content of a page (`<article>`, `<main>` or else `<body>`):
+/// headings, paragraphs, lists, quotes, code blocks, tables, links and images, the urls
+/// resolved against the page url
+pub fn html_to_markdown(html: &str, page_url: &str) -> String {
+    let document = Html::parse_document(html);
+    let Some(root) = content_root(&document) else {
+        return String::new();
+    };
+    element_to_markdown(root, page_url)
+}
+
+/// Markdown of the children of an element, the urls resolved against the page url
+pub(crate) fn element_to_markdown(root: ElementRef, page_url: &str) -> String {
+    let converter = Converter {
+        base: Url::parse(page_url).ok(),
+    };
+    let mut blocks = vec![];
+    converter.blocks(root, &mut blocks);
+    blocks.join("\n\n")
+}
+
+/// `<article>`, `<main>` or else `<body>` of a document
+pub(crate) fn content_root(document: &Html) -> Option<ElementRef<'_>> {
+    ["article", "main", "body"].iter().find_map(|name| {
+        let selector = Selector::parse(name).expect("valid selector");
+        document.select(&selector).next()
+    })
+}
+
+struct Converter {
+    base: Option<Url>,
+}
+
+impl Converter {
+    /// Blocks of the children of an element, runs of inline content being paragraphs
+    fn blocks(&self, element: ElementRef, blocks: &mut Vec<String>) {
+        let mut inline = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(text),
+                Node::Element(_) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    let name = child.value().name();
+                    if SKIPPED.contains(&name) {
+                        continue;
+                    }
+                    if BLOCKS.contains(&name) {
+                        push_paragraph(&mut inline, blocks);
+                        self.block(child, blocks);
+                    } else {
+                        inline.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        push_paragraph(&mut inline, blocks);
+    }
+
+    fn block(&self, element: ElementRef, blocks: &mut Vec<String>) {
+        let name = element.value().name();
+        let block = match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let text = tidy(&self.inline_children(element)).replace(LINE_BREAK, " ");
+                (!text.is_empty()).then(|| format!("{} {}", "#".repeat(level), text))
+            }
+            "p" | "dt" | "summary" | "figcaption" => {
+                Some(tidy(&self.inline_children(element))).filter(|text| !text.is_empty())
+            }
+            "ul" | "ol" => Some(self.list(element, name == "ol")).filter(|list| !list.is_empty()),
+            "pre" => Some(code_block(element)),
+            "blockquote" => {
+                let mut inner = vec![];
+                self.blocks(element, &mut inner);
+                let quoted: Vec<String> = inner
+                    .join("\n\n")
+                    .lines()
+                    .map(|line| format!("> {}", line).trim_end().to_string())
+                    .collect();
+                Some(quoted.join("\n")).filter(|quote| !quote.is_empty())
+            }
+            "hr" => Some("---".to_string()),
+            "table" => self.table(element),
+            _ => {
+                self.blocks(element, blocks);
+                None
+            }
+        };
+        blocks.extend(block);
+    }
+
+    /// Items of a list, their nested blocks indented under their marker
+    fn list(&self, element: ElementRef, ordered: bool) -> String {
+        let items = element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|child| child.value().name() == "li");
+        let mut lines = vec![];
+        for (index, item) in items.enumerate() {
+            let marker = match ordered {
+                true => format!("{}.", index + 1),
+                false => "-".to_string(),
+            };
+            let indent = " ".repeat(marker.len() + 1);
+            let mut blocks = vec![];
+            self.blocks(item, &mut blocks);
+            let content = blocks.join("\n");
+            let mut content_lines = content.lines();
+            lines.push(format!(
+                "{} {}",
+                marker,
+                content_lines.next().unwrap_or_default()
+            ));
+            for line in content_lines {
+                lines.push(format!("{}{}", indent, line).trim_end().to_string());
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Pipe table, the first row being the header
+    fn table(&self, element: ElementRef) -> Option<String> {
+        let selector = Selector::parse("tr").expect("valid selector");
+        let cells = Selector::parse("th, td").expect("valid selector");
+        let rows: Vec<Vec<String>> = element
+            .select(&selector)
+            .map(|row| {
+                row.select(&cells)
+                    .map(|cell| {
+                        tidy(&self.inline_children(cell))
+                            .replace(LINE_BREAK, " ")
+                            .replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .filter(|row: &Vec<String>| !row.is_empty())
+            .collect();
+        let columns = rows.iter().map(Vec::len).max()?;
+        let line = |row: &[String]| {
+            let mut cells = row.to_vec();
+            cells.resize(columns, String::new());
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+        lines.extend(rows[1..].iter().map(|row| line(row)));
+        Some(lines.join("\n"))
+    }
+
+    fn inline_children(&self, element: ElementRef) -> String {
+        let mut text = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(content) => text.push_str(content),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        text.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+
+    fn inline(&self, element: ElementRef) -> String {
+        let name = element.value().name();
+        if SKIPPED.contains(&name) {
+            return String::new();
+        }
+        let wrap = |marker: &str| {
+            let text = tidy(&self.inline_children(element));
+            match text.is_empty() {
+                true => String::new(),
+                false => format!("{}{}{}", marker, text, marker),
+            }
+        };
+        match name {
+            "br" => LINE_BREAK.to_string(),
+            "strong" | "b" => wrap("**"),
+            "em" | "i" => wrap("*"),
+            "del" | "s" => wrap("~~"),
+            "code" | "kbd" | "samp" => {
+                let code: String = element.text().collect();
+                let code = code.trim();
+                let fence = if code.contains('`') { "``" } else { "`" };
+                match code.is_empty() {
+                    true => String::new(),
+                    false => format!("{}{}{}", fence, code, fence),
+                }
+            }
+            "a" => {
+                let text = tidy(&self.inline_children(element));
+                match element
+                    .value()
+                    .attr("href")
+                    .and_then(|href| self.resolve(href))
+                {
+                    Some(href) if !text.is_empty() => format!("[{}]({})", text, href),
+                    _ => text,
+                }
+            }
+            "img" => {
+                let alt = element.value().attr("alt").unwrap_or_default().trim();
+                match element
+                    .value()
+                    .attr("src")
+                    .and_then(|src| self.resolve(src))
+                {
+                    Some(src) => format!("![{}]({})", alt, src),
+                    None => String::new(),
+                }
+            }
+            _ => self.inline_children(element),
+        }
+    }
+
+    /// Absolute http(s) url, anchors and scripts aside
+    fn resolve(&self, href: &str) -> Option<String> {
+        let href = href.trim();
+        if href.is_empty() || href.starts_with('#') {
+            return None;
+        }
+        let url = match &self.base {
+            Some(base) => base.join(href).ok()?,
+            None => Url::parse(href).ok()?,
+        };
+        matches!(url.scheme(), "http" | "https" | "mailto").then(|| url.to_string())
+    }
+}
+
+fn push_paragraph(inline: &mut String, blocks: &mut Vec<String>) {
+    let paragraph = tidy(inline);
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+    inline.clear();
+}
+
+/// Collapse the whitespace of each line, lines coming from `<br>`
+fn tidy(text: &str) -> String {
+    let lines: Vec<String> = text
+        .split(LINE_BREAK)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    match first.zip(last) {
+        Some((first, last)) => lines[first..=last].join(LINE_BREAK),
+        None => String::new(),
+    }
+}
+
+/// Fenced code block, with the language of a `language-*` or `lang-*` class
+fn code_block(element: ElementRef) -> String {
+    let code: String = element.text().collect();
+    let selector = Selector::parse("code[class]").expect("valid selector");
+    let language = element
+        .select(&selector)
+        .next()
+        .and_then(|code| code.value().attr("class"))
+        .and_then(|class| {
+            class.split_whitespace().find_map(|class| {
+                class
+                    .strip_prefix("language-")
+                    .or(class.strip_prefix("lang-"))
+            })
+        })
+        .unwrap_or_default();
+    let fence = if code.contains("```") { "~~~" } else { "```" };
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        language,
+        code.trim_matches('\n'),
+        fence
+    )
+}
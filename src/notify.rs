@@ -0,0 +1,314 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::scrap::{with_network, ScrapOptions};
+use crate::secret::resolve_secrets;
+
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+const NTFY_SERVER: &str = "https://ntfy.sh";
+
+/// A change worth telling the user about
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+}
+
+/// `[smtp]` section of `config.toml`, used by the `email` notifier
+///
+/// Subject and body are templates where `{{title}}`, `{{body}}` and `{{url}}`
+/// are replaced by the notification.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpConfig {
+    pub server: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    /// May reference a keyring secret, `{{secret:NAME}}`
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_subject")]
+    pub subject: String,
+    #[serde(default = "default_body")]
+    pub body: String,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    /// Plain text, for local relays only
+    None,
+}
+
+fn default_subject() -> String {
+    "[scrapr] {{title}}".to_string()
+}
+
+fn default_body() -> String {
+    "{{body}}\n\n{{url}}\n".to_string()
+}
+
+/// `[matrix]` section of `config.toml`, used by the `matrix` notifier
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MatrixConfig {
+    /// e.g. `https://matrix.org`
+    pub homeserver: String,
+    /// Room id, e.g. `!abcdef:matrix.org`
+    pub room: String,
+    /// May reference a keyring secret, `{{secret:NAME}}`
+    pub access_token: String,
+}
+
+/// `[gotify]` section of `config.toml`, used by the `gotify` notifier
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GotifyConfig {
+    pub server: String,
+    /// Application token, may reference a keyring secret
+    pub token: String,
+    pub priority: Option<u8>,
+}
+
+/// Where notifications are sent, from a `--notify` or job `notify` spec
+#[derive(Debug, Clone)]
+pub enum Notifier {
+    Desktop,
+    Email(Box<SmtpConfig>),
+    /// Incoming webhook url
+    Slack(String),
+    /// Webhook url
+    Discord(String),
+    Matrix(MatrixConfig),
+    /// Topic url
+    Ntfy(String),
+    Gotify(GotifyConfig),
+}
+
+impl Notifier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Notifier::Desktop => "desktop",
+            Notifier::Email(_) => "email",
+            Notifier::Slack(_) => "slack",
+            Notifier::Discord(_) => "discord",
+            Notifier::Matrix(_) => "matrix",
+            Notifier::Ntfy(_) => "ntfy",
+            Notifier::Gotify(_) => "gotify",
+        }
+    }
+
+    /// Send through the proxy and dns cache of `options`
+    pub async fn send(&self, notification: &Notification, options: &ScrapOptions) -> Result<()> {
+        match self {
+            Notifier::Desktop => {
+                notify_rust::Notification::new()
+                    .appname("scrapr")
+                    .summary(&notification.title)
+                    .body(&format!("{}\n{}", notification.body, notification.url))
+                    .show()?;
+            }
+            Notifier::Email(smtp) => send_email(smtp, notification).await?,
+            Notifier::Slack(webhook) => {
+                let text = format!(
+                    "*{}*\n{}\n<{}>",
+                    notification.title, notification.body, notification.url
+                );
+                post_json(webhook, &json!({ "text": text }), options).await?;
+            }
+            Notifier::Discord(webhook) => {
+                let content = format!(
+                    "**{}**\n{}\n{}",
+                    notification.title, notification.body, notification.url
+                );
+                post_json(webhook, &json!({ "content": content }), options).await?;
+            }
+            Notifier::Matrix(matrix) => send_matrix(matrix, notification, options).await?,
+            Notifier::Ntfy(topic_url) => {
+                // published as json, headers cannot carry a non-ascii title
+                let (server, topic) = topic_url
+                    .trim_end_matches('/')
+                    .rsplit_once('/')
+                    .ok_or(anyhow!("No topic in ntfy url {}", topic_url))?;
+                let payload = json!({
+                    "topic": topic,
+                    "title": notification.title,
+                    "message": notification.body,
+                    "click": notification.url,
+                });
+                post_json(server, &payload, options).await?;
+            }
+            Notifier::Gotify(gotify) => {
+                let message = format!("{}\n{}", notification.body, notification.url);
+                client(options)?
+                    .post(format!("{}/message", gotify.server.trim_end_matches('/')))
+                    .header("X-Gotify-Key", resolve_secrets(&gotify.token)?)
+                    .json(&json!({
+                        "title": notification.title,
+                        "message": message,
+                        "priority": gotify.priority.unwrap_or(5),
+                    }))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Notifiers of specs: `desktop`, `email` (needs `[smtp]`), `slack:WEBHOOK_URL`,
+/// `discord:WEBHOOK_URL`, `matrix` (needs `[matrix]`), `ntfy:TOPIC` or `ntfy:TOPIC_URL`,
+/// `gotify` (needs `[gotify]`); urls may reference secrets
+pub fn notifiers(specs: &[String], config: &Config) -> Result<Vec<Notifier>> {
+    specs
+        .iter()
+        .map(|spec| match spec.split_once(':') {
+            Some(("slack", webhook)) => Ok(Notifier::Slack(resolve_secrets(webhook)?)),
+            Some(("discord", webhook)) => Ok(Notifier::Discord(resolve_secrets(webhook)?)),
+            Some(("ntfy", topic)) if topic.starts_with("http") => {
+                Ok(Notifier::Ntfy(resolve_secrets(topic)?))
+            }
+            Some(("ntfy", topic)) => Ok(Notifier::Ntfy(format!("{}/{}", NTFY_SERVER, topic))),
+            _ => named_notifier(spec, config),
+        })
+        .collect()
+}
+
+fn named_notifier(spec: &str, config: &Config) -> Result<Notifier> {
+    let missing = |section: &str| {
+        anyhow!(
+            "The {} notifier requires a [{}] section in config.toml",
+            spec,
+            section
+        )
+    };
+    match spec {
+        "desktop" => Ok(Notifier::Desktop),
+        "email" => config
+            .smtp
+            .clone()
+            .map(|smtp| Notifier::Email(Box::new(smtp)))
+            .ok_or(missing("smtp")),
+        "matrix" => config
+            .matrix
+            .clone()
+            .map(Notifier::Matrix)
+            .ok_or(missing("matrix")),
+        "gotify" => config
+            .gotify
+            .clone()
+            .map(Notifier::Gotify)
+            .ok_or(missing("gotify")),
+        _ => Err(anyhow!(
+            "Unknown notifier {}, expected desktop, email, slack:URL, discord:URL, matrix, ntfy:TOPIC or gotify",
+            spec
+        )),
+    }
+}
+
+/// Send to every notifier, a failing one does not stop the others
+pub async fn notify_all(
+    notifiers: &[Notifier],
+    notification: &Notification,
+    options: &ScrapOptions,
+) {
+    for notifier in notifiers {
+        if let Err(e) = notifier.send(notification, options).await {
+            eprintln!("{} notification failed: {}", notifier.name(), e);
+        }
+    }
+}
+
+async fn send_email(smtp: &SmtpConfig, notification: &Notification) -> Result<()> {
+    let render = |template: &str| {
+        template
+            .replace("{{title}}", &notification.title)
+            .replace("{{body}}", &notification.body)
+            .replace("{{url}}", &notification.url)
+    };
+    let mut message = Message::builder()
+        .from(smtp.from.parse::<Mailbox>()?)
+        .subject(render(&smtp.subject));
+    for to in &smtp.to {
+        message = message.to(to.parse::<Mailbox>()?);
+    }
+    let message = message.body(render(&smtp.body))?;
+
+    let mut transport = match smtp.security {
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.server)?
+        }
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.server)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.server),
+    };
+    if let Some(port) = smtp.port {
+        transport = transport.port(port);
+    }
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        transport = transport.credentials(Credentials::new(
+            username.clone(),
+            resolve_secrets(password)?,
+        ));
+    }
+    // smtp connects directly, lettre has no proxy support
+    transport.build().send(message).await?;
+    Ok(())
+}
+
+/// Client of the notifier apis, on the network of the scraping
+fn client(options: &ScrapOptions) -> Result<Client> {
+    Ok(with_network(Client::builder().timeout(NOTIFY_TIMEOUT), options).build()?)
+}
+
+async fn post_json(url: &str, payload: &Value, options: &ScrapOptions) -> Result<()> {
+    client(options)?
+        .post(url)
+        .json(payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Client-server api message, the transaction id keeps retries idempotent
+async fn send_matrix(
+    matrix: &MatrixConfig,
+    notification: &Notification,
+    options: &ScrapOptions,
+) -> Result<()> {
+    let transaction = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let url = format!(
+        "{}/_matrix/client/v3/rooms/{}/send/m.room.message/scrapr{}",
+        matrix.homeserver.trim_end_matches('/'),
+        url::form_urlencoded::byte_serialize(matrix.room.as_bytes()).collect::<String>(),
+        transaction
+    );
+    let body = format!(
+        "{}\n{}\n{}",
+        notification.title, notification.body, notification.url
+    );
+    client(options)?
+        .put(url)
+        .bearer_auth(resolve_secrets(&matrix.access_token)?)
+        .json(&json!({ "msgtype": "m.text", "body": body }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
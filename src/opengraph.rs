@@ -0,0 +1,36 @@
+use scraper::Html;
+use serde::Serialize;
+use url::Url;
+
+use crate::structured;
+
+/// Open graph properties of a page, flattened into its record as `og_*` fields
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OpenGraph {
+    #[serde(rename = "og_title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "og_description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Resolved against the page url
+    #[serde(rename = "og_image", skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(rename = "og_type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(rename = "og_site_name", skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+}
+
+pub fn extract_open_graph(document: &Html, page_url: &str) -> OpenGraph {
+    let meta = |key| structured::meta_content(document, key);
+    OpenGraph {
+        title: meta("og:title"),
+        description: meta("og:description"),
+        image: meta("og:image").or(meta("og:image:url")).map(|image| {
+            Url::parse(page_url)
+                .and_then(|base| base.join(&image))
+                .map_or(image, String::from)
+        }),
+        kind: meta("og:type"),
+        site_name: meta("og:site_name"),
+    }
+}
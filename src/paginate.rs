@@ -0,0 +1,47 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use url::Url;
+
+use crate::pluck::PluckPath;
+use crate::scrap::{grab_url, ScrapOptions, Scraped};
+
+/// Parse `next=<path>`, the path pointing to the next page url in each json page
+pub fn parse_next_path(paginate: &str) -> Result<PluckPath> {
+    let path = paginate.strip_prefix("next=").ok_or(anyhow!(
+        "Invalid pagination {}: expected next=<path>",
+        paginate
+    ))?;
+    Ok(PluckPath::parse(path)?)
+}
+
+/// Follow next page pointers of a json api, up to `max_pages` pages
+pub async fn grab_pages(
+    url: &str,
+    next: &PluckPath,
+    max_pages: usize,
+    options: &ScrapOptions,
+) -> Result<Vec<Value>> {
+    let mut pages = vec![];
+    let mut visited = vec![];
+    let mut current = Some(Url::parse(url)?);
+
+    while let Some(page_url) = current.take() {
+        if pages.len() >= max_pages || visited.contains(&page_url) {
+            break;
+        }
+        let page = match grab_url(page_url.as_str(), options).await? {
+            Scraped::Json(value) => value,
+            _ => return Err(anyhow!("Pagination requires JSON responses: {}", page_url)),
+        };
+        // next pointer may be relative to the current page
+        current = next
+            .select(&page)
+            .into_iter()
+            .find_map(Value::as_str)
+            .and_then(|next_url| page_url.join(next_url).ok());
+        visited.push(page_url);
+        pages.push(page);
+    }
+
+    Ok(pages)
+}
@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+/// Where scrapr keeps its on-disk state: XDG directories, or all under `--data-dir`
+#[derive(Debug, Clone, Serialize)]
+pub struct Paths {
+    pub config: PathBuf,
+    pub cache: PathBuf,
+    pub data: PathBuf,
+    pub plugins: PathBuf,
+}
+
+impl Paths {
+    pub fn resolve(data_dir: Option<&Path>) -> Result<Self> {
+        if let Some(root) = data_dir {
+            return Ok(Self {
+                config: root.join("config"),
+                cache: root.join("cache"),
+                data: root.join("data"),
+                plugins: root.join("plugins"),
+            });
+        }
+        let missing = |kind: &str| anyhow!("No {} directory on this system, use --data-dir", kind);
+        let data = dirs::data_dir().ok_or(missing("data"))?.join("scrapr");
+        Ok(Self {
+            config: dirs::config_dir().ok_or(missing("config"))?.join("scrapr"),
+            cache: dirs::cache_dir().ok_or(missing("cache"))?.join("scrapr"),
+            plugins: data.join("plugins"),
+            data,
+        })
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config.join("config.toml")
+    }
+
+    pub fn profiles(&self) -> PathBuf {
+        self.config.join("profiles")
+    }
+
+    pub fn http_cache(&self) -> PathBuf {
+        self.cache.join("http")
+    }
+
+    pub fn favicons(&self) -> PathBuf {
+        self.cache.join("favicons")
+    }
+}
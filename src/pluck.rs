@@ -0,0 +1,92 @@
+use serde_json::Value;
+use thiserror::Error;
+
+/// One step of a jq-like path: `.name`, `[2]` or `[]`
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+    Iterate,
+}
+
+#[derive(Debug, Error)]
+pub enum PluckError {
+    #[error("Invalid pluck path {0}: {1}")]
+    InvalidPath(String, String),
+}
+
+/// Parsed path like `.data.items[].title`
+#[derive(Debug, Clone)]
+pub struct PluckPath {
+    segments: Vec<Segment>,
+}
+
+impl PluckPath {
+    pub fn parse(path: &str) -> Result<Self, PluckError> {
+        let invalid = |reason: &str| PluckError::InvalidPath(path.to_string(), reason.to_string());
+        let mut segments = vec![];
+        let mut chars = path.trim().chars().peekable();
+        if chars.peek() != Some(&'.') {
+            return Err(invalid("path must start with '.'"));
+        }
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        let name: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                        segments.push(Segment::Field(name));
+                        continue;
+                    }
+                    let mut name = String::new();
+                    while let Some(c) = chars.peek() {
+                        if *c == '.' || *c == '[' {
+                            break;
+                        }
+                        name.push(*c);
+                        chars.next();
+                    }
+                    if !name.is_empty() {
+                        segments.push(Segment::Field(name));
+                    }
+                }
+                '[' => {
+                    let inner: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    if inner.is_empty() {
+                        segments.push(Segment::Iterate);
+                    } else {
+                        let index = inner
+                            .trim()
+                            .parse()
+                            .map_err(|_| invalid("index must be a positive number"))?;
+                        segments.push(Segment::Index(index));
+                    }
+                }
+                _ => return Err(invalid("unexpected character")),
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    /// Every value matching the path (missing fields are skipped)
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match segment {
+                        Segment::Field(name) => value.get(name).into_iter().collect(),
+                        Segment::Index(index) => value.get(index).into_iter().collect(),
+                        Segment::Iterate => match value {
+                            Value::Array(items) => items.iter().collect(),
+                            Value::Object(map) => map.values().collect(),
+                            _ => vec![],
+                        },
+                    }
+                })
+                .collect();
+        }
+        current
+    }
+}
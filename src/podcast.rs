@@ -0,0 +1,157 @@
+use std::fmt::Write;
+
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use serde_json::Value;
+use sxd_document::parser;
+use sxd_xpath::{evaluate_xpath, Context, Factory};
+use url::Url;
+
+use crate::format::{format_json, Format, Shape};
+use crate::scrap::ScrapedWebpage;
+use crate::structured::{self, find_entity};
+
+/// Audio episode of a podcast page or feed
+#[derive(Debug, Clone, Serialize)]
+pub struct PodcastEpisode {
+    pub episode: String,
+    pub show: Option<String>,
+    pub audio_url: String,
+    pub duration_secs: Option<u64>,
+}
+
+/// Episode from schema.org PodcastEpisode/AudioObject data, or else an `og:audio`
+/// or `<audio>` source, resolved against the page url
+pub fn extract_episode(
+    document: &Html,
+    entities: &[Value],
+    page_url: &str,
+) -> Option<PodcastEpisode> {
+    let episode = find_entity(entities, &["PodcastEpisode", "RadioEpisode"]);
+    let audio = episode
+        .and_then(|episode| episode.get("associatedMedia").or(episode.get("audio")))
+        .or(find_entity(entities, &["AudioObject"]));
+    let audio_url = audio
+        .and_then(|audio| structured::text(audio, "contentUrl"))
+        .or(structured::meta_content(document, "og:audio"))
+        .or_else(|| audio_source(document))
+        .map(|audio_url| {
+            Url::parse(page_url)
+                .and_then(|base| base.join(&audio_url))
+                .map_or(audio_url, String::from)
+        })?;
+    let duration_secs = [episode, audio]
+        .into_iter()
+        .flatten()
+        .find_map(|entity| structured::text(entity, "duration"))
+        .and_then(|duration| parse_duration(&duration));
+    let title = || {
+        let selector = Selector::parse("title").expect("valid selector");
+        let title: String = document.select(&selector).next()?.text().collect();
+        Some(title.trim().to_string())
+    };
+    Some(PodcastEpisode {
+        episode: episode
+            .and_then(|episode| structured::text(episode, "name"))
+            .or_else(title)
+            .unwrap_or("Episode".to_string()),
+        show: episode.and_then(|episode| structured::text(episode, "partOfSeries")),
+        audio_url,
+        duration_secs,
+    })
+}
+
+fn audio_source(document: &Html) -> Option<String> {
+    let selector = Selector::parse("audio[src], audio source[src]").expect("valid selector");
+    document
+        .select(&selector)
+        .next()
+        .and_then(|audio| audio.value().attr("src"))
+        .map(str::to_string)
+}
+
+/// Items of a RSS feed with an audio enclosure
+pub fn feed_episodes(content: &str) -> Vec<PodcastEpisode> {
+    let Ok(package) = parser::parse(content) else {
+        return vec![];
+    };
+    let document = package.as_document();
+    let show = evaluate_xpath(&document, "string(/rss/channel/title)")
+        .map(|value| value.string())
+        .ok()
+        .filter(|show| !show.is_empty());
+    let Ok(sxd_xpath::Value::Nodeset(items)) = evaluate_xpath(&document, "//item") else {
+        return vec![];
+    };
+    let factory = Factory::new();
+    let context = Context::new();
+    let text = |node, expression: &str| {
+        let xpath = factory.build(expression).ok()??;
+        let value = xpath.evaluate(&context, node).ok()?.string();
+        Some(value.trim().to_string()).filter(|value| !value.is_empty())
+    };
+    items
+        .document_order()
+        .into_iter()
+        .filter_map(|item| {
+            Some(PodcastEpisode {
+                episode: text(item, "string(title)").unwrap_or("Episode".to_string()),
+                show: show.clone(),
+                audio_url: text(item, "string(enclosure[starts-with(@type, 'audio')]/@url)")
+                    .or(text(item, "string(enclosure/@url)"))?,
+                duration_secs: text(item, "string(*[local-name() = 'duration'])")
+                    .and_then(|duration| parse_duration(&duration)),
+            })
+        })
+        .collect()
+}
+
+/// Episodes of a scraped page: its own episode, or those of a feed
+pub fn episodes(page: &ScrapedWebpage) -> Vec<PodcastEpisode> {
+    match &page.podcast {
+        Some(episode) => vec![episode.clone()],
+        None => feed_episodes(&page.html),
+    }
+}
+
+/// Seconds of `PT45M12S`, `1:02:03`, `45:12` or `2712`
+fn parse_duration(text: &str) -> Option<u64> {
+    if let Some(duration) = structured::parse_iso_duration(text) {
+        return Some(duration.as_secs());
+    }
+    text.split(':').try_fold(0, |seconds, part| {
+        Some(seconds * 60 + part.trim().parse::<u64>().ok()?)
+    })
+}
+
+pub fn format_episodes(
+    episodes: &[PodcastEpisode],
+    format: Format,
+    shape: &Shape,
+) -> Result<String> {
+    if episodes.is_empty() {
+        return Err(anyhow!("No podcast episode found"));
+    }
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(&episodes, shape)?)?,
+        _ => {
+            for episode in episodes {
+                write!(output, "- [{}]({})", episode.episode, episode.audio_url)?;
+                if let Some(show) = &episode.show {
+                    write!(output, " - {}", show)?;
+                }
+                if let Some(duration) = episode.duration_secs {
+                    write!(
+                        output,
+                        " ({})",
+                        humantime::format_duration(std::time::Duration::from_secs(duration))
+                    )?;
+                }
+                writeln!(output)?;
+            }
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,178 @@
+use scraper::{ElementRef, Html, Selector};
+use serde_json::Value;
+
+use crate::structured::{self, find_entity};
+
+/// Currency symbols and their ISO 4217 codes, longest first
+const CURRENCY_SYMBOLS: [(&str, &str); 10] = [
+    ("US$", "USD"),
+    ("CA$", "CAD"),
+    ("A$", "AUD"),
+    ("CHF", "CHF"),
+    ("€", "EUR"),
+    ("$", "USD"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("₽", "RUB"),
+];
+
+/// Codes recognized in price texts
+const CURRENCY_CODES: [&str; 15] = [
+    "USD", "EUR", "GBP", "JPY", "CHF", "CAD", "AUD", "INR", "RUB", "CNY", "SEK", "NOK", "DKK",
+    "PLN", "BRL",
+];
+
+/// Elements commonly holding a product price
+const PRICE_SELECTORS: [&str; 4] = ["[itemprop=price]", "[data-price]", ".price", "#price"];
+
+/// Class name words of a former price shown next to the current one, like `old-price`
+/// or `price-item--regular`
+const FORMER_PRICE_WORDS: [&str; 5] = ["old", "was", "regular", "compare", "original"];
+
+/// Price of a product page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Price {
+    pub amount: f64,
+    /// ISO 4217 code
+    pub currency: Option<String>,
+}
+
+/// Price from a schema.org offer, product meta tags or common price elements
+pub fn extract_price(document: &Html, entities: &[Value]) -> Option<Price> {
+    offer_price(entities)
+        .or_else(|| meta_price(document))
+        .or_else(|| element_price(document))
+}
+
+fn offer_price(entities: &[Value]) -> Option<Price> {
+    let offer = find_entity(entities, &["Offer", "AggregateOffer"])?;
+    let amount = ["price", "lowPrice"]
+        .iter()
+        .find_map(|key| structured::text(offer, key))
+        .or_else(|| structured::text(offer.get("priceSpecification")?, "price"))?;
+    let currency = structured::text(offer, "priceCurrency")
+        .or_else(|| structured::text(offer.get("priceSpecification")?, "priceCurrency"));
+    let (amount, symbol) = parse_amount(&amount)?;
+    Some(Price {
+        amount,
+        currency: currency.map(|code| code.to_uppercase()).or(symbol),
+    })
+}
+
+fn meta_price(document: &Html) -> Option<Price> {
+    let (amount, currency) = [
+        ("product:price:amount", "product:price:currency"),
+        ("og:price:amount", "og:price:currency"),
+    ]
+    .iter()
+    .find_map(|(amount, currency)| {
+        Some((
+            structured::meta_content(document, amount)?,
+            structured::meta_content(document, currency),
+        ))
+    })?;
+    let (amount, symbol) = parse_amount(&amount)?;
+    Some(Price {
+        amount,
+        currency: currency.map(|code| code.to_uppercase()).or(symbol),
+    })
+}
+
+fn element_price(document: &Html) -> Option<Price> {
+    let currency_selector = Selector::parse("[itemprop=priceCurrency]").expect("valid selector");
+    let currency = document
+        .select(&currency_selector)
+        .next()
+        .and_then(|element| {
+            element
+                .value()
+                .attr("content")
+                .map(str::to_string)
+                .or_else(|| Some(element.text().collect::<String>()))
+                .map(|code| code.trim().to_uppercase())
+                .filter(|code| code.len() == 3)
+        });
+    PRICE_SELECTORS.iter().find_map(|selector| {
+        let selector = Selector::parse(selector).expect("valid selector");
+        document.select(&selector).find_map(|element| {
+            let element_value = element.value();
+            let (amount, symbol) = match element_value
+                .attr("content")
+                .or(element_value.attr("data-price"))
+            {
+                Some(value) => parse_amount(value)?,
+                // a sale shows the former price struck through, unless it is the only one
+                None => parse_amount(&current_price_text(element))
+                    .or_else(|| parse_amount(&element.text().collect::<String>()))?,
+            };
+            Some(Price {
+                amount,
+                currency: currency.clone().or(symbol),
+            })
+        })
+    })
+}
+
+/// Text of a price element without its `<del>`, `<s>` or former price descendants
+fn current_price_text(element: ElementRef) -> String {
+    let mut text = String::new();
+    for child in element.children() {
+        if let Some(child_text) = child.value().as_text() {
+            text.push_str(child_text);
+        } else if let Some(child) = ElementRef::wrap(child) {
+            if !is_former_price(child) {
+                text.push_str(&current_price_text(child));
+            }
+        }
+    }
+    text
+}
+
+fn is_former_price(element: ElementRef) -> bool {
+    let element = element.value();
+    matches!(element.name(), "del" | "s" | "strike")
+        || element.classes().any(|class| {
+            class
+                .split(['-', '_'])
+                .any(|word| FORMER_PRICE_WORDS.contains(&word))
+        })
+}
+
+/// Amount and currency of a price text like `1 299,00 €`, `$1,299.00` or `EUR 12.5`
+pub fn parse_amount(text: &str) -> Option<(f64, Option<String>)> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let number: String = text[start..]
+        .chars()
+        .take_while(|c| {
+            c.is_ascii_digit() || matches!(c, '.' | ',' | ' ' | '\'' | '\u{a0}' | '\u{202f}')
+        })
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+        .collect();
+    let amount = normalize_number(number.trim_end_matches(['.', ',']))?;
+    Some((amount, currency_code(text)))
+}
+
+/// The last separator followed by one or two digits is the decimal one
+fn normalize_number(number: &str) -> Option<f64> {
+    let normalized = match number.rfind(['.', ',']) {
+        Some(position) if number.len() - position - 1 <= 2 => {
+            let (integer, decimals) = number.split_at(position);
+            format!("{}.{}", integer.replace(['.', ','], ""), &decimals[1..])
+        }
+        _ => number.replace(['.', ','], ""),
+    };
+    normalized.parse().ok()
+}
+
+fn currency_code(text: &str) -> Option<String> {
+    if let Some((_, code)) = CURRENCY_SYMBOLS
+        .iter()
+        .find(|(symbol, _)| text.contains(symbol))
+    {
+        return Some(code.to_string());
+    }
+    text.split(|c: char| !c.is_ascii_alphabetic())
+        .find(|word| CURRENCY_CODES.contains(word))
+        .map(str::to_string)
+}
@@ -0,0 +1,31 @@
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+use crate::structured;
+
+/// Publication date of an article, normalized to ISO 8601: `article:published_time`,
+/// a JSON-LD `datePublished`, then a `<time>` element
+pub fn extract_published_at(document: &Html, entities: &[Value]) -> Option<String> {
+    let candidates = [
+        structured::meta_content(document, "article:published_time"),
+        entities
+            .iter()
+            .find_map(|entity| structured::text(entity, "datePublished")),
+        time_element(document, "time[itemprop=datePublished]"),
+        time_element(document, "time[datetime]"),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .find_map(|date| structured::normalize_date(&date))
+}
+
+/// `datetime` of the first matching element, else its text
+fn time_element(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).expect("valid selector");
+    let time = document.select(&selector).next()?;
+    time.value()
+        .attr("datetime")
+        .map(str::to_string)
+        .or_else(|| Some(time.text().collect::<String>()))
+}
@@ -0,0 +1,35 @@
+use scraper::{Html, Selector};
+
+/// RTA label value, https://www.rtalabel.org
+const RTA_LABEL: &str = "RTA-5042-1996-1400-1577-RTA";
+/// `rating` meta values of age restricted content
+const ADULT_RATINGS: [&str; 3] = ["adult", "mature", "restricted"];
+
+/// Whether a page labels itself as age restricted: RTA label, `rating` meta
+/// or open graph age restriction
+pub fn is_adult_content(content: &str) -> bool {
+    let document = Html::parse_document(content);
+    let selector = Selector::parse("meta[content]").expect("valid selector");
+    document.select(&selector).any(|meta| {
+        let element = meta.value();
+        let key = element
+            .attr("name")
+            .or(element.attr("property"))
+            .unwrap_or_default()
+            .to_lowercase();
+        let value = element.attr("content").unwrap_or_default().trim();
+        match key.as_str() {
+            "rating" | "pics-label" => {
+                value.contains(RTA_LABEL)
+                    || ADULT_RATINGS
+                        .iter()
+                        .any(|rating| value.eq_ignore_ascii_case(rating))
+            }
+            "og:restrictions:age" => value
+                .trim_end_matches('+')
+                .parse::<u8>()
+                .is_ok_and(|age| age >= 18),
+            _ => false,
+        }
+    })
+}
@@ -0,0 +1,181 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Serialize;
+
+use crate::markdown::{content_root, SKIPPED};
+
+/// Readability of the main content of a page: Flesch scores for english, LIX for other
+/// languages, including unknown ones, as it does not count syllables
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Readability {
+    pub words: usize,
+    pub sentences: usize,
+    /// School grade needed to understand the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flesch_kincaid_grade: Option<f64>,
+    /// 0 to 100, the higher the easier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flesch_reading_ease: Option<f64>,
+    /// Words per sentence plus the percentage of words longer than 6 letters: below 30
+    /// is easy, above 60 very hard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lix: Option<f64>,
+}
+
+/// Readability of the paragraphs of `<article>`, `<main>` or `<body>`, outside of
+/// navigation and other chrome
+pub fn extract_readability(document: &Html, language: Option<&str>) -> Option<Readability> {
+    let root = content_root(document)?;
+    let selector = Selector::parse("p").expect("valid selector");
+    let paragraphs: Vec<String> = root
+        .select(&selector)
+        .filter(|paragraph| !in_chrome(*paragraph, root))
+        .map(paragraph_text)
+        .collect();
+    text_readability(&paragraphs.join("\n\n"), language)
+}
+
+/// Text of a paragraph, its `<br>` being line breaks
+fn paragraph_text(paragraph: ElementRef) -> String {
+    let mut text = String::new();
+    for node in paragraph.descendants() {
+        match node.value() {
+            Node::Text(content) => text.push_str(content),
+            Node::Element(element) if element.name() == "br" => text.push('\n'),
+            _ => {}
+        }
+    }
+    text
+}
+
+pub(crate) fn in_chrome(element: ElementRef, root: ElementRef) -> bool {
+    element
+        .ancestors()
+        .take_while(|ancestor| ancestor.id() != root.id())
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| SKIPPED.contains(&ancestor.value().name()))
+}
+
+/// Readability of plain text, its paragraphs separated by blank lines; none without words
+pub fn text_readability(text: &str, language: Option<&str>) -> Option<Readability> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’')
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    let sentences = sentence_count(text);
+    let words_per_sentence = words.len() as f64 / sentences as f64;
+    let english = language.is_some_and(|language| {
+        let language = language.to_lowercase();
+        language == "en" || language.starts_with("en-") || language.starts_with("en_")
+    });
+    let mut readability = Readability {
+        words: words.len(),
+        sentences,
+        flesch_kincaid_grade: None,
+        flesch_reading_ease: None,
+        lix: None,
+    };
+    if english {
+        let syllables: usize = words.iter().map(|word| syllable_count(word)).sum();
+        let syllables_per_word = syllables as f64 / words.len() as f64;
+        readability.flesch_kincaid_grade = Some(round(
+            0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+        ));
+        readability.flesch_reading_ease = Some(round(
+            206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+        ));
+    } else {
+        let long_words = words
+            .iter()
+            .filter(|word| word.chars().filter(|c| c.is_alphabetic()).count() > 6)
+            .count();
+        readability.lix = Some(round(
+            words_per_sentence + 100.0 * long_words as f64 / words.len() as f64,
+        ));
+    }
+    Some(readability)
+}
+
+/// Sentences ended by `.`, `!` or `?` and a space, or by a blank line, as paragraphs
+/// without a final period are sentences too
+fn sentence_count(text: &str) -> usize {
+    let count: usize = text
+        .split("\n\n")
+        .map(|paragraph| {
+            let mut count = 0;
+            let mut chars = paragraph.chars().peekable();
+            let mut in_sentence = false;
+            while let Some(c) = chars.next() {
+                if c.is_alphanumeric() {
+                    in_sentence = true;
+                }
+                let ends = matches!(c, '.' | '!' | '?' | '…')
+                    && chars.peek().is_none_or(|next| next.is_whitespace());
+                if ends && in_sentence {
+                    count += 1;
+                    in_sentence = false;
+                }
+            }
+            count + usize::from(in_sentence)
+        })
+        .sum();
+    count.max(1)
+}
+
+/// English syllables from vowel groups, a final silent `e` aside
+fn syllable_count(word: &str) -> usize {
+    let word: Vec<char> = word
+        .to_lowercase()
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .collect();
+    let is_vowel = |c: &char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in &word {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    // like in "make", but not "table"
+    if word.len() > 2
+        && word.ends_with(&['e'])
+        && !word.ends_with(&['l', 'e'])
+        && !is_vowel(&word[word.len() - 2])
+    {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::syllable_count;
+
+    #[test]
+    fn syllables() {
+        for (word, count) in [
+            ("cat", 1),
+            ("make", 1),
+            ("the", 1),
+            ("table", 2),
+            ("Hello", 2),
+            ("rhythm", 1),
+            ("queue", 1),
+            ("beautiful", 3),
+            ("readability", 5),
+            ("don't", 1),
+            ("", 1),
+        ] {
+            assert_eq!(syllable_count(word), count, "{}", word);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use scraper::Html;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::structured::{self, find_entity};
+
+/// schema.org Recipe of a page, without the story around it
+#[derive(Debug, Serialize)]
+pub struct Recipe {
+    pub name: String,
+    #[serde(rename = "yield")]
+    pub recipe_yield: Option<String>,
+    pub prep_time: Option<String>,
+    pub cook_time: Option<String>,
+    pub total_time: Option<String>,
+    pub ingredients: Vec<String>,
+    pub steps: Vec<String>,
+}
+
+pub fn extract_recipe(content: &str) -> Option<Recipe> {
+    let entities = structured::json_ld_entities(&Html::parse_document(content));
+    let recipe = find_entity(&entities, &["Recipe"])?;
+    let time = |key| {
+        let text = structured::text(recipe, key)?;
+        Some(match structured::parse_iso_duration(&text) {
+            Some(duration) => humantime::format_duration(duration).to_string(),
+            None => text,
+        })
+    };
+    let ingredients = match recipe.get("recipeIngredient").or(recipe.get("ingredients")) {
+        Some(Value::Array(items)) => items.iter().filter_map(structured::value_text).collect(),
+        Some(value) => structured::value_text(value).into_iter().collect(),
+        None => vec![],
+    };
+    let mut steps = vec![];
+    if let Some(instructions) = recipe.get("recipeInstructions") {
+        collect_steps(instructions, &mut steps);
+    }
+    Some(Recipe {
+        name: structured::text(recipe, "name").unwrap_or("Recipe".to_string()),
+        recipe_yield: structured::text(recipe, "recipeYield"),
+        prep_time: time("prepTime"),
+        cook_time: time("cookTime"),
+        total_time: time("totalTime"),
+        ingredients,
+        steps,
+    })
+}
+
+/// Instructions are a text, a list of texts, of `HowToStep` or of `HowToSection`
+fn collect_steps(instructions: &Value, steps: &mut Vec<String>) {
+    match instructions {
+        Value::String(text) => steps.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        ),
+        Value::Array(items) => items.iter().for_each(|item| collect_steps(item, steps)),
+        Value::Object(object) => match object.get("itemListElement") {
+            Some(items) => collect_steps(items, steps),
+            None => {
+                if let Some(text) = object.get("text").or(object.get("name")) {
+                    collect_steps(text, steps)
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+pub fn format_recipe(recipe: &Recipe) -> Result<String> {
+    let mut output = String::new();
+    writeln!(output, "# {}", recipe.name)?;
+    writeln!(output)?;
+    let details = [
+        ("Yield", &recipe.recipe_yield),
+        ("Prep time", &recipe.prep_time),
+        ("Cook time", &recipe.cook_time),
+        ("Total time", &recipe.total_time),
+    ];
+    for (label, value) in details {
+        if let Some(value) = value {
+            writeln!(output, "- {}: {}", label, value)?;
+        }
+    }
+    writeln!(output)?;
+    writeln!(output, "## Ingredients")?;
+    writeln!(output)?;
+    for ingredient in &recipe.ingredients {
+        writeln!(output, "- {}", ingredient)?;
+    }
+    writeln!(output)?;
+    writeln!(output, "## Steps")?;
+    writeln!(output)?;
+    for (index, step) in recipe.steps.iter().enumerate() {
+        writeln!(output, "{}. {}", index + 1, step)?;
+    }
+    Ok(output)
+}
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use anyhow::Result;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+use url::Url;
+
+use crate::batch::{self, Outcome};
+use crate::scrap::{ScrapOptions, Scraped, ScrapedWebpage};
+use crate::simhash::similarity;
+
+/// Syntax of the suggested redirects
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum RedirectRules {
+    /// `old_url,new_url,score` rows
+    #[default]
+    Csv,
+    /// `location = /old { return 301 new; }` blocks
+    Nginx,
+    /// `RedirectMatch 301 ^/old$ new` directives of mod_alias, matching the whole path
+    Apache,
+}
+
+/// The new page an old one is redirected to
+#[derive(Debug, Serialize)]
+pub struct Redirect {
+    pub old_url: String,
+    pub new_url: String,
+    /// From 0 to 1, the mean of the title and content similarities
+    pub score: f64,
+}
+
+/// The redirects of old pages matched with new ones, and the old urls left without
+#[derive(Debug, Default, Serialize)]
+pub struct RedirectMap {
+    pub redirects: Vec<Redirect>,
+    pub unmatched: Vec<String>,
+}
+
+/// Scrape both sites and match each old page with its most similar new page, from
+/// `min_score`; failed urls are reported on stderr
+pub async fn redirect_map(
+    old_urls: &[String],
+    new_urls: &[String],
+    min_score: f64,
+    options: &ScrapOptions,
+    concurrency: usize,
+) -> RedirectMap {
+    let old_pages = pages(batch::grab_all(old_urls, options, None, concurrency).await);
+    let new_pages = pages(batch::grab_all(new_urls, options, None, concurrency).await);
+    match_pages(&old_pages, &new_pages, min_score)
+}
+
+fn pages(outcomes: Vec<Outcome>) -> Vec<ScrapedWebpage> {
+    outcomes
+        .into_iter()
+        .filter_map(|outcome| match outcome.result {
+            Ok(Scraped::Webpage(page)) => Some(*page),
+            Ok(_) => {
+                eprintln!("{}: not an html page, skipped", outcome.url);
+                None
+            }
+            Err(e) => {
+                eprintln!("{}: {}", outcome.url, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Each old page with its best scoring new page; several old pages may share one
+pub fn match_pages(
+    old_pages: &[ScrapedWebpage],
+    new_pages: &[ScrapedWebpage],
+    min_score: f64,
+) -> RedirectMap {
+    let mut map = RedirectMap::default();
+    for old in old_pages {
+        let best = new_pages
+            .iter()
+            .map(|new| (new, score(old, new)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((new, score)) if score >= min_score => {
+                // a page which kept its url needs no redirect
+                if new.url != old.url {
+                    map.redirects.push(Redirect {
+                        old_url: old.url.clone(),
+                        new_url: new.url.clone(),
+                        score: (score * 100.0).round() / 100.0,
+                    });
+                }
+            }
+            _ => map.unmatched.push(old.url.clone()),
+        }
+    }
+    map
+}
+
+/// Title similarity, averaged with the content similarity when both pages have content
+fn score(old: &ScrapedWebpage, new: &ScrapedWebpage) -> f64 {
+    let title = title_similarity(&old.title, &new.title);
+    let content = match (&old.simhash, &new.simhash) {
+        (Some(old), Some(new)) => similarity(old, new),
+        _ => None,
+    };
+    match content {
+        // unrelated pages share about half of their simhash bits
+        Some(content) => (title + ((content - 0.5) * 2.0).max(0.0)) / 2.0,
+        None => title,
+    }
+}
+
+/// Jaccard index of the lowercased words of two titles
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let words = |title: &str| -> HashSet<String> {
+        title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    match union {
+        0 => 0.0,
+        union => a.intersection(&b).count() as f64 / union as f64,
+    }
+}
+
+pub fn format_redirect_map(map: &RedirectMap, rules: RedirectRules) -> Result<String> {
+    let mut output = String::new();
+    if rules == RedirectRules::Csv {
+        writeln!(output, "old_url,new_url,score")?;
+    }
+    for redirect in &map.redirects {
+        let old_path = server_path(&redirect.old_url);
+        match rules {
+            RedirectRules::Csv => writeln!(
+                output,
+                "{},{},{}",
+                csv_field(&redirect.old_url),
+                csv_field(&redirect.new_url),
+                redirect.score
+            )?,
+            RedirectRules::Nginx => writeln!(
+                output,
+                "location = {} {{ return 301 {}; }}",
+                quoted(&old_path),
+                quoted(&redirect.new_url)
+            )?,
+            RedirectRules::Apache => writeln!(
+                output,
+                "RedirectMatch 301 {} {}",
+                apache_quoted(&format!("^{}$", regex_escaped(&old_path))),
+                apache_quoted(&substitution_escaped(&redirect.new_url))
+            )?,
+        }
+    }
+    Ok(output)
+}
+
+/// Path of an url as servers match it, percent-decoded
+fn server_path(url: &str) -> String {
+    let Ok(url) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let bytes = url.path().as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or(url.path().to_string())
+}
+
+/// A CSV field, quoted when it holds a comma, a quote or a line break
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+/// A path or url of an nginx directive, quoted when it holds spaces or quotes
+fn quoted(value: &str) -> String {
+    match value.contains([' ', '"', ';', '{', '}']) {
+        true => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+        false => value.to_string(),
+    }
+}
+
+/// An argument of an Apache directive, quoted when it holds spaces or quotes; other
+/// backslashes are kept as is
+fn apache_quoted(value: &str) -> String {
+    match value.contains(|c: char| c.is_whitespace() || c == '"') {
+        true => format!("\"{}\"", value.replace('"', "\\\"")),
+        false => value.to_string(),
+    }
+}
+
+/// A literal path as a regular expression
+fn regex_escaped(path: &str) -> String {
+    path.chars()
+        .fold(String::with_capacity(path.len()), |mut escaped, c| {
+            if "\\.+*?()|[]{}^$".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+}
+
+/// A literal url as the target of `RedirectMatch`, where `$1` and `&` stand for groups
+fn substitution_escaped(url: &str) -> String {
+    url.replace('$', "\\$").replace('&', "\\&")
+}
@@ -0,0 +1,113 @@
+use reqwest::header::{HeaderMap, LINK};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use url::Url;
+
+/// A typed link of the page (next, prev, canonical, alternate, webmention...)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Relation {
+    pub rel: String,
+    pub target: String,
+    /// `header` (RFC 8288 Link header) or `html`
+    pub source: String,
+}
+
+/// Relations of the Link headers and of `<link rel>`/`<a rel>` elements, without duplicates
+pub fn extract_relations(headers: &HeaderMap, content: &str, page_url: &str) -> Vec<Relation> {
+    let base = Url::parse(page_url).ok();
+    let mut relations = vec![];
+    for value in headers.get_all(LINK).iter() {
+        if let Ok(value) = value.to_str() {
+            relations.extend(parse_link_header(value, base.as_ref()));
+        }
+    }
+    for relation in html_relations(content, base.as_ref()) {
+        let is_known = relations
+            .iter()
+            .any(|known| known.rel == relation.rel && known.target == relation.target);
+        if !is_known {
+            relations.push(relation);
+        }
+    }
+    relations
+}
+
+/// Parse `<url>; rel="next"; title="x", <url2>; rel=prev`
+pub fn parse_link_header(value: &str, base: Option<&Url>) -> Vec<Relation> {
+    let mut relations = vec![];
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        let target = resolve(&rest[start + 1..end], base);
+        // parameters run until the next link value
+        let params_end = rest[end..]
+            .find(", <")
+            .map(|i| end + i)
+            .unwrap_or(rest.len());
+        let params = &rest[end + 1..params_end];
+        let rel = params.split(';').find_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            (key.trim().eq_ignore_ascii_case("rel")).then(|| value.trim().trim_matches('"'))
+        });
+        if let Some(rel) = rel {
+            for rel in rel.split_whitespace() {
+                relations.push(Relation {
+                    rel: rel.to_lowercase(),
+                    target: target.clone(),
+                    source: "header".to_string(),
+                });
+            }
+        }
+        rest = &rest[params_end..];
+    }
+    relations
+}
+
+/// Icon of the page: `<link rel="icon">`, then apple touch icons, else `/favicon.ico`
+pub fn favicon_url(relations: &[Relation], page_url: &str) -> Option<String> {
+    ["icon", "apple-touch-icon", "apple-touch-icon-precomposed"]
+        .iter()
+        .find_map(|rel| {
+            relations
+                .iter()
+                .find(|relation| relation.rel == *rel)
+                .map(|relation| relation.target.clone())
+        })
+        .or_else(|| {
+            let url = Url::parse(page_url).ok()?;
+            matches!(url.scheme(), "http" | "https")
+                .then(|| url.join("/favicon.ico").ok())
+                .flatten()
+                .map(String::from)
+        })
+}
+
+fn html_relations(content: &str, base: Option<&Url>) -> Vec<Relation> {
+    let document = Html::parse_document(content);
+    let selector = Selector::parse("link[rel][href], a[rel][href]").expect("valid selector");
+    document
+        .select(&selector)
+        .flat_map(|element| {
+            let target = resolve(element.value().attr("href").unwrap_or_default(), base);
+            element
+                .value()
+                .attr("rel")
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|rel| Relation {
+                    rel: rel.to_lowercase(),
+                    target: target.clone(),
+                    source: "html".to_string(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn resolve(target: &str, base: Option<&Url>) -> String {
+    base.and_then(|base| base.join(target.trim()).ok())
+        .map(|url| url.to_string())
+        .unwrap_or(target.trim().to_string())
+}
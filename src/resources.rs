@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+use reqwest::{header, Client};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use tokio::task::JoinSet;
+use url::Url;
+
+use crate::format::{format_json, Format, Shape};
+use crate::scrap::{with_network, ScrapOptions, ScrapedWebpage};
+use crate::{domains, units};
+
+const FONT_EXTENSIONS: [&str; 5] = [".woff2", ".woff", ".ttf", ".otf", ".eot"];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceKind {
+    Stylesheet,
+    Script,
+    Font,
+    Iframe,
+    Image,
+}
+
+/// A file a page loads
+#[derive(Debug, Clone, Serialize)]
+pub struct Resource {
+    pub url: String,
+    pub kind: ResourceKind,
+    /// `Content-Length` of a HEAD request
+    pub bytes: Option<u64>,
+}
+
+/// Resources of a registrable domain
+#[derive(Debug, Serialize)]
+pub struct ResourceGroup {
+    pub domain: String,
+    /// Another domain than the page one
+    pub third_party: bool,
+    /// Sum of the known sizes
+    pub bytes: u64,
+    pub resources: Vec<Resource>,
+}
+
+/// Stylesheets, scripts, fonts and iframes of a page, absolute and deduplicated
+pub fn find_resources(content: &str, page_url: &str) -> Vec<Resource> {
+    let document = Html::parse_document(content);
+    let Ok(base) = Url::parse(page_url) else {
+        return vec![];
+    };
+    let selectors = [
+        (
+            r#"link[rel~="stylesheet"][href]"#,
+            "href",
+            ResourceKind::Stylesheet,
+        ),
+        (
+            r#"link[rel~="preload"][as="font"][href]"#,
+            "href",
+            ResourceKind::Font,
+        ),
+        ("script[src]", "src", ResourceKind::Script),
+        ("iframe[src]", "src", ResourceKind::Iframe),
+    ];
+    let mut resources: Vec<Resource> = vec![];
+    let mut add = |url: &str, kind| {
+        let Ok(url) = base.join(url.trim()) else {
+            return;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return;
+        }
+        let url = String::from(url);
+        if !resources.iter().any(|resource| resource.url == url) {
+            resources.push(Resource {
+                url,
+                kind,
+                bytes: None,
+            });
+        }
+    };
+    for (selector, attribute, kind) in selectors {
+        let selector = Selector::parse(selector).expect("valid selector");
+        for element in document.select(&selector) {
+            if let Some(url) = element.value().attr(attribute) {
+                add(url, kind);
+            }
+        }
+    }
+    // fonts of inline @font-face rules
+    let style_selector = Selector::parse("style").expect("valid selector");
+    for style in document.select(&style_selector) {
+        let css: String = style.text().collect();
+        for url in css_urls(&css) {
+            let path = url
+                .split(['?', '#'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            if FONT_EXTENSIONS
+                .iter()
+                .any(|extension| path.ends_with(extension))
+            {
+                add(&url, ResourceKind::Font);
+            }
+        }
+    }
+    resources
+}
+
+/// Values of the `url(...)` of a stylesheet
+fn css_urls(css: &str) -> Vec<String> {
+    css.split("url(")
+        .skip(1)
+        .filter_map(|rest| {
+            let value = rest.split(')').next()?;
+            Some(value.trim().trim_matches(['"', '\'']).to_string())
+        })
+        .filter(|url| !url.is_empty() && !url.starts_with("data:"))
+        .collect()
+}
+
+/// Images of a page, absolute and deduplicated
+pub fn find_images(content: &str, page_url: &str) -> Vec<Resource> {
+    let document = Html::parse_document(content);
+    let Ok(base) = Url::parse(page_url) else {
+        return vec![];
+    };
+    let selector = Selector::parse("img[src]").expect("valid selector");
+    let mut images: Vec<Resource> = vec![];
+    for image in document.select(&selector) {
+        let src = image.value().attr("src").unwrap_or_default();
+        let Ok(url) = base.join(src.trim()) else {
+            continue;
+        };
+        let url = String::from(url);
+        if url.starts_with("http") && !images.iter().any(|image| image.url == url) {
+            images.push(Resource {
+                url,
+                kind: ResourceKind::Image,
+                bytes: None,
+            });
+        }
+    }
+    images
+}
+
+/// HEAD requests running at once when measuring resources
+const HEAD_CONCURRENCY: usize = 8;
+
+/// Fill the sizes of resources with concurrent HEAD requests
+pub async fn measure_sizes(resources: &mut [Resource], options: &ScrapOptions) -> Result<()> {
+    let client = head_client(options)?;
+    let mut requests = JoinSet::new();
+    let urls: Vec<String> = resources
+        .iter()
+        .map(|resource| resource.url.clone())
+        .collect();
+    let mut pending = urls.into_iter().enumerate();
+    let mut spawn = |requests: &mut JoinSet<(usize, Option<u64>)>| {
+        let Some((index, url)) = pending.next() else {
+            return;
+        };
+        let client = client.clone();
+        requests.spawn(async move { (index, head_size(&client, &url).await) });
+    };
+    for _ in 0..HEAD_CONCURRENCY {
+        spawn(&mut requests);
+    }
+    while let Some(result) = requests.join_next().await {
+        let (index, bytes) = result?;
+        resources[index].bytes = bytes;
+        spawn(&mut requests);
+    }
+    Ok(())
+}
+
+/// Size of a resource from a HEAD request, unknown when the server does not tell
+pub async fn head_size(client: &Client, url: &str) -> Option<u64> {
+    let response = client.head(url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response
+        .headers()
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+pub fn head_client(options: &ScrapOptions) -> Result<Client> {
+    let client = Client::builder()
+        .timeout(options.timeout)
+        .user_agent(options.user_agent.as_str());
+    Ok(with_network(client, options).build()?)
+}
+
+/// Resources of a page with their sizes, grouped by registrable domain, the page domain first
+pub async fn resource_report(
+    page: &ScrapedWebpage,
+    options: &ScrapOptions,
+) -> Result<Vec<ResourceGroup>> {
+    let mut resources = find_resources(&page.html, &page.url);
+    measure_sizes(&mut resources, options).await?;
+    Ok(group_by_domain(resources, &page.url))
+}
+
+pub fn group_by_domain(resources: Vec<Resource>, page_url: &str) -> Vec<ResourceGroup> {
+    let page_domain = domains::url_domain(page_url);
+    let mut by_domain: BTreeMap<String, Vec<Resource>> = BTreeMap::new();
+    for resource in resources {
+        let domain = domains::url_domain(&resource.url).unwrap_or_default();
+        by_domain.entry(domain).or_default().push(resource);
+    }
+    let mut groups: Vec<ResourceGroup> = by_domain
+        .into_iter()
+        .map(|(domain, resources)| ResourceGroup {
+            third_party: page_domain.as_ref() != Some(&domain),
+            bytes: resources.iter().filter_map(|resource| resource.bytes).sum(),
+            domain,
+            resources,
+        })
+        .collect();
+    groups.sort_by_key(|group| group.third_party);
+    groups
+}
+
+pub fn format_resources(groups: &[ResourceGroup], format: Format, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(&groups, shape)?)?,
+        _ => {
+            for group in groups {
+                let party = if group.third_party {
+                    "third party"
+                } else {
+                    "first party"
+                };
+                writeln!(
+                    output,
+                    "## {} ({}, {} resources, {})",
+                    group.domain,
+                    party,
+                    group.resources.len(),
+                    format_size(Some(group.bytes))
+                )?;
+                writeln!(output)?;
+                for resource in &group.resources {
+                    writeln!(
+                        output,
+                        "- {} {} ({})",
+                        serde_json::to_value(resource.kind)?
+                            .as_str()
+                            .unwrap_or_default(),
+                        resource.url,
+                        format_size(resource.bytes)
+                    )?;
+                }
+                writeln!(output)?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn format_size(bytes: Option<u64>) -> String {
+    bytes.map_or("unknown size".to_string(), units::format_bytes)
+}
+
+/// Assets listed as the heaviest in a weight report
+const HEAVIEST_COUNT: usize = 10;
+
+/// Estimated transfer size of a page and its resources
+#[derive(Debug, Serialize)]
+pub struct PageWeight {
+    pub url: String,
+    /// Html and resources with a known size
+    pub total_bytes: u64,
+    /// Bytes per kind, `html` included
+    pub by_kind: BTreeMap<String, u64>,
+    /// Resources without a size from their HEAD request
+    pub unknown_sizes: usize,
+    pub heaviest: Vec<Resource>,
+}
+
+/// Html transfer size plus the HEAD sizes of its resources and images
+pub async fn page_weight(page: &ScrapedWebpage, options: &ScrapOptions) -> Result<PageWeight> {
+    let mut resources = find_resources(&page.html, &page.url);
+    resources.extend(find_images(&page.html, &page.url));
+    measure_sizes(&mut resources, options).await?;
+    let html_bytes = page
+        .http
+        .as_ref()
+        .map_or(page.html.len(), |http| http.wire_bytes) as u64;
+    let mut by_kind = BTreeMap::from([("html".to_string(), html_bytes)]);
+    for resource in &resources {
+        let kind = serde_json::to_value(resource.kind)?
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        *by_kind.entry(kind).or_default() += resource.bytes.unwrap_or_default();
+    }
+    let unknown_sizes = resources
+        .iter()
+        .filter(|resource| resource.bytes.is_none())
+        .count();
+    let mut heaviest: Vec<Resource> = resources
+        .into_iter()
+        .filter(|resource| resource.bytes.is_some())
+        .collect();
+    heaviest.sort_by_key(|resource| std::cmp::Reverse(resource.bytes));
+    heaviest.truncate(HEAVIEST_COUNT);
+    Ok(PageWeight {
+        url: page.url.clone(),
+        total_bytes: by_kind.values().sum(),
+        by_kind,
+        unknown_sizes,
+        heaviest,
+    })
+}
+
+pub fn format_weight(weight: &PageWeight, format: Format, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        Format::Json => writeln!(output, "{}", format_json(weight, shape)?)?,
+        _ => {
+            writeln!(
+                output,
+                "{}: {}",
+                weight.url,
+                units::format_bytes(weight.total_bytes)
+            )?;
+            if weight.unknown_sizes > 0 {
+                writeln!(
+                    output,
+                    "({} resources of unknown size)",
+                    weight.unknown_sizes
+                )?;
+            }
+            writeln!(output)?;
+            writeln!(output, "| kind | size |")?;
+            writeln!(output, "| --- | ---: |")?;
+            for (kind, bytes) in &weight.by_kind {
+                writeln!(output, "| {} | {} |", kind, units::format_bytes(*bytes))?;
+            }
+            writeln!(output)?;
+            writeln!(output, "Heaviest:")?;
+            for resource in &weight.heaviest {
+                writeln!(
+                    output,
+                    "- {} ({})",
+                    resource.url,
+                    format_size(resource.bytes)
+                )?;
+            }
+        }
+    }
+    Ok(output)
+}
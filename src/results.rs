@@ -0,0 +1,88 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use serde_json::Value;
+use url::Url;
+
+/// Ordering and trimming of collected results, `--sort`, `--unique-by` and `--limit`
+#[derive(Debug, Clone, Default)]
+pub struct Arrange {
+    /// Field to sort on, descending when prefixed with `-`
+    pub sort: Option<String>,
+    /// Keep the first result of each field value
+    pub unique_by: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl Arrange {
+    /// Results are kept as they come
+    pub fn is_empty(&self) -> bool {
+        self.sort.is_none() && self.unique_by.is_none() && self.limit.is_none()
+    }
+
+    pub fn apply(&self, results: Vec<Value>) -> Vec<Value> {
+        self.apply_by(results, |result| result)
+    }
+
+    /// Arrange any results, through their json view
+    pub fn apply_by<T>(&self, mut results: Vec<T>, view: impl Fn(&T) -> &Value) -> Vec<T> {
+        if let Some(sort) = &self.sort {
+            let (field, descending) = match sort.strip_prefix('-') {
+                Some(field) => (field, true),
+                None => (sort.as_str(), false),
+            };
+            // stable sort, missing values last
+            results.sort_by(|a, b| {
+                let ordering = match (field_value(view(a), field), field_value(view(b), field)) {
+                    (Some(a), Some(b)) => compare(&a, &b),
+                    (Some(_), None) => return Ordering::Less,
+                    (None, Some(_)) => return Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        if let Some(field) = &self.unique_by {
+            let mut seen = HashSet::new();
+            results.retain(|result| {
+                let key = field_value(view(result), field).map(|value| value.to_string());
+                // results without the field are all kept
+                key.is_none() || seen.insert(key)
+            });
+        }
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+        results
+    }
+}
+
+/// Dotted field of a result; `domain` falls back to the host of its url
+pub fn field_value(result: &Value, field: &str) -> Option<Value> {
+    let value = field
+        .split('.')
+        .try_fold(result, |value, name| value.get(name))
+        .filter(|value| !value.is_null())
+        .cloned();
+    if value.is_none() && field == "domain" {
+        let url = Url::parse(result.get("url")?.as_str()?).ok()?;
+        return url.host_str().map(|host| Value::String(host.to_string()));
+    }
+    value
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .zip(b.as_f64())
+            .and_then(|(a, b)| a.partial_cmp(&b))
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
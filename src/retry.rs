@@ -0,0 +1,91 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+#[cfg(feature = "store")]
+use anyhow::Result;
+#[cfg(feature = "store")]
+use serde::Serialize;
+
+#[cfg(feature = "store")]
+use crate::scrap::ScrapOptions;
+use crate::scrap::ScraperError;
+#[cfg(feature = "store")]
+use crate::{batch, store::Store};
+
+/// Outcome of `scrapr retry-failed`
+#[cfg(feature = "store")]
+#[derive(Debug, Default, Serialize)]
+pub struct RetryReport {
+    pub recovered: Vec<String>,
+    pub failed: Vec<RetryFailure>,
+}
+
+#[cfg(feature = "store")]
+#[derive(Debug, Serialize)]
+pub struct RetryFailure {
+    pub url: String,
+    pub error: String,
+}
+
+/// Retry the queued urls with a longer timeout and maybe another user agent;
+/// recovered urls leave the queue, the others count one more attempt
+#[cfg(feature = "store")]
+pub async fn retry_failed(
+    store: &Store,
+    options: &ScrapOptions,
+    timeout: Duration,
+    user_agent: Option<&str>,
+) -> Result<RetryReport> {
+    let mut options = ScrapOptions {
+        timeout,
+        adaptive_timeout: None,
+        ..options.clone()
+    };
+    if let Some(user_agent) = user_agent {
+        options.user_agent = user_agent.to_string();
+    }
+    let failures = store.failures()?;
+    let urls: Vec<String> = failures.iter().map(|failure| failure.url.clone()).collect();
+    let outcomes = batch::grab_all(&urls, &options, None, 1).await;
+    batch::record_latencies(store, &outcomes)?;
+    let mut report = RetryReport::default();
+    for (failure, outcome) in failures.iter().zip(&outcomes) {
+        match &outcome.result {
+            Ok(_) => {
+                store.remove_failure(&failure.url)?;
+                report.recovered.push(failure.url.clone());
+            }
+            Err(e) => {
+                store.record_failure(&failure.url, &failure.source, &e.to_string())?;
+                report.failed.push(RetryFailure {
+                    url: failure.url.clone(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Timeouts, connection errors, interrupted bodies and 5xx statuses, which may not
+/// happen again
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    if let Some(error) = error.downcast_ref::<ScraperError>() {
+        return matches!(
+            error,
+            ScraperError::Timeout(..) | ScraperError::Connection(..) | ScraperError::Server(..)
+        );
+    }
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|error| error.is_timeout() || error.is_connect() || error.is_body())
+}
+
+/// `delay` doubled at each attempt, half of it random so that clients failing together
+/// do not retry together
+pub fn backoff(delay: Duration, attempt: usize) -> Duration {
+    let delay = delay.saturating_mul(1 << attempt.min(16));
+    let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    delay.div_f64(2.0).mul_f64(1.0 + jitter)
+}
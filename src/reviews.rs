@@ -0,0 +1,57 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::structured::{self, find_entity, has_type};
+
+/// schema.org AggregateRating of a page
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateRating {
+    pub value: f64,
+    /// Number of ratings, or of reviews when not given
+    pub count: Option<u64>,
+    pub best: Option<f64>,
+}
+
+/// schema.org Review of a page
+#[derive(Debug, Clone, Serialize)]
+pub struct Review {
+    pub author: Option<String>,
+    pub rating: Option<f64>,
+    pub date: Option<String>,
+    pub body: Option<String>,
+}
+
+pub fn extract_rating(entities: &[Value]) -> Option<AggregateRating> {
+    let rating = find_entity(entities, &["AggregateRating"])?;
+    Some(AggregateRating {
+        value: number(rating, "ratingValue")?,
+        count: number(rating, "ratingCount")
+            .or(number(rating, "reviewCount"))
+            .map(|count| count as u64),
+        best: number(rating, "bestRating"),
+    })
+}
+
+pub fn extract_reviews(entities: &[Value]) -> Vec<Review> {
+    entities
+        .iter()
+        .filter(|entity| has_type(entity, "Review"))
+        .map(|review| Review {
+            author: structured::text(review, "author"),
+            rating: review
+                .get("reviewRating")
+                .and_then(|rating| number(rating, "ratingValue")),
+            date: structured::text(review, "datePublished"),
+            body: structured::text(review, "reviewBody")
+                .or(structured::text(review, "description")),
+        })
+        .collect()
+}
+
+/// Numbers are often strings, with a decimal comma
+fn number(entity: &Value, key: &str) -> Option<f64> {
+    structured::text(entity, key)?
+        .replace(',', ".")
+        .parse()
+        .ok()
+}
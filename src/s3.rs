@@ -0,0 +1,250 @@
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use reqwest::{header, Client, Method, RequestBuilder, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::secret::resolve_secrets;
+
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Access key of an S3 compatible service
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Configured keys, which may be `{{secret:NAME}}`, or else the `AWS_ACCESS_KEY_ID`,
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` environment variables
+    pub fn resolve(access_key_id: Option<&str>, secret_access_key: Option<&str>) -> Result<Self> {
+        match (access_key_id, secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Credentials {
+                access_key_id: resolve_secrets(access_key_id)?,
+                secret_access_key: resolve_secrets(secret_access_key)?,
+                session_token: None,
+            }),
+            _ => Ok(Credentials {
+                access_key_id: std::env::var("AWS_ACCESS_KEY_ID")
+                    .map_err(|_| anyhow!("No S3 access key, set AWS_ACCESS_KEY_ID"))?,
+                secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY")
+                    .map_err(|_| anyhow!("No S3 secret key, set AWS_SECRET_ACCESS_KEY"))?,
+                session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            }),
+        }
+    }
+}
+
+/// Object of an S3 compatible bucket, addressed path-style as `{endpoint}/{bucket}/{key}`
+#[derive(Debug, Clone)]
+pub struct S3Object {
+    pub url: Url,
+    pub region: String,
+    pub credentials: Credentials,
+}
+
+/// Current content of an object with its ETag, for a conditional write
+pub struct Fetched {
+    pub body: Vec<u8>,
+    pub etag: Option<String>,
+}
+
+impl S3Object {
+    /// Object `name` under the prefix of `s3://bucket/prefix`, on AWS unless an endpoint
+    /// like `http://localhost:9000` is given
+    pub fn new(
+        remote: &str,
+        name: &str,
+        endpoint: Option<&str>,
+        region: Option<&str>,
+        credentials: Credentials,
+    ) -> Result<Self> {
+        let location = remote
+            .strip_prefix("s3://")
+            .ok_or(anyhow!("{} is not an s3:// url", remote))?;
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err(anyhow!("No bucket in {}", remote));
+        }
+        let region = region.unwrap_or(DEFAULT_REGION).to_string();
+        let endpoint = match endpoint {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!("https://s3.{}.amazonaws.com", region),
+        };
+        let path = std::iter::once(bucket)
+            .chain(prefix.split('/'))
+            .chain([name])
+            .filter(|segment| !segment.is_empty())
+            .map(uri_encode)
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(S3Object {
+            url: Url::parse(&format!("{}/{}", endpoint, path))?,
+            region,
+            credentials,
+        })
+    }
+
+    /// Content of the object, `None` when it does not exist yet
+    pub async fn get(&self, client: &Client) -> Result<Option<Fetched>> {
+        let response = self.request(client, Method::GET, &[]).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response).await?;
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        Ok(Some(Fetched {
+            body: response.bytes().await?.to_vec(),
+            etag,
+        }))
+    }
+
+    /// Write the object, only if it is still the fetched version so a concurrent sync
+    /// is not overwritten
+    pub async fn put(
+        &self,
+        client: &Client,
+        body: Vec<u8>,
+        fetched: Option<&Fetched>,
+    ) -> Result<()> {
+        let request = self.request(client, Method::PUT, &body);
+        let request = match fetched.and_then(|fetched| fetched.etag.as_deref()) {
+            Some(etag) => request.header(header::IF_MATCH, etag),
+            None if fetched.is_none() => request.header(header::IF_NONE_MATCH, "*"),
+            None => request,
+        };
+        let response = request.body(body).send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(anyhow!(
+                "{} changed during the sync, run it again",
+                self.url
+            ));
+        }
+        check(response).await?;
+        Ok(())
+    }
+
+    /// Request signed with AWS signature version 4
+    fn request(&self, client: &Client, method: Method, body: &[u8]) -> RequestBuilder {
+        let timestamp = humantime::format_rfc3339_seconds(SystemTime::now())
+            .to_string()
+            .replace(['-', ':'], "");
+        let date = &timestamp[..8];
+        let payload_hash = hex(&Sha256::digest(body));
+        let host = match self.url.port() {
+            Some(port) => format!("{}:{}", self.url.host_str().unwrap_or_default(), port),
+            None => self.url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", timestamp.clone()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method,
+            self.url.path(),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = [date, &self.region, "s3", "aws4_request"].iter().fold(
+            format!("AWS4{}", self.credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()).to_vec(),
+        );
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.credentials.access_key_id, scope, signed_headers, signature
+        );
+
+        let mut request = client
+            .request(method, self.url.clone())
+            .header(header::AUTHORIZATION, authorization);
+        // host is set by the client from the url
+        for (name, value) in headers.into_iter().skip(1) {
+            request = request.header(name, value);
+        }
+        request
+    }
+}
+
+/// Error with the S3 error message when the request failed
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let url = response.url().clone();
+    let body = response.text().await.unwrap_or_default();
+    let message = body
+        .split_once("<Message>")
+        .and_then(|(_, rest)| rest.split_once("</Message>"))
+        .map_or(status.to_string(), |(message, _)| message.to_string());
+    Err(anyhow!("S3 request to {} failed: {}", url, message))
+}
+
+/// Percent encoding of a path segment, keeping only the unreserved characters
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// HMAC-SHA256 (RFC 2104)
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|k| k ^ byte).collect::<Vec<_>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
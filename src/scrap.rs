@@ -1,24 +1,286 @@
-use std::time::Duration;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::Once;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{anyhow, Result};
-use reqwest::{header, Client, StatusCode};
+use reqwest::cookie::Jar;
+use reqwest::header::HeaderMap;
+use reqwest::redirect::Policy;
+use reqwest::{header, Client, ClientBuilder, Method, Proxy, StatusCode, Version};
+use scraper::{Html, Selector};
 use serde::Serialize;
+use serde_json::Value;
 use thiserror::Error;
-use webpage::HTML;
+use url::Url;
+use webpage::{Link, HTML};
 
+use crate::antibot::Challenge;
+use crate::bandwidth::BandwidthLimiter;
+use crate::cache::{CachedResponse, HttpCache};
+use crate::dns::{CachedResolver, DnsCache};
+use crate::forms::Form;
+use crate::imagemeta::ImageMetadata;
+use crate::impersonate::Browser;
+use crate::jobposting::JobPosting;
+use crate::opengraph::OpenGraph;
+use crate::podcast::PodcastEpisode;
+use crate::readability::Readability;
+use crate::relations::Relation;
+use crate::reviews::{AggregateRating, Review};
+use crate::timeout::AdaptiveTimeout;
+use crate::transfer::HttpInfo;
+use crate::twitter::TwitterCard;
+use crate::video::Video;
+use crate::{
+    activitypub, antibot, author, cache, cleanup, domains, forms, homograph, jobposting, opengraph,
+    podcast, price, published, rating, readability, relations, retry, reviews, simhash, structured,
+    tags, transfer, twitter, video, xml,
+};
+
+/// Metadata of a web page
 #[derive(Debug, Serialize)]
 pub struct ScrapedWebpage {
     pub title: String,
+    /// `final_url`, or the canonical url with `prefer_canonical`
     pub url: String,
+    /// `<link rel="canonical">` or canonical Link header, absolute
+    pub canonical_url: Option<String>,
+    /// Url the redirects landed on
+    pub final_url: String,
+    /// Urls redirected from, in order, starting with the requested one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<String>,
+    /// Host of the url
+    pub domain: Option<String>,
+    /// Host part registrable under a public suffix
+    pub registrable_domain: Option<String>,
+    pub status: u16,
+    /// Open graph description when present, else the meta description, else the
+    /// twitter card one
     pub description: Option<String>,
     pub language: Option<String>,
+    /// Author names, comma separated, or a profile url
+    pub author: Option<String>,
+    /// Publication date, ISO 8601 in UTC
+    pub published_at: Option<String>,
+    /// `article:tag` and keywords, or the hashtags of a post
+    pub tags: Vec<String>,
+    /// Absolute url of the lead image, `og:image` or `twitter:image`
+    pub image: Option<String>,
+    #[serde(flatten)]
+    pub open_graph: OpenGraph,
+    #[serde(flatten)]
+    pub twitter: TwitterCard,
+    pub attachments: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub forms: Vec<Form>,
+    /// Response headers selected with `capture_headers`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Link header and html link relations
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub relations: Vec<Relation>,
+    /// Absolute url of the page icon, `/favicon.ico` when none is declared
+    pub favicon: Option<String>,
+    /// Local copy of the favicon, from `--download-favicon` or `--format script-filter`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpInfo>,
+    /// Age restriction label (RTA, `rating` meta, open graph)
+    pub adult_content: bool,
+    /// Until when the response may be reused, from `Cache-Control` or `Expires`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cacheable_until: Option<String>,
+    /// Product price, from a schema.org offer, meta tags or price elements
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    /// ISO 4217 code of the price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// schema.org JobPosting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job: Option<JobPosting>,
+    /// schema.org AggregateRating
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<AggregateRating>,
+    /// schema.org Reviews
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reviews: Vec<Review>,
+    /// Audio episode of a podcast page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub podcast: Option<PodcastEpisode>,
+    /// Main video of the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video: Option<Video>,
+    /// Readability scores of the main content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readability: Option<Readability>,
+    /// Simhash of the main content, 16 hex digits: near-duplicates differ by few bits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simhash: Option<String>,
+    /// Hero image saved with `--download-image`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hero_image: Option<ImageMetadata>,
+    /// Line of `--input-file` the page was requested from, like `urls.txt:3`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Suspicious things about the page, like homograph hostnames
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip)]
+    pub links: Vec<Link>,
+    /// Raw html document, for extractions beyond metadata
+    #[serde(skip)]
+    pub html: String,
 }
 
-struct HtmlPage {
-    url: String,
-    content: String,
+/// What a url resolved to: an html page or a json document passed through as is
+pub enum Scraped {
+    Webpage(Box<ScrapedWebpage>),
+    Json(Value),
+    Xml(String),
+}
+
+impl Scraped {
+    /// Json view of the result, xml documents being a string
+    pub fn to_value(&self) -> Result<Value> {
+        Ok(match self {
+            Scraped::Webpage(page) => serde_json::to_value(page)?,
+            Scraped::Json(value) => value.clone(),
+            Scraped::Xml(content) => Value::String(content.clone()),
+        })
+    }
+}
+
+/// Request settings shared by all fetches of a run
+#[derive(Debug, Clone)]
+pub struct ScrapOptions {
+    pub accept: String,
+    pub user_agent: String,
+    pub timeout: Duration,
+    /// Extra request headers
+    pub headers: Vec<(String, String)>,
+    pub method: Method,
+    pub body: Option<String>,
+    pub content_type: Option<String>,
+    pub cookie_jar: Option<Arc<Jar>>,
+    /// Response headers copied to the output
+    pub capture_headers: Vec<String>,
+    /// Global body download rate limit
+    pub bandwidth: Option<Arc<BandwidthLimiter>>,
+    /// Cache of GET responses
+    pub cache: Option<HttpCache>,
+    /// Fail on homograph hostnames instead of warning
+    pub deny_homographs: bool,
+    /// Per host timeout from past latencies, replacing `timeout`
+    pub adaptive_timeout: Option<Arc<AdaptiveTimeout>>,
+    /// Client reused by the fetches, each one building its own otherwise
+    pub client: Option<Client>,
+    pub max_redirects: usize,
+    /// Use the canonical url of pages declaring one as their `url`
+    pub prefer_canonical: bool,
+    /// Send the request headers of a browser, with `user_agent` and `accept`
+    pub impersonate: Option<Browser>,
+    /// Report a panic of the extraction as a `Malformed` error of the page
+    pub robust: bool,
+    /// Tags kept on a page, all of them by default
+    pub max_tags: Option<usize>,
+    /// Unwrap redirectors and remove tracking parameters from the links of pages
+    pub strip_tracking: bool,
+    /// Retries of a `GET` or `HEAD` fetch failing on a timeout, a connection error or a
+    /// 5xx status
+    pub retries: usize,
+    /// Wait before the first retry, doubled at each of the next ones
+    pub retry_delay: Duration,
+    /// DNS lookups shared by the clients, the system resolver being used without
+    pub dns_cache: Option<Arc<DnsCache>>,
+    /// HTTP or SOCKS5 proxy of all requests, instead of the `HTTP_PROXY` and
+    /// `HTTPS_PROXY` variables
+    pub proxy: Option<Proxy>,
+    /// Fetches at once on a host in a batch, within its global concurrency
+    pub per_host_concurrency: usize,
 }
 
+impl Default for ScrapOptions {
+    fn default() -> Self {
+        Self {
+            accept: ACCEPT_HTML.to_string(),
+            user_agent: USER_AGENT.to_string(),
+            timeout: Duration::from_secs(2),
+            headers: vec![],
+            method: Method::GET,
+            body: None,
+            content_type: None,
+            cookie_jar: None,
+            capture_headers: vec![],
+            bandwidth: None,
+            cache: None,
+            deny_homographs: false,
+            adaptive_timeout: None,
+            client: None,
+            max_redirects: 10,
+            prefer_canonical: false,
+            impersonate: None,
+            robust: false,
+            max_tags: None,
+            strip_tracking: false,
+            retries: 0,
+            retry_delay: Duration::from_secs(1),
+            dns_cache: None,
+            proxy: None,
+            per_host_concurrency: 2,
+        }
+    }
+}
+
+impl ScrapOptions {
+    /// Same options with one client for all fetches, sharing its connection pool;
+    /// left without when the headers are invalid, each fetch then failing on them
+    pub fn with_shared_client(&self) -> Self {
+        Self {
+            client: build_client(self).ok(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Response of a fetch, before extraction
+pub struct HtmlPage {
+    pub url: String,
+    pub status: u16,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub headers: HeaderMap,
+    pub http: HttpInfo,
+    pub redirects: Vec<String>,
+}
+
+impl HtmlPage {
+    /// `200 OK` html response of `url`, for a document obtained elsewhere
+    pub fn ok(url: &str, content: String) -> Self {
+        let http = HttpInfo::new(
+            Version::HTTP_11,
+            &HeaderMap::new(),
+            content.len(),
+            content.len(),
+        );
+        Self {
+            url: url.to_string(),
+            status: 200,
+            content,
+            content_type: Some("text/html".to_string()),
+            headers: HeaderMap::new(),
+            http,
+            redirects: vec![],
+        }
+    }
+}
+
+/// Why a fetch failed, with the url
 #[derive(Debug, Error)]
 pub enum ScraperError {
     #[error("Client error (status: {0}): {1}")]
@@ -27,74 +289,551 @@ pub enum ScraperError {
     Server(StatusCode, String),
     #[error("Timout error {0}: {1}")]
     Timeout(String, String),
+    #[error("Connection error {0}: {1}")]
+    Connection(String, String),
+    #[error("Redirect error (more than {1} redirects): {0}")]
+    TooManyRedirects(String, usize),
+    #[error("Blocked (blocked_by: {0}): {1}; {hint}", hint = .0.guidance())]
+    Blocked(Challenge, String),
+    #[error("Malformed page {1}: {0}")]
+    Malformed(String, String),
     #[error("Scraper error {0}: {1}")]
     Other(String, String),
 }
 
-pub async fn grab_url(url: &str) -> Result<ScrapedWebpage> {
+/// Fetch an url and extract its metadata; json and xml documents are passed through
+pub async fn grab_url(url: &str, options: &ScrapOptions) -> Result<Scraped> {
+    check_homographs(url, options)?;
     // grap html page
-    let html_response = retrieve_html_page(url).await?;
+    let html_response = retrieve_html_page(url, options).await?;
+    extract_response(url, html_response, options).await
+}
+
+/// Extract an html document obtained elsewhere, as the `200 OK` response of `url`
+pub async fn extract_document(
+    url: &str,
+    content: String,
+    options: &ScrapOptions,
+) -> Result<Scraped> {
+    extract_response(url, HtmlPage::ok(url, content), options).await
+}
+
+/// Extract a retrieved response of the requested `url`
+pub(crate) async fn extract_response(
+    url: &str,
+    html_response: HtmlPage,
+    options: &ScrapOptions,
+) -> Result<Scraped> {
+    check_homographs(&html_response.url, options)?;
+    // json apis and content negotiating endpoints
+    if is_json(html_response.content_type.as_deref()) {
+        return Ok(Scraped::Json(serde_json::from_str(&html_response.content)?));
+    }
+    if xml::is_generic_xml(html_response.content_type.as_deref()) {
+        return Ok(Scraped::Xml(html_response.content));
+    }
+    // fediverse posts: prefer the activitypub object over the html shell
+    if let Some(object_url) =
+        activitypub::discover_object_url(&html_response.content, &html_response.url)
+    {
+        if let Ok(mut article) =
+            activitypub::grab_object(&object_url, &html_response.url, options).await
+        {
+            article.redirects = html_response.redirects;
+            article.tags = limit_tags(article.tags, options);
+            return Ok(Scraped::Webpage(Box::new(article)));
+        }
+    }
+    if !options.robust {
+        return Ok(Scraped::Webpage(Box::new(extract_webpage(
+            url,
+            html_response,
+            options,
+        )?)));
+    }
+    let page_url = html_response.url.clone();
+    let article = catch_panic(|| extract_webpage(url, html_response, options))
+        .map_err(|message| anyhow!(ScraperError::Malformed(message, page_url)))??;
+    Ok(Scraped::Webpage(Box::new(article)))
+}
+
+/// Element nesting extracted, deeper documents overflowing the stack of `extract_html_infos`
+const MAX_NESTING: usize = 512;
+
+/// Error message for a document nested deeper than `MAX_NESTING`
+fn check_nesting(document: &Html) -> Result<(), String> {
+    let too_deep = document
+        .tree
+        .nodes()
+        .any(|node| !node.has_children() && node.ancestors().nth(MAX_NESTING).is_some());
+    match too_deep {
+        true => Err(format!("elements nested more than {} deep", MAX_NESTING)),
+        false => Ok(()),
+    }
+}
+
+thread_local! {
+    /// Set while an extraction runs under `catch_panic`
+    static CATCHING_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `extract`, a panic being returned as its message instead of printed
+fn catch_panic<T>(extract: impl FnOnce() -> T) -> Result<T, String> {
+    static QUIET_HOOK: Once = Once::new();
+    QUIET_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !CATCHING_PANIC.with(Cell::get) {
+                hook(info);
+            }
+        }));
+    });
+    CATCHING_PANIC.with(|catching| catching.set(true));
+    let result = std::panic::catch_unwind(AssertUnwindSafe(extract));
+    CATCHING_PANIC.with(|catching| catching.set(false));
+    result.map_err(|panic| {
+        panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or("panic".to_string())
+    })
+}
+
+/// Metadata of an html response, `url` being the requested url
+pub fn extract_webpage(
+    url: &str,
+    html_response: HtmlPage,
+    options: &ScrapOptions,
+) -> Result<ScrapedWebpage> {
+    let content = html_response.content.clone();
+    let document = Html::parse_document(&content);
+    if let Err(message) = check_nesting(&document) {
+        return Err(anyhow!(ScraperError::Malformed(message, html_response.url)));
+    }
     // extract infos
-    let html: HTML = extract_html_infos(html_response.content)?;
+    let forms = forms::extract_forms(&html_response.content, &html_response.url);
+    let relations = relations::extract_relations(
+        &html_response.headers,
+        &html_response.content,
+        &html_response.url,
+    );
+    let html: HTML = extract_html_page(html_response.content, &html_response.url)?;
+    // the canonical url may be relative
+    let (domain, registrable_domain) = domains::domain_fields(&html_response.url);
+    let mut warnings = homograph::host_warnings(url);
+    for warning in homograph::host_warnings(&html_response.url) {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    let entities = structured::json_ld_entities(&document);
+    let price = price::extract_price(&document, &entities);
+    let open_graph = opengraph::extract_open_graph(&document, &html_response.url);
+    let twitter = twitter::extract_twitter_card(&document, &html_response.url);
+    let favicon = relations::favicon_url(&relations, &html_response.url);
+    let author = author::extract_author(&document, &entities, &html_response.url);
+    let podcast = podcast::extract_episode(&document, &entities, &html_response.url);
+    let readability = readability::extract_readability(&document, html.language.as_deref());
+    let canonical_url = relations
+        .iter()
+        .find(|relation| relation.rel == "canonical")
+        .map(|relation| relation.target.clone());
     // populate article for saving
     let article = ScrapedWebpage {
-        title: html.title.unwrap_or("No title".to_string()),
-        url: html.url.unwrap_or(html_response.url),
-        description: html.description,
+        title: html
+            .title
+            .or(open_graph.title.clone())
+            .or(twitter.title.clone())
+            .unwrap_or("No title".to_string()),
+        url: canonical_url
+            .clone()
+            .filter(|_| options.prefer_canonical)
+            .unwrap_or(html_response.url.clone()),
+        canonical_url,
+        final_url: html_response.url,
+        redirects: html_response.redirects,
+        domain,
+        registrable_domain,
+        status: html_response.status,
+        description: open_graph
+            .description
+            .clone()
+            .or(html.description)
+            .or(twitter.description.clone()),
         language: html.language,
+        author,
+        published_at: published::extract_published_at(&document, &entities),
+        tags: limit_tags(tags::extract_tags(&document), options),
+        image: open_graph.image.clone().or(twitter.image.clone()),
+        open_graph,
+        twitter,
+        attachments: vec![],
+        forms,
+        headers: capture_headers(&html_response.headers, &options.capture_headers),
+        favicon,
+        favicon_file: None,
+        relations,
+        http: Some(html_response.http),
+        adult_content: rating::is_adult_content(&content),
+        cacheable_until: cache::cacheable_until(&html_response.headers, SystemTime::now())
+            .map(|until| humantime::format_rfc3339_seconds(until).to_string()),
+        price: price.as_ref().map(|price| price.amount),
+        currency: price.and_then(|price| price.currency),
+        job: jobposting::extract_job_posting(&entities),
+        rating: reviews::extract_rating(&entities),
+        reviews: reviews::extract_reviews(&entities),
+        podcast,
+        video: video::extract_video(&document, &entities),
+        readability,
+        simhash: simhash::extract_simhash(&document),
+        hero_image: None,
+        source: None,
+        warnings,
+        links: match options.strip_tracking {
+            true => html
+                .links
+                .into_iter()
+                .map(|mut link| {
+                    link.url = cleanup::clean_url(&link.url);
+                    link
+                })
+                .collect(),
+            false => html.links,
+        },
+        html: content,
     };
 
     Ok(article)
 }
 
-async fn retrieve_html_page(url: &str) -> Result<HtmlPage> {
+fn limit_tags(mut tags: Vec<String>, options: &ScrapOptions) -> Vec<String> {
+    if let Some(max_tags) = options.max_tags {
+        tags.truncate(max_tags);
+    }
+    tags
+}
+
+pub(crate) fn check_homographs(url: &str, options: &ScrapOptions) -> Result<()> {
+    if options.deny_homographs {
+        if let Some(warning) = homograph::host_warnings(url).first() {
+            return Err(anyhow!("Refusing {}: {}", url, warning));
+        }
+    }
+    Ok(())
+}
+
+fn capture_headers(headers: &HeaderMap, names: &[String]) -> BTreeMap<String, String> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let values: Vec<&str> = headers
+                .get_all(name.as_str())
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect();
+            (!values.is_empty()).then(|| (name.to_lowercase(), values.join(", ")))
+        })
+        .collect()
+}
+
+pub(crate) fn is_json(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        mime == "application/json" || mime.ends_with("+json")
+    })
+}
+
+pub const USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/116.0";
+pub const ACCEPT_HTML: &str = "text/html,application/xhtml+xml;q=0.9,*/*;q=0.8";
+pub const ACCEPT_JSON: &str = "application/json,*/*;q=0.5";
+/// Request headers dropped when a redirect leaves the requested origin
+const SENSITIVE_HEADERS: [header::HeaderName; 3] = [
+    header::AUTHORIZATION,
+    header::COOKIE,
+    header::PROXY_AUTHORIZATION,
+];
+
+pub(crate) async fn retrieve_html_page(url: &str, options: &ScrapOptions) -> Result<HtmlPage> {
+    let cache = options
+        .cache
+        .as_ref()
+        .filter(|_| options.method == Method::GET && options.body.is_none());
+    let Some(cache) = cache else {
+        return fetch_with_retries(url, options).await;
+    };
+    if let Some(cached) = cache.get(url, &options.accept) {
+        return Ok(HtmlPage {
+            headers: cached.header_map(),
+            url: cached.url,
+            status: cached.status,
+            content: cached.content,
+            content_type: cached.content_type,
+            http: cached.http,
+            redirects: cached.redirects,
+        });
+    }
+    let page = fetch_with_retries(url, options).await?;
+    let cached = CachedResponse {
+        url: page.url.clone(),
+        status: page.status,
+        content: page.content.clone(),
+        content_type: page.content_type.clone(),
+        headers: page
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        http: page.http.clone(),
+        redirects: page.redirects.clone(),
+    };
+    // keyed by the requested url, redirects included
+    cache.put(url, &options.accept, &cached)?;
+    Ok(page)
+}
+
+/// Fetch a page, retrying transient failures with exponential backoff; a `POST` or
+/// another unsafe method is sent once, the server may have acted on it already
+async fn fetch_with_retries(url: &str, options: &ScrapOptions) -> Result<HtmlPage> {
+    let retries = match options.method {
+        Method::GET | Method::HEAD => options.retries,
+        _ => 0,
+    };
+    let mut attempt = 0;
+    loop {
+        match fetch_html_page(url, options).await {
+            Err(e) if attempt < retries && retry::is_transient(&e) => {
+                tokio::time::sleep(retry::backoff(options.retry_delay, attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Client with the headers and cookies of the options, extra headers and timeouts
+/// being set per request
+fn build_client(options: &ScrapOptions) -> Result<Client> {
     let mut headers = header::HeaderMap::new();
     headers.insert(
         header::USER_AGENT,
-        header::HeaderValue::from_static(
-            "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/116.0",
-        ),
+        header::HeaderValue::from_str(&options.user_agent)?,
+    );
+    headers.insert(
+        header::ACCEPT,
+        header::HeaderValue::from_str(&options.accept)?,
     );
-    let client = Client::builder()
-        .gzip(true)
-        .timeout(Duration::from_secs(2))
+    // decompressed by hand to measure the transfer
+    headers.insert(
+        header::ACCEPT_ENCODING,
+        header::HeaderValue::from_static(transfer::ACCEPT_ENCODING),
+    );
+    // followed by hand to report the chain
+    let mut client = Client::builder()
         .default_headers(headers)
-        .build()?;
-    match client.get(url).send().await {
-        Ok(response) => {
-            if response.status().is_client_error() {
-                return Err(anyhow!(ScraperError::Client(
-                    response.status(),
-                    url.to_string()
-                )));
-            }
-            if response.status().is_server_error() {
-                return Err(anyhow!(ScraperError::Server(
-                    response.status(),
-                    url.to_string()
-                )));
-            }
+        .redirect(Policy::none());
+    if options.impersonate.is_some() {
+        client = client.http1_title_case_headers();
+    }
+    if let Some(cookie_jar) = &options.cookie_jar {
+        client = client.cookie_provider(cookie_jar.clone());
+    }
+    Ok(with_network(client, options).build()?)
+}
+
+/// A client routed through the proxy and the dns cache of the options
+pub(crate) fn with_network(mut client: ClientBuilder, options: &ScrapOptions) -> ClientBuilder {
+    if let Some(proxy) = &options.proxy {
+        client = client.proxy(proxy.clone());
+    }
+    if let Some(dns_cache) = &options.dns_cache {
+        client = client.dns_resolver(Arc::new(CachedResolver(dns_cache.clone())));
+    }
+    client
+}
 
-            let url = response.url().to_string();
-            let content = response.text().await?;
+/// Headers of the impersonated browser in its order, sent before the client defaults
+fn browser_headers(options: &ScrapOptions) -> Result<header::HeaderMap> {
+    let mut headers = header::HeaderMap::new();
+    let Some(browser) = options.impersonate else {
+        return Ok(headers);
+    };
+    for (name, value) in browser.headers() {
+        let value = match *name {
+            "user-agent" => &options.user_agent,
+            "accept" => &options.accept,
+            "accept-encoding" => transfer::ACCEPT_ENCODING,
+            _ => value,
+        };
+        headers.insert(
+            header::HeaderName::from_static(name),
+            header::HeaderValue::from_str(value)?,
+        );
+    }
+    Ok(headers)
+}
 
-            Ok(HtmlPage { url, content })
+async fn fetch_html_page(url: &str, options: &ScrapOptions) -> Result<HtmlPage> {
+    let client = match &options.client {
+        Some(client) => client.clone(),
+        None => build_client(options)?,
+    };
+    let timeout = match &options.adaptive_timeout {
+        Some(adaptive_timeout) => adaptive_timeout.timeout_for(url),
+        None => options.timeout,
+    };
+    let mut headers = browser_headers(options)?;
+    for (name, value) in &options.headers {
+        headers.insert(
+            header::HeaderName::from_bytes(name.as_bytes())?,
+            header::HeaderValue::from_str(value)?,
+        );
+    }
+    let invalid = |e: url::ParseError| anyhow!(ScraperError::Other(e.to_string(), url.to_string()));
+    let origin = Url::parse(url).map_err(invalid)?.origin();
+    let mut method = options.method.clone();
+    let mut body = options.body.clone();
+    let mut content_type = options.content_type.clone();
+    let mut current = url.to_string();
+    let mut redirects = vec![];
+    let mut response = loop {
+        let mut request = client
+            .request(method.clone(), &current)
+            .headers(headers.clone())
+            .timeout(timeout);
+        if let Some(content_type) = &content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(body) = &body {
+            request = request.body(body.clone());
+        }
+        let response = request.send().await.map_err(|e| request_error(e, url))?;
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok());
+        // 304 has no location
+        let (true, Some(location)) = (status.is_redirection(), location) else {
+            break response;
+        };
+        if redirects.len() >= options.max_redirects {
+            return Err(anyhow!(ScraperError::TooManyRedirects(
+                url.to_string(),
+                options.max_redirects
+            )));
         }
-        Err(e) => {
-            if e.is_timeout() {
-                Err(anyhow!(ScraperError::Timeout(
-                    e.to_string(),
-                    url.to_string()
-                )))
-            } else {
-                Err(anyhow!(ScraperError::Other(e.to_string(), url.to_string())))
+        let next = Url::parse(&current)
+            .and_then(|current| current.join(location))
+            .map_err(invalid)?;
+        if status == StatusCode::SEE_OTHER
+            || (method == Method::POST
+                && matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND))
+        {
+            method = Method::GET;
+            body = None;
+            content_type = None;
+        }
+        // credentials stay with the requested origin
+        if next.origin() != origin {
+            for name in SENSITIVE_HEADERS {
+                headers.remove(name);
             }
         }
+        redirects.push(std::mem::replace(&mut current, next.to_string()));
+    };
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        // challenges are mostly served as errors, their markup tells them apart
+        let mut challenge = None;
+        if matches!(status.as_u16(), 403 | 429 | 503) {
+            let headers = response.headers().clone();
+            let content_type = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string());
+            // decompressed by hand like successful bodies, the client does not do it
+            let body = response.bytes().await.unwrap_or_default().to_vec();
+            let content = transfer::decompress(body, &headers)
+                .map(|body| transfer::decode_text(&body, content_type.as_deref()))
+                .unwrap_or_default();
+            challenge = antibot::detect_challenge(status.as_u16(), &headers, &content);
+        }
+        let error = match challenge {
+            Some(challenge) => ScraperError::Blocked(challenge, url.to_string()),
+            None if status.is_client_error() => ScraperError::Client(status, url.to_string()),
+            None => ScraperError::Server(status, url.to_string()),
+        };
+        return Err(anyhow!(error));
     }
+
+    let url = response.url().to_string();
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let version = response.version();
+    let mut wire = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        if let Some(bandwidth) = &options.bandwidth {
+            bandwidth.acquire(chunk.len()).await;
+        }
+        wire.extend_from_slice(&chunk);
+    }
+    let wire_bytes = wire.len();
+    let body = transfer::decompress(wire, &headers)?;
+    let http = HttpInfo::new(version, &headers, wire_bytes, body.len());
+    let content = transfer::decode_text(&body, content_type.as_deref());
+    if let Some(challenge) = antibot::detect_challenge(status, &headers, &content) {
+        return Err(anyhow!(ScraperError::Blocked(challenge, url)));
+    }
+
+    Ok(HtmlPage {
+        url,
+        status,
+        content,
+        content_type,
+        headers,
+        http,
+        redirects,
+    })
 }
 
-fn extract_html_infos(response: String) -> Result<HTML> {
+fn request_error(e: reqwest::Error, url: &str) -> anyhow::Error {
+    if e.is_timeout() {
+        anyhow!(ScraperError::Timeout(e.to_string(), url.to_string()))
+    } else if e.is_connect() || e.is_request() {
+        anyhow!(ScraperError::Connection(e.to_string(), url.to_string()))
+    } else {
+        anyhow!(ScraperError::Other(e.to_string(), url.to_string()))
+    }
+}
+
+pub fn extract_html_infos(response: String) -> Result<HTML> {
+    check_nesting(&Html::parse_document(&response)).map_err(|message| anyhow!(message))?;
     // webpage
     let html = HTML::from_string(response, None)?;
     Ok(html)
 }
+
+/// Text of the first element matching a css selector
+pub fn select_text(html: &str, selector: &str) -> Result<Option<String>> {
+    let selector =
+        Selector::parse(selector).map_err(|e| anyhow!("Invalid selector {}: {}", selector, e))?;
+    let document = Html::parse_document(html);
+    let text = document
+        .select(&selector)
+        .next()
+        .map(|element| element.text().collect::<String>().trim().to_string());
+    Ok(text)
+}
+
+/// Same as `extract_html_infos`, with links resolved against the page url
+fn extract_html_page(response: String, url: &str) -> Result<HTML> {
+    let html = HTML::from_string(response, Some(url.to_string()))?;
+    Ok(html)
+}
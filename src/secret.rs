@@ -0,0 +1,40 @@
+use anyhow::{anyhow, Result};
+use keyring::Entry;
+
+const SERVICE: &str = "scrapr";
+const PLACEHOLDER_START: &str = "{{secret:";
+const PLACEHOLDER_END: &str = "}}";
+
+/// Store a secret in the OS keyring
+pub fn set_secret(name: &str, value: &str) -> Result<()> {
+    Entry::new(SERVICE, name)?.set_password(value)?;
+    Ok(())
+}
+
+pub fn delete_secret(name: &str) -> Result<()> {
+    Entry::new(SERVICE, name)?.delete_credential()?;
+    Ok(())
+}
+
+fn get_secret(name: &str) -> Result<String> {
+    Entry::new(SERVICE, name)?
+        .get_password()
+        .map_err(|e| anyhow!("Secret {} not found: {}", name, e))
+}
+
+/// Replace `{{secret:NAME}}` placeholders with keyring values
+pub fn resolve_secrets(text: &str) -> Result<String> {
+    let mut resolved = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        let after = &rest[start + PLACEHOLDER_START.len()..];
+        let Some(end) = after.find(PLACEHOLDER_END) else {
+            break;
+        };
+        resolved.push_str(&rest[..start]);
+        resolved.push_str(&get_secret(after[..end].trim())?);
+        rest = &after[end + PLACEHOLDER_END.len()..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
@@ -0,0 +1,59 @@
+use scraper::Html;
+
+use crate::keywords::content_segments;
+
+/// Words hashed together, so that pages sharing words but not sentences differ
+const SHINGLE: usize = 3;
+
+/// 64-bit simhash of the words of the main content, as 16 hex digits; none without words
+pub fn extract_simhash(document: &Html) -> Option<String> {
+    let words: Vec<String> = content_segments(document).into_iter().flatten().collect();
+    simhash(&words).map(|hash| format!("{:016x}", hash))
+}
+
+/// Simhash of plain text
+pub fn text_simhash(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    simhash(&words).map(|hash| format!("{:016x}", hash))
+}
+
+/// Share of equal bits of two simhashes, from 0 to 1
+pub fn similarity(a: &str, b: &str) -> Option<f64> {
+    let a = u64::from_str_radix(a, 16).ok()?;
+    let b = u64::from_str_radix(b, 16).ok()?;
+    Some(1.0 - (a ^ b).count_ones() as f64 / 64.0)
+}
+
+/// Each bit is the majority of that bit over the hashes of the shingles
+fn simhash(words: &[String]) -> Option<u64> {
+    if words.is_empty() {
+        return None;
+    }
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE.min(words.len())) {
+        let hash = fnv1a(shingle.join(" ").as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            match hash >> bit & 1 {
+                1 => *weight += 1,
+                _ => *weight -= 1,
+            }
+        }
+    }
+    let hash = weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit);
+    Some(hash)
+}
+
+/// 64-bit FNV-1a, stable across runs and platforms unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,96 @@
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use sxd_document::parser;
+use sxd_xpath::{evaluate_xpath, Context, Factory, Value};
+
+use crate::scrap::{grab_url, ScrapOptions, Scraped};
+#[cfg(feature = "store")]
+use crate::store::Store;
+use crate::structured::parse_datetime;
+
+/// Nesting of sitemap indexes followed at most
+const MAX_DEPTH: usize = 3;
+
+/// An url of a sitemap
+#[derive(Debug, Clone, Serialize)]
+pub struct SitemapEntry {
+    pub url: String,
+    pub lastmod: Option<SystemTime>,
+}
+
+/// Urls of a sitemap, those of the sitemaps of an index included
+pub async fn fetch_sitemap(url: &str, options: &ScrapOptions) -> Result<Vec<SitemapEntry>> {
+    let mut entries = vec![];
+    let mut pending = vec![(url.to_string(), 0)];
+    while let Some((url, depth)) = pending.pop() {
+        let Scraped::Xml(content) = grab_url(&url, options).await? else {
+            bail!("{} is not a xml sitemap", url);
+        };
+        let (urls, sitemaps) = parse_sitemap(&content)?;
+        entries.extend(urls);
+        if depth < MAX_DEPTH {
+            // popped in document order
+            pending.extend(
+                sitemaps
+                    .into_iter()
+                    .rev()
+                    .map(|sitemap| (sitemap, depth + 1)),
+            );
+        }
+    }
+    Ok(entries)
+}
+
+/// Urls of a `<urlset>` and sitemaps of a `<sitemapindex>`
+pub fn parse_sitemap(content: &str) -> Result<(Vec<SitemapEntry>, Vec<String>)> {
+    let package = parser::parse(content).map_err(|e| anyhow!("Invalid sitemap: {}", e))?;
+    let document = package.as_document();
+    let factory = Factory::new();
+    let context = Context::new();
+    let text = |node, expression: &str| {
+        let xpath = factory.build(expression).ok()??;
+        let value = xpath.evaluate(&context, node).ok()?.string();
+        Some(value.trim().to_string()).filter(|value| !value.is_empty())
+    };
+    let nodes = |expression| match evaluate_xpath(&document, expression) {
+        Ok(Value::Nodeset(nodes)) => nodes.document_order(),
+        _ => vec![],
+    };
+    // sitemaps live in a namespace
+    let urls = nodes("//*[local-name() = 'url']")
+        .into_iter()
+        .filter_map(|node| {
+            Some(SitemapEntry {
+                url: text(node, "string(*[local-name() = 'loc'])")?,
+                lastmod: text(node, "string(*[local-name() = 'lastmod'])")
+                    .and_then(|lastmod| parse_datetime(&lastmod)),
+            })
+        })
+        .collect();
+    let sitemaps = nodes("//*[local-name() = 'sitemap']")
+        .into_iter()
+        .filter_map(|node| text(node, "string(*[local-name() = 'loc'])"))
+        .collect();
+    Ok((urls, sitemaps))
+}
+
+/// Entries modified since their latest stored snapshot, and the count of the
+/// unchanged ones. Urls without `<lastmod>` or snapshot are always changed
+#[cfg(feature = "store")]
+pub fn changed_entries(
+    entries: Vec<SitemapEntry>,
+    store: &Store,
+) -> Result<(Vec<SitemapEntry>, usize)> {
+    let mut changed = vec![];
+    let mut unchanged = 0;
+    for entry in entries {
+        let fetched = store.last_fetched(&entry.url)?;
+        match (entry.lastmod, fetched) {
+            (Some(lastmod), Some(fetched)) if lastmod <= fetched => unchanged += 1,
+            _ => changed.push(entry),
+        }
+    }
+    Ok((changed, unchanged))
+}
@@ -0,0 +1,16 @@
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+/// Current time in rfc3339, to the second
+pub fn now() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+/// Sha256 of a text in hex, naming snapshots and cached files
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
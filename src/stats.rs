@@ -0,0 +1,216 @@
+use std::fmt::Write;
+use std::ops::AddAssign;
+
+use anyhow::{bail, Result};
+use scraper::{Html, Selector};
+use serde::Serialize;
+
+use crate::domains;
+use crate::format::{format_json, format_json_line, text_line, Format, Shape};
+use crate::scrap::ScrapedWebpage;
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Headings {
+    pub h1: usize,
+    pub h2: usize,
+    pub h3: usize,
+    pub h4: usize,
+    pub h5: usize,
+    pub h6: usize,
+}
+
+impl Headings {
+    fn levels(&self) -> [usize; 6] {
+        [self.h1, self.h2, self.h3, self.h4, self.h5, self.h6]
+    }
+}
+
+/// Structural counts of a page, or of a batch
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Counts {
+    /// Words and sentences of the main content, as counted for readability
+    pub words: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+    pub headings: Headings,
+    /// Links to the registrable domain of the page
+    pub internal_links: usize,
+    pub external_links: usize,
+    pub images: usize,
+    pub tables: usize,
+    pub forms: usize,
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        self.words += other.words;
+        self.sentences += other.sentences;
+        self.paragraphs += other.paragraphs;
+        self.headings.h1 += other.headings.h1;
+        self.headings.h2 += other.headings.h2;
+        self.headings.h3 += other.headings.h3;
+        self.headings.h4 += other.headings.h4;
+        self.headings.h5 += other.headings.h5;
+        self.headings.h6 += other.headings.h6;
+        self.internal_links += other.internal_links;
+        self.external_links += other.external_links;
+        self.images += other.images;
+        self.tables += other.tables;
+        self.forms += other.forms;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageStats {
+    pub url: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Stats of the pages of a batch, with their total
+#[derive(Debug, Serialize)]
+pub struct BatchStats {
+    pub pages: Vec<PageStats>,
+    pub total: Counts,
+}
+
+pub fn page_stats(page: &ScrapedWebpage) -> PageStats {
+    let document = Html::parse_document(&page.html);
+    let count = |selector: &str| {
+        let selector = Selector::parse(selector).expect("valid selector");
+        document.select(&selector).count()
+    };
+    let page_domain = domains::url_domain(&page.url);
+    // mailto and javascript links are neither
+    let web_links: Vec<Option<String>> = page
+        .links
+        .iter()
+        .filter(|link| link.url.starts_with("http://") || link.url.starts_with("https://"))
+        .map(|link| domains::url_domain(&link.url))
+        .collect();
+    let internal_links = web_links
+        .iter()
+        .filter(|domain| page_domain.is_some() && **domain == page_domain)
+        .count();
+    let readability = page.readability.as_ref();
+    PageStats {
+        url: page.url.clone(),
+        counts: Counts {
+            words: readability.map_or(0, |readability| readability.words),
+            sentences: readability.map_or(0, |readability| readability.sentences),
+            paragraphs: count("p"),
+            headings: Headings {
+                h1: count("h1"),
+                h2: count("h2"),
+                h3: count("h3"),
+                h4: count("h4"),
+                h5: count("h5"),
+                h6: count("h6"),
+            },
+            internal_links,
+            external_links: web_links.len() - internal_links,
+            images: count("img"),
+            tables: count("table"),
+            forms: page.forms.len(),
+        },
+    }
+}
+
+pub fn batch_stats(pages: &[ScrapedWebpage]) -> BatchStats {
+    let pages: Vec<PageStats> = pages.iter().map(page_stats).collect();
+    let mut total = Counts::default();
+    for page in &pages {
+        total += page.counts;
+    }
+    BatchStats { pages, total }
+}
+
+/// A table of the pages, with a total row for several pages; a json record of one page,
+/// or the pages and their total
+pub fn format_stats(stats: &BatchStats, format: Format, shape: &Shape) -> Result<String> {
+    let mut output = String::new();
+    let several = stats.pages.len() > 1;
+    match format {
+        Format::Json if several => writeln!(output, "{}", format_json(stats, shape)?)?,
+        Format::Json => {
+            for page in &stats.pages {
+                writeln!(output, "{}", format_json(page, shape)?)?;
+            }
+        }
+        Format::Jsonl => {
+            for page in &stats.pages {
+                writeln!(output, "{}", format_json_line(page, shape)?)?;
+            }
+        }
+        Format::Text => {
+            for page in &stats.pages {
+                writeln!(output, "{}", stats_line(&page.url, &page.counts))?;
+            }
+            if several {
+                writeln!(output, "{}", stats_line("total", &stats.total))?;
+            }
+        }
+        Format::Markdown => {
+            writeln!(
+                output,
+                "| url | words | sentences | paragraphs | h1/h2/h3/h4/h5/h6 | internal links | external links | images | tables | forms |"
+            )?;
+            writeln!(
+                output,
+                "| --- | ---: | ---: | ---: | --- | ---: | ---: | ---: | ---: | ---: |"
+            )?;
+            for page in &stats.pages {
+                writeln!(output, "{}", stats_row(&page.url, &page.counts))?;
+            }
+            if several {
+                writeln!(output, "{}", stats_row("**total**", &stats.total))?;
+            }
+        }
+        _ => bail!("--style stats applies to the markdown, json, jsonl and text formats"),
+    }
+    Ok(output)
+}
+
+fn heading_levels(headings: &Headings) -> String {
+    let levels: Vec<String> = headings.levels().iter().map(usize::to_string).collect();
+    levels.join("/")
+}
+
+fn stats_row(label: &str, counts: &Counts) -> String {
+    format!(
+        "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+        label,
+        counts.words,
+        counts.sentences,
+        counts.paragraphs,
+        heading_levels(&counts.headings),
+        counts.internal_links,
+        counts.external_links,
+        counts.images,
+        counts.tables,
+        counts.forms
+    )
+}
+
+fn stats_line(label: &str, counts: &Counts) -> String {
+    let mut fields = vec![
+        label.to_string(),
+        counts.words.to_string(),
+        counts.sentences.to_string(),
+        counts.paragraphs.to_string(),
+    ];
+    fields.extend(counts.headings.levels().iter().map(usize::to_string));
+    fields.extend(
+        [
+            counts.internal_links,
+            counts.external_links,
+            counts.images,
+            counts.tables,
+            counts.forms,
+        ]
+        .iter()
+        .map(usize::to_string),
+    );
+    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+    text_line(&fields)
+}
@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::format::page_text;
+use crate::scrap::ScrapedWebpage;
+use crate::stamp::{content_hash, now};
+
+const ARCHIVE_VERSION: u32 = 1;
+const WEEK: u64 = 7 * 24 * 3600;
+
+/// A schema change, applied once in order; the schema version is sqlite `user_version`
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "links",
+        sql: "CREATE TABLE links (
+            id INTEGER PRIMARY KEY,
+            url TEXT NOT NULL UNIQUE,
+            title TEXT NOT NULL,
+            description TEXT,
+            language TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );",
+    },
+    Migration {
+        version: 2,
+        description: "snapshots of each scrape",
+        sql: "CREATE TABLE snapshots (
+            id INTEGER PRIMARY KEY,
+            link_id INTEGER NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+            fetched_at TEXT NOT NULL,
+            status INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT,
+            content_hash TEXT NOT NULL
+        );
+        CREATE INDEX snapshots_link ON snapshots(link_id, fetched_at);",
+    },
+    Migration {
+        version: 3,
+        description: "link tags",
+        sql: "CREATE TABLE link_tags (
+            link_id INTEGER NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (link_id, tag)
+        );",
+    },
+    Migration {
+        version: 4,
+        description: "failed urls queue",
+        sql: "CREATE TABLE failed (
+            url TEXT PRIMARY KEY,
+            source TEXT NOT NULL,
+            error TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            first_failed_at TEXT NOT NULL,
+            last_failed_at TEXT NOT NULL
+        );",
+    },
+    Migration {
+        version: 5,
+        description: "response latencies per host",
+        sql: "CREATE TABLE latencies (
+            id INTEGER PRIMARY KEY,
+            host TEXT NOT NULL,
+            latency_ms INTEGER NOT NULL,
+            measured_at TEXT NOT NULL
+        );
+        CREATE INDEX latencies_host ON latencies(host, id);",
+    },
+    Migration {
+        version: 6,
+        description: "extracted text of snapshots",
+        sql: "ALTER TABLE snapshots ADD COLUMN text TEXT;",
+    },
+];
+
+/// Latencies kept per host for `--timeout auto`
+const LATENCY_HISTORY: i64 = 100;
+
+/// Sqlite link database, `store.sqlite` in the data directory
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Open the store, applying pending migrations
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        let store = Self::open_without_migrations(data_dir)?;
+        store.migrate()?;
+        Ok(store)
+    }
+
+    pub fn open_without_migrations(data_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(data_dir)?;
+        let connection = Connection::open(data_dir.join("store.sqlite"))?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        Ok(Self { connection })
+    }
+
+    pub fn version(&self) -> Result<u32> {
+        Ok(self
+            .connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    pub fn pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+        let version = self.version()?;
+        Ok(MIGRATIONS
+            .iter()
+            .filter(|migration| migration.version > version)
+            .collect())
+    }
+
+    /// Apply pending migrations, each in its own transaction
+    pub fn migrate(&self) -> Result<Vec<&'static Migration>> {
+        let pending = self.pending_migrations()?;
+        for migration in &pending {
+            let transaction = self.connection.unchecked_transaction()?;
+            transaction.execute_batch(migration.sql)?;
+            transaction.pragma_update(None, "user_version", migration.version)?;
+            transaction.commit()?;
+        }
+        Ok(pending)
+    }
+
+    /// Queue a failed url, or count one more attempt when already queued
+    pub fn record_failure(&self, url: &str, source: &str, error: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO failed (url, source, error, attempts, first_failed_at, last_failed_at)
+             VALUES (?1, ?2, ?3, 1, ?4, ?4)
+             ON CONFLICT(url) DO UPDATE SET
+                error = excluded.error,
+                attempts = failed.attempts + 1,
+                last_failed_at = excluded.last_failed_at",
+            params![url, source, error, now()],
+        )?;
+        Ok(())
+    }
+
+    /// Record a response latency, keeping the most recent ones of the host
+    pub fn record_latency(&self, host: &str, latency: Duration) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO latencies (host, latency_ms, measured_at) VALUES (?1, ?2, ?3)",
+            params![host, latency.as_millis() as i64, now()],
+        )?;
+        self.connection.execute(
+            "DELETE FROM latencies WHERE host = ?1 AND id NOT IN (
+                SELECT id FROM latencies WHERE host = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            params![host, LATENCY_HISTORY],
+        )?;
+        Ok(())
+    }
+
+    /// 95th percentile of the recorded latencies, per host
+    pub fn latency_p95(&self) -> Result<HashMap<String, Duration>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT host, latency_ms FROM latencies ORDER BY host, latency_ms")?;
+        let mut by_host: HashMap<String, Vec<i64>> = HashMap::new();
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (host, latency) = row?;
+            by_host.entry(host).or_default().push(latency);
+        }
+        Ok(by_host
+            .into_iter()
+            .map(|(host, latencies)| {
+                // sorted by the query
+                let index = (latencies.len() * 95).div_ceil(100).saturating_sub(1);
+                (host, Duration::from_millis(latencies[index] as u64))
+            })
+            .collect())
+    }
+
+    pub fn failures(&self) -> Result<Vec<FailedUrl>> {
+        let mut statement = self.connection.prepare(
+            "SELECT url, source, error, attempts, first_failed_at, last_failed_at
+             FROM failed ORDER BY first_failed_at, url",
+        )?;
+        let failures = statement
+            .query_map([], |row| {
+                Ok(FailedUrl {
+                    url: row.get(0)?,
+                    source: row.get(1)?,
+                    error: row.get(2)?,
+                    attempts: row.get(3)?,
+                    first_failed_at: row.get(4)?,
+                    last_failed_at: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(failures)
+    }
+
+    pub fn remove_failure(&self, url: &str) -> Result<()> {
+        self.connection
+            .execute("DELETE FROM failed WHERE url = ?1", params![url])?;
+        Ok(())
+    }
+
+    /// Insert or update the link and record a snapshot of this scrape
+    pub fn save(&self, page: &ScrapedWebpage) -> Result<()> {
+        let now = now();
+        self.connection.execute(
+            "INSERT INTO links (url, title, description, language, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+             ON CONFLICT(url) DO UPDATE SET
+                title = excluded.title,
+                description = excluded.description,
+                language = excluded.language,
+                updated_at = excluded.updated_at",
+            params![page.url, page.title, page.description, page.language, now],
+        )?;
+        let link_id: i64 = self.connection.query_row(
+            "SELECT id FROM links WHERE url = ?1",
+            params![page.url],
+            |row| row.get(0),
+        )?;
+        for tag in &page.tags {
+            self.connection.execute(
+                "INSERT OR IGNORE INTO link_tags (link_id, tag) VALUES (?1, ?2)",
+                params![link_id, tag],
+            )?;
+        }
+        self.connection.execute(
+            "INSERT INTO snapshots (link_id, fetched_at, status, title, description, content_hash, text)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                link_id,
+                now,
+                page.status,
+                page.title,
+                page.description,
+                content_hash(&page.html),
+                page_text(&page.html)
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// An url whose scrape failed, waiting for `scrapr retry-failed`
+#[derive(Debug, Serialize)]
+pub struct FailedUrl {
+    pub url: String,
+    /// What scraped it, e.g. `job:NAME`
+    pub source: String,
+    pub error: String,
+    pub attempts: u32,
+    pub first_failed_at: String,
+    pub last_failed_at: String,
+}
+
+/// Portable copy of the store, see `Store::export` and `Store::import`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    pub exported_at: String,
+    pub links: Vec<ArchivedLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedLink {
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub snapshots: Vec<ArchivedSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedSnapshot {
+    pub fetched_at: String,
+    pub status: u16,
+    pub title: String,
+    pub description: Option<String>,
+    pub content_hash: String,
+    /// Extracted text, unknown for snapshots saved before schema version 6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// What to do when an imported link already exists
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum OnConflict {
+    /// Keep the stored link
+    Skip,
+    /// Replace the stored link
+    Overwrite,
+    /// Keep the most recently updated one
+    Newest,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub snapshots: usize,
+}
+
+impl Store {
+    pub fn export(&self) -> Result<Archive> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, url, title, description, language, created_at, updated_at
+             FROM links ORDER BY id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                ArchivedLink {
+                    url: row.get(1)?,
+                    title: row.get(2)?,
+                    description: row.get(3)?,
+                    language: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    tags: vec![],
+                    snapshots: vec![],
+                },
+            ))
+        })?;
+        let mut links = vec![];
+        for row in rows {
+            let (link_id, mut link) = row?;
+            link.tags = self.tags(link_id)?;
+            link.snapshots = self.snapshots(link_id)?;
+            links.push(link);
+        }
+        Ok(Archive {
+            version: ARCHIVE_VERSION,
+            exported_at: now(),
+            links,
+        })
+    }
+
+    /// Most recent link update, `None` for an empty store
+    pub fn last_updated(&self) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row("SELECT max(updated_at) FROM links", [], |row| row.get(0))?)
+    }
+
+    fn tags(&self, link_id: i64) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT tag FROM link_tags WHERE link_id = ?1 ORDER BY tag")?;
+        let tags = statement
+            .query_map(params![link_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tags)
+    }
+
+    /// Time of the latest snapshot of an url, `None` when the url is not stored
+    pub fn last_fetched(&self, url: &str) -> Result<Option<SystemTime>> {
+        let fetched_at: Option<String> = self.connection.query_row(
+            "SELECT MAX(snapshots.fetched_at) FROM snapshots
+             JOIN links ON links.id = snapshots.link_id WHERE links.url = ?1",
+            params![url],
+            |row| row.get(0),
+        )?;
+        Ok(fetched_at.and_then(|fetched_at| humantime::parse_rfc3339(&fetched_at).ok()))
+    }
+
+    /// Snapshots of a stored url, oldest first, `None` when the url is not stored
+    pub fn url_snapshots(&self, url: &str) -> Result<Option<Vec<ArchivedSnapshot>>> {
+        let link_id: Option<i64> = self
+            .connection
+            .query_row("SELECT id FROM links WHERE url = ?1", params![url], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        link_id.map(|link_id| self.snapshots(link_id)).transpose()
+    }
+
+    fn snapshots(&self, link_id: i64) -> Result<Vec<ArchivedSnapshot>> {
+        let mut statement = self.connection.prepare(
+            "SELECT fetched_at, status, title, description, content_hash, text
+             FROM snapshots WHERE link_id = ?1 ORDER BY fetched_at, id",
+        )?;
+        let snapshots = statement
+            .query_map(params![link_id], |row| {
+                Ok(ArchivedSnapshot {
+                    fetched_at: row.get(0)?,
+                    status: row.get(1)?,
+                    title: row.get(2)?,
+                    description: row.get(3)?,
+                    content_hash: row.get(4)?,
+                    text: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(snapshots)
+    }
+
+    /// Merge an archive: links by url following `on_conflict`, tags and unknown snapshots added
+    pub fn import(&self, archive: &Archive, on_conflict: OnConflict) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let transaction = self.connection.unchecked_transaction()?;
+        for link in &archive.links {
+            let existing: Option<(i64, String)> = transaction
+                .query_row(
+                    "SELECT id, updated_at FROM links WHERE url = ?1",
+                    params![link.url],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let replace = match (&existing, on_conflict) {
+                (None, _) => true,
+                (Some(_), OnConflict::Skip) => false,
+                (Some(_), OnConflict::Overwrite) => true,
+                // rfc3339 timestamps sort as text
+                (Some((_, updated_at)), OnConflict::Newest) => link.updated_at > *updated_at,
+            };
+            if replace {
+                transaction.execute(
+                    "INSERT INTO links (url, title, description, language, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT(url) DO UPDATE SET
+                        title = excluded.title,
+                        description = excluded.description,
+                        language = excluded.language,
+                        created_at = min(links.created_at, excluded.created_at),
+                        updated_at = excluded.updated_at",
+                    params![
+                        link.url,
+                        link.title,
+                        link.description,
+                        link.language,
+                        link.created_at,
+                        link.updated_at
+                    ],
+                )?;
+            }
+            match (&existing, replace) {
+                (None, _) => report.inserted += 1,
+                (Some(_), true) => report.updated += 1,
+                (Some(_), false) => report.skipped += 1,
+            }
+            let link_id: i64 = transaction.query_row(
+                "SELECT id FROM links WHERE url = ?1",
+                params![link.url],
+                |row| row.get(0),
+            )?;
+            for tag in &link.tags {
+                transaction.execute(
+                    "INSERT OR IGNORE INTO link_tags (link_id, tag) VALUES (?1, ?2)",
+                    params![link_id, tag],
+                )?;
+            }
+            for snapshot in &link.snapshots {
+                report.snapshots += transaction.execute(
+                    "INSERT INTO snapshots (link_id, fetched_at, status, title, description, content_hash, text)
+                     SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7
+                     WHERE NOT EXISTS (
+                        SELECT 1 FROM snapshots
+                        WHERE link_id = ?1 AND fetched_at = ?2 AND content_hash = ?6
+                     )",
+                    params![
+                        link_id,
+                        snapshot.fetched_at,
+                        snapshot.status,
+                        snapshot.title,
+                        snapshot.description,
+                        snapshot.content_hash,
+                        snapshot.text
+                    ],
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(report)
+    }
+}
+
+/// `[retention]` of config.toml: which snapshots `scrapr db prune` keeps
+///
+/// ```toml
+/// [retention]
+/// keep_last = 10
+/// weekly_after = "30days"
+/// purge_after = "1year"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Retention {
+    /// Most recent snapshots of each link, always kept
+    pub keep_last: Option<usize>,
+    /// Age after which one snapshot per week is kept, the newest
+    #[serde(default, deserialize_with = "deserialize_age")]
+    pub weekly_after: Option<Duration>,
+    /// Age after which snapshots are removed
+    #[serde(default, deserialize_with = "deserialize_age")]
+    pub purge_after: Option<Duration>,
+}
+
+fn deserialize_age<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    humantime::parse_duration(&text)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PruneReport {
+    pub kept: usize,
+    pub removed: usize,
+}
+
+impl Store {
+    /// Remove the snapshots left out by a retention policy, or only count them
+    pub fn prune_snapshots(&self, retention: &Retention, dry_run: bool) -> Result<PruneReport> {
+        let mut report = PruneReport::default();
+        let now = SystemTime::now();
+        let transaction = self.connection.unchecked_transaction()?;
+        let link_ids: Vec<i64> = transaction
+            .prepare("SELECT id FROM links")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for link_id in link_ids {
+            let snapshots: Vec<(i64, String)> = transaction
+                .prepare(
+                    "SELECT id, fetched_at FROM snapshots WHERE link_id = ?1
+                     ORDER BY fetched_at DESC, id DESC",
+                )?
+                .query_map(params![link_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?;
+            let mut weeks = vec![];
+            for (index, (snapshot_id, fetched_at)) in snapshots.into_iter().enumerate() {
+                // unreadable dates are kept
+                let Ok(fetched_at) = humantime::parse_rfc3339(&fetched_at) else {
+                    report.kept += 1;
+                    continue;
+                };
+                let age = now.duration_since(fetched_at).unwrap_or_default();
+                let week = fetched_at
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+                    / WEEK;
+                let keep = if retention
+                    .keep_last
+                    .is_some_and(|keep_last| index < keep_last)
+                {
+                    true
+                } else if retention
+                    .purge_after
+                    .is_some_and(|purge_after| age > purge_after)
+                {
+                    false
+                } else if retention
+                    .weekly_after
+                    .is_some_and(|weekly_after| age > weekly_after)
+                {
+                    !weeks.contains(&week)
+                } else {
+                    true
+                };
+                if keep {
+                    weeks.push(week);
+                    report.kept += 1;
+                    continue;
+                }
+                report.removed += 1;
+                if !dry_run {
+                    transaction
+                        .execute("DELETE FROM snapshots WHERE id = ?1", params![snapshot_id])?;
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(report)
+    }
+}
+
+/// Write an archive, gzip compressed when the path ends with `.gz`
+pub fn write_archive(archive: &Archive, path: &Path) -> Result<()> {
+    let file = File::create(path)?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        serde_json::to_writer(&mut encoder, archive)?;
+        encoder.finish()?;
+    } else {
+        serde_json::to_writer_pretty(file, archive)?;
+    }
+    Ok(())
+}
+
+pub fn read_archive(path: &Path) -> Result<Archive> {
+    let file = File::open(path)?;
+    let archive: Archive = if path.extension().is_some_and(|ext| ext == "gz") {
+        serde_json::from_reader(GzDecoder::new(file))?
+    } else {
+        serde_json::from_reader(file)?
+    };
+    check_archive_version(archive)
+}
+
+/// Archive from the bytes of an uncompressed export
+pub fn parse_archive(bytes: &[u8]) -> Result<Archive> {
+    check_archive_version(serde_json::from_slice(bytes)?)
+}
+
+fn check_archive_version(archive: Archive) -> Result<Archive> {
+    if archive.version > ARCHIVE_VERSION {
+        return Err(anyhow!(
+            "Archive version {} is newer than supported {}",
+            archive.version,
+            ARCHIVE_VERSION
+        ));
+    }
+    Ok(archive)
+}
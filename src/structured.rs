@@ -0,0 +1,171 @@
+use std::time::{Duration, SystemTime};
+
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+/// schema.org entities of the JSON-LD scripts of a page: every object with a `@type`,
+/// nested ones and `@graph` members included
+pub fn json_ld_entities(document: &Html) -> Vec<Value> {
+    let selector =
+        Selector::parse(r#"script[type="application/ld+json"]"#).expect("valid selector");
+    let mut entities = vec![];
+    for script in document.select(&selector) {
+        let text: String = script.text().collect();
+        // invalid scripts are common, they are skipped
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            collect_entities(&value, &mut entities);
+        }
+    }
+    entities
+}
+
+fn collect_entities(value: &Value, entities: &mut Vec<Value>) {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_entities(item, entities)),
+        Value::Object(object) => {
+            if object.contains_key("@type") {
+                entities.push(value.clone());
+            }
+            object
+                .values()
+                .for_each(|value| collect_entities(value, entities));
+        }
+        _ => {}
+    }
+}
+
+/// Whether an entity is of a type, also as `schema:Type` or `https://schema.org/Type`
+pub fn has_type(entity: &Value, name: &str) -> bool {
+    let matches = |value: &Value| {
+        value
+            .as_str()
+            .is_some_and(|kind| kind.rsplit(['/', ':']).next() == Some(name))
+    };
+    match entity.get("@type") {
+        Some(Value::Array(kinds)) => kinds.iter().any(matches),
+        Some(kind) => matches(kind),
+        None => false,
+    }
+}
+
+/// First entity of one of the types
+pub fn find_entity<'a>(entities: &'a [Value], names: &[&str]) -> Option<&'a Value> {
+    entities
+        .iter()
+        .find(|entity| names.iter().any(|name| has_type(entity, name)))
+}
+
+/// Text of a property: a string or number, the `name` of an object, the first of a list
+pub fn text(entity: &Value, key: &str) -> Option<String> {
+    value_text(entity.get(key)?)
+}
+
+pub fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(items) => items.iter().find_map(value_text),
+        Value::Object(object) => object.get("name").and_then(value_text),
+        _ => None,
+    }
+}
+
+/// Content of a `meta` tag by `name` or `property`
+pub fn meta_content(document: &Html, key: &str) -> Option<String> {
+    let selector = Selector::parse("meta[content]").expect("valid selector");
+    document
+        .select(&selector)
+        .find(|meta| {
+            let element = meta.value();
+            element.attr("property").or(element.attr("name")) == Some(key)
+        })
+        .and_then(|meta| meta.value().attr("content"))
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// ISO 8601 duration like `PT1H30M` or `P1DT2H`
+pub fn parse_iso_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().strip_prefix('P')?;
+    let (days, time) = text.split_once('T').unwrap_or((text, ""));
+    let mut seconds = 0.0;
+    for (part, units) in [(days, "WD"), (time, "HMS")] {
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                continue;
+            }
+            let value: f64 = number.parse().ok()?;
+            number.clear();
+            seconds += value
+                * match (units, c) {
+                    ("WD", 'W') => 604_800.0,
+                    ("WD", 'D') => 86_400.0,
+                    ("HMS", 'H') => 3_600.0,
+                    ("HMS", 'M') => 60.0,
+                    ("HMS", 'S') => 1.0,
+                    _ => return None,
+                };
+        }
+        if !number.is_empty() {
+            return None;
+        }
+    }
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// W3C datetime (`2025-05-01`, `2025-05-01T17:00+02:00`, `2025-05-01T17:00:00.5Z`),
+/// UTC without offset
+pub fn parse_datetime(text: &str) -> Option<SystemTime> {
+    let text = text.trim();
+    let Some((date, time)) = text.split_once(['T', ' ']) else {
+        return humantime::parse_rfc3339(&format!("{}T00:00:00Z", text)).ok();
+    };
+    let offset_start = time.find(['Z', '+', '-']).unwrap_or(time.len());
+    let (clock, offset) = time.split_at(offset_start);
+    // fractional seconds are dropped
+    let clock = clock.split('.').next().unwrap_or_default();
+    let clock = match clock.len() {
+        5 => format!("{}:00", clock),
+        _ => clock.to_string(),
+    };
+    let local = humantime::parse_rfc3339(&format!("{}T{}Z", date, clock)).ok()?;
+    let offset = match offset {
+        "" => 0,
+        offset => offset_seconds(offset)?,
+    };
+    if offset >= 0 {
+        local.checked_sub(Duration::from_secs(offset as u64))
+    } else {
+        local.checked_add(Duration::from_secs(offset.unsigned_abs()))
+    }
+}
+
+/// ISO 8601 form of a date (`2025-05-01`) or of a datetime in UTC (`2025-05-01T15:00:00Z`)
+pub fn normalize_date(text: &str) -> Option<String> {
+    let time = parse_datetime(text)?;
+    let utc = humantime::format_rfc3339_seconds(time).to_string();
+    match text.trim().len() {
+        10 => utc.get(..10).map(str::to_string),
+        _ => Some(utc),
+    }
+}
+
+/// Seconds east of UTC of `Z`, `+02:00` or `-0500`
+pub fn offset_seconds(offset: &str) -> Option<i64> {
+    if offset == "Z" {
+        return Some(0);
+    }
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = offset[1..].replace(':', "");
+    let hours: i64 = digits.get(..2)?.parse().ok()?;
+    let minutes: i64 = digits.get(2..4).unwrap_or("00").parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
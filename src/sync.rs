@@ -0,0 +1,224 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use reqwest::Client;
+
+use crate::paths::Paths;
+use crate::s3::{Credentials, S3Object};
+use crate::scrap::{with_network, ScrapOptions};
+use crate::stamp::now;
+use crate::store::{self, Archive, ImportReport, OnConflict, Store};
+
+/// Archive file name in the sync repository or bucket, uncompressed so git can diff it
+const ARCHIVE_FILE: &str = "store.json";
+
+/// How the remote archive is combined with the local store
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncStrategy {
+    /// Link by link, the most recently updated one wins
+    #[default]
+    Merge,
+    /// The most recently written side replaces the other's links
+    LastWriteWins,
+}
+
+/// `[sync]` section of `config.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Git remote url, e.g. `git@github.com:me/bookmarks.git`, or S3 bucket and prefix,
+    /// e.g. `s3://bookmarks/laptop`
+    pub remote: String,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    #[serde(default)]
+    pub strategy: SyncStrategy,
+    /// S3 compatible service, AWS when missing
+    pub endpoint: Option<String>,
+    pub region: Option<String>,
+    /// S3 keys, from the `AWS_*` environment variables when missing
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+}
+
+impl SyncConfig {
+    pub fn new(remote: &str) -> Self {
+        SyncConfig {
+            remote: remote.to_string(),
+            branch: default_branch(),
+            strategy: SyncStrategy::default(),
+            endpoint: None,
+            region: None,
+            access_key_id: None,
+            secret_access_key: None,
+        }
+    }
+}
+
+pub fn default_branch() -> String {
+    "main".to_string()
+}
+
+/// Pull the remote archive into the store, then push the store back
+pub async fn sync(
+    paths: &Paths,
+    config: &SyncConfig,
+    options: &ScrapOptions,
+) -> Result<ImportReport> {
+    if config.remote.starts_with("s3://") {
+        sync_s3(paths, config, options).await
+    } else {
+        sync_git(paths, config)
+    }
+}
+
+fn sync_git(paths: &Paths, config: &SyncConfig) -> Result<ImportReport> {
+    let checkout = paths.data.join("sync");
+    pull(&checkout, config)?;
+
+    let store = Store::open(&paths.data)?;
+    let archive_path = checkout.join(ARCHIVE_FILE);
+    let report = match archive_path.exists() {
+        true => import(
+            &store,
+            &store::read_archive(&archive_path)?,
+            config.strategy,
+        )?,
+        false => ImportReport::default(),
+    };
+
+    store::write_archive(&store.export()?, &archive_path)?;
+    git(&checkout, &["add", ARCHIVE_FILE])?;
+    let unchanged = git(&checkout, &["diff", "--cached", "--quiet"]).is_ok();
+    if !unchanged {
+        let message = format!("scrapr sync {}", now());
+        git(&checkout, &["commit", "--quiet", "-m", &message])?;
+        git(
+            &checkout,
+            &[
+                "push",
+                "--quiet",
+                "origin",
+                &format!("HEAD:{}", config.branch),
+            ],
+        )?;
+    }
+    Ok(report)
+}
+
+/// Same as the git sync on the `store.json` object of the bucket, the upload failing
+/// when another machine wrote it meanwhile
+async fn sync_s3(
+    paths: &Paths,
+    config: &SyncConfig,
+    options: &ScrapOptions,
+) -> Result<ImportReport> {
+    let credentials = Credentials::resolve(
+        config.access_key_id.as_deref(),
+        config.secret_access_key.as_deref(),
+    )?;
+    let object = S3Object::new(
+        &config.remote,
+        ARCHIVE_FILE,
+        config.endpoint.as_deref(),
+        config.region.as_deref(),
+        credentials,
+    )?;
+    let client = with_network(Client::builder(), options).build()?;
+
+    let store = Store::open(&paths.data)?;
+    let fetched = object.get(&client).await?;
+    let remote = match &fetched {
+        Some(fetched) => Some(store::parse_archive(&fetched.body)?),
+        None => None,
+    };
+    let report = match &remote {
+        Some(archive) => import(&store, archive, config.strategy)?,
+        None => ImportReport::default(),
+    };
+
+    let archive = store.export()?;
+    // exported_at always differs, only the links tell a change
+    let unchanged = remote.is_some_and(|remote| {
+        serde_json::to_value(&remote.links).ok() == serde_json::to_value(&archive.links).ok()
+    });
+    if !unchanged {
+        let body = serde_json::to_vec_pretty(&archive)?;
+        object.put(&client, body, fetched.as_ref()).await?;
+    }
+    Ok(report)
+}
+
+/// Import the remote archive according to the strategy
+fn import(store: &Store, archive: &Archive, strategy: SyncStrategy) -> Result<ImportReport> {
+    let on_conflict = match strategy {
+        SyncStrategy::Merge => Some(OnConflict::Newest),
+        // rfc3339 timestamps sort as text
+        SyncStrategy::LastWriteWins => store
+            .last_updated()?
+            .is_none_or(|local| archive.exported_at > local)
+            .then_some(OnConflict::Overwrite),
+    };
+    match on_conflict {
+        Some(on_conflict) => store.import(archive, on_conflict),
+        None => Ok(ImportReport::default()),
+    }
+}
+
+/// Clone the remote once, then reset the checkout to the remote branch when it exists
+fn pull(checkout: &Path, config: &SyncConfig) -> Result<()> {
+    if !checkout.join(".git").exists() {
+        std::fs::create_dir_all(checkout)?;
+        git(checkout, &["init", "--quiet"])?;
+        git(checkout, &["remote", "add", "origin", &config.remote])?;
+    }
+    git(checkout, &["remote", "set-url", "origin", &config.remote])?;
+    git(checkout, &["fetch", "--quiet", "origin"])?;
+    let remote_branch = format!("origin/{}", config.branch);
+    if git(
+        checkout,
+        &["rev-parse", "--verify", "--quiet", &remote_branch],
+    )
+    .is_ok()
+    {
+        // the checkout only holds exports, local changes live in the store
+        git(
+            checkout,
+            &["checkout", "--quiet", "-B", &config.branch, &remote_branch],
+        )?;
+        git(checkout, &["reset", "--quiet", "--hard", &remote_branch])?;
+    } else {
+        git(checkout, &["checkout", "--quiet", "-B", &config.branch])?;
+    }
+    Ok(())
+}
+
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    git_with_env(dir, args, &[])
+}
+
+/// Same as `git`, with extra environment variables like `GIT_COMMITTER_DATE`
+pub(crate) fn git_with_env(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .envs(env.iter().copied())
+        .output()
+        .map_err(|e| anyhow!("Cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
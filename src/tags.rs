@@ -0,0 +1,51 @@
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+/// Tags of a page: its `article:tag` values, then `<meta name="keywords">` split on
+/// commas, each once whatever its case
+pub fn extract_tags(document: &Html) -> Vec<String> {
+    let selector = Selector::parse("meta[content]").expect("valid selector");
+    let mut article_tags = vec![];
+    let mut keywords = vec![];
+    for meta in document.select(&selector) {
+        let element = meta.value();
+        let content = element.attr("content").unwrap_or_default();
+        match element.attr("property").or(element.attr("name")) {
+            Some("article:tag") => article_tags.push(content.to_string()),
+            Some(name) if name.eq_ignore_ascii_case("keywords") => {
+                keywords.extend(content.split(',').map(str::to_string))
+            }
+            _ => {}
+        }
+    }
+    unique_tags(article_tags.into_iter().chain(keywords))
+}
+
+/// Names of the `Hashtag`s of an activitypub object, without their `#`
+pub fn hashtags(object: &Value) -> Vec<String> {
+    let names = match object.get("tag") {
+        Some(Value::Array(tags)) => tags.iter().collect(),
+        Some(tag) => vec![tag],
+        None => vec![],
+    }
+    .into_iter()
+    .filter(|tag| tag.get("type").and_then(Value::as_str) == Some("Hashtag"))
+    .filter_map(|tag| tag.get("name").and_then(Value::as_str))
+    .map(|name| name.trim_start_matches('#').to_string());
+    unique_tags(names)
+}
+
+fn unique_tags(tags: impl Iterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty()
+            && !unique
+                .iter()
+                .any(|known| known.to_lowercase() == tag.to_lowercase())
+        {
+            unique.push(tag.to_string());
+        }
+    }
+    unique
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use tera::{Context, Tera};
+
+use crate::scrap::ScrapedWebpage;
+
+/// A `--template` file, rendered with the fields of each page
+pub struct PageTemplate {
+    tera: Tera,
+    name: String,
+}
+
+impl PageTemplate {
+    /// A Tera template, autoescaped when its name ends with `.html`, `.htm` or `.xml`
+    pub fn read(path: &Path) -> Result<PageTemplate> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read template {}: {}", path.display(), e))?;
+        let name = path.file_name().map_or("template".to_string(), |name| {
+            name.to_string_lossy().into_owned()
+        });
+        PageTemplate::parse(&name, &content)
+            .map_err(|e| anyhow!("Invalid template {}: {:#}", path.display(), e))
+    }
+
+    pub fn parse(name: &str, content: &str) -> Result<PageTemplate> {
+        let mut tera = Tera::default();
+        tera.add_raw_template(name, content)?;
+        Ok(PageTemplate {
+            tera,
+            name: name.to_string(),
+        })
+    }
+
+    /// The template with every field of the page record, and its `links` and `html`
+    pub fn render(&self, page: &ScrapedWebpage) -> Result<String> {
+        let mut context = Context::from_serialize(page)?;
+        context.insert("links", &page.links);
+        context.insert("html", &page.html);
+        self.tera.render(&self.name, &context).map_err(|e| {
+            let e = anyhow::Error::from(e);
+            anyhow!("Cannot render {} for {}: {:#}", self.name, page.url, e)
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+/// `--timeout` value: a duration, or `auto` from the latency history of each host
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeoutSetting {
+    Fixed(Duration),
+    Auto,
+}
+
+pub fn parse_timeout(value: &str) -> Result<TimeoutSetting> {
+    if value == "auto" {
+        return Ok(TimeoutSetting::Auto);
+    }
+    humantime::parse_duration(value)
+        .map(TimeoutSetting::Fixed)
+        .map_err(|e| {
+            anyhow!(
+                "Invalid timeout {}: {}, expected a duration or auto",
+                value,
+                e
+            )
+        })
+}
+
+/// Timeouts derived from the past p95 latency of each host, within bounds
+#[derive(Debug, Clone)]
+pub struct AdaptiveTimeout {
+    pub p95: HashMap<String, Duration>,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl AdaptiveTimeout {
+    /// Twice the p95 latency of the url host, the max bound for unknown hosts
+    pub fn timeout_for(&self, url: &str) -> Duration {
+        let p95 = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().and_then(|host| self.p95.get(host)).copied());
+        match p95 {
+            Some(p95) => (p95 * 2).clamp(self.min, self.max),
+            None => self.max,
+        }
+    }
+}
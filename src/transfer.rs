@@ -0,0 +1,100 @@
+use std::io::Read;
+
+use anyhow::{anyhow, Result};
+use encoding_rs::{Encoding, UTF_8};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use reqwest::header::{HeaderMap, CONNECTION, CONTENT_ENCODING};
+use reqwest::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::units::format_bytes;
+
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+/// Transfer statistics of the page response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpInfo {
+    pub version: String,
+    pub content_encoding: Option<String>,
+    /// Body bytes received, before decompression
+    pub wire_bytes: usize,
+    /// Body bytes after decompression
+    pub body_bytes: usize,
+    pub keep_alive: bool,
+}
+
+impl HttpInfo {
+    pub fn new(
+        version: Version,
+        headers: &HeaderMap,
+        wire_bytes: usize,
+        body_bytes: usize,
+    ) -> Self {
+        Self {
+            version: format!("{:?}", version),
+            content_encoding: content_encoding(headers),
+            wire_bytes,
+            body_bytes,
+            keep_alive: is_keep_alive(version, headers),
+        }
+    }
+}
+
+fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_lowercase())
+}
+
+/// Http/2+ connections are multiplexed, http/1.1 persists unless closed, http/1.0 only on demand
+fn is_keep_alive(version: Version, headers: &HeaderMap) -> bool {
+    let connection = headers
+        .get(CONNECTION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_lowercase());
+    match version {
+        Version::HTTP_09 => false,
+        Version::HTTP_10 => connection.is_some_and(|value| value.contains("keep-alive")),
+        Version::HTTP_11 => !connection.is_some_and(|value| value.contains("close")),
+        _ => true,
+    }
+}
+
+/// Largest decompressed body, a bound against decompression bombs
+pub const MAX_BODY_BYTES: u64 = 64_000_000;
+
+/// Decompress the body according to Content-Encoding, up to `MAX_BODY_BYTES`
+pub fn decompress(bytes: Vec<u8>, headers: &HeaderMap) -> Result<Vec<u8>> {
+    let decoder: Box<dyn Read + '_> = match content_encoding(headers).as_deref() {
+        None | Some("identity") => return Ok(bytes),
+        Some("gzip") | Some("x-gzip") => Box::new(GzDecoder::new(&bytes[..])),
+        Some("deflate") => Box::new(ZlibDecoder::new(&bytes[..])),
+        Some(other) => return Err(anyhow!("Unsupported content encoding {}", other)),
+    };
+    let mut decoded = vec![];
+    decoder.take(MAX_BODY_BYTES + 1).read_to_end(&mut decoded)?;
+    if decoded.len() as u64 > MAX_BODY_BYTES {
+        return Err(anyhow!(
+            "Decompressed body larger than {}",
+            format_bytes(MAX_BODY_BYTES)
+        ));
+    }
+    Ok(decoded)
+}
+
+/// Decode the body text with the charset of the content type, utf-8 by default
+pub fn decode_text(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(|content_type| {
+            content_type
+                .split(';')
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+                .map(|(_, charset)| charset.trim().trim_matches('"').to_string())
+        })
+        .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
@@ -0,0 +1,39 @@
+use scraper::Html;
+use serde::Serialize;
+use url::Url;
+
+use crate::structured;
+
+/// Twitter card tags of a page, flattened into its record as `twitter_*` fields
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TwitterCard {
+    /// `summary`, `summary_large_image`, `player` or `app`
+    #[serde(rename = "twitter_card", skip_serializing_if = "Option::is_none")]
+    pub card: Option<String>,
+    #[serde(rename = "twitter_title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(
+        rename = "twitter_description",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    /// Resolved against the page url
+    #[serde(rename = "twitter_image", skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+pub fn extract_twitter_card(document: &Html, page_url: &str) -> TwitterCard {
+    let meta = |key| structured::meta_content(document, key);
+    TwitterCard {
+        card: meta("twitter:card"),
+        title: meta("twitter:title"),
+        description: meta("twitter:description"),
+        image: meta("twitter:image")
+            .or(meta("twitter:image:src"))
+            .map(|image| {
+                Url::parse(page_url)
+                    .and_then(|base| base.join(&image))
+                    .map_or(image, String::from)
+            }),
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::{anyhow, Result};
+
+/// Parse a byte size like `100MB`, `500KB`, `1MiB` or `4096`
+pub fn parse_bytes(size: &str) -> Result<u64> {
+    let invalid = || anyhow!("Invalid size {}: expected e.g. 100MB", size);
+    let unit_start = size.find(|c: char| c.is_alphabetic()).unwrap_or(size.len());
+    let (value, unit) = size.split_at(unit_start);
+    let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+    let multiplier = match unit.trim() {
+        "" | "B" => 1.0,
+        "KB" | "kB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(invalid()),
+    };
+    Ok((value * multiplier) as u64)
+}
+
+/// Parse a ratio between 0 and 1, like `0.9`
+pub fn parse_ratio(ratio: &str) -> Result<f64> {
+    ratio
+        .parse::<f64>()
+        .ok()
+        .filter(|ratio| (0.0..=1.0).contains(ratio))
+        .ok_or(anyhow!(
+            "Invalid ratio {}: expected a number between 0 and 1",
+            ratio
+        ))
+}
+
+/// Human readable size in decimal units, like `1.5 MB`
+pub fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        bytes if bytes >= 1_000_000 => format!("{:.1} MB", bytes as f64 / 1e6),
+        bytes if bytes >= 1_000 => format!("{:.1} kB", bytes as f64 / 1e3),
+        bytes => format!("{} B", bytes),
+    }
+}
@@ -0,0 +1,59 @@
+use scraper::Html;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::structured::{self, find_entity};
+
+/// Video of a page, from schema.org VideoObject data or open graph
+#[derive(Debug, Clone, Serialize)]
+pub struct Video {
+    pub name: Option<String>,
+    pub duration_secs: Option<u64>,
+    pub upload_date: Option<String>,
+    pub embed_url: Option<String>,
+    pub content_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thumbnails: Vec<String>,
+}
+
+pub fn extract_video(document: &Html, entities: &[Value]) -> Option<Video> {
+    let meta = |key| structured::meta_content(document, key);
+    let og_url = meta("og:video:secure_url")
+        .or(meta("og:video:url"))
+        .or(meta("og:video"));
+    let Some(video) = find_entity(entities, &["VideoObject"]) else {
+        let og_url = og_url?;
+        return Some(Video {
+            name: meta("og:title"),
+            duration_secs: meta("video:duration").and_then(|seconds| seconds.parse().ok()),
+            upload_date: meta("video:release_date"),
+            embed_url: Some(og_url),
+            content_url: None,
+            thumbnails: meta("og:image").into_iter().collect(),
+        });
+    };
+    Some(Video {
+        name: structured::text(video, "name"),
+        duration_secs: structured::text(video, "duration")
+            .and_then(|duration| structured::parse_iso_duration(&duration))
+            .map(|duration| duration.as_secs()),
+        upload_date: structured::text(video, "uploadDate"),
+        embed_url: structured::text(video, "embedUrl").or(og_url),
+        content_url: structured::text(video, "contentUrl"),
+        thumbnails: thumbnails(video),
+    })
+}
+
+/// `thumbnailUrl` strings, or `thumbnail` ImageObjects
+fn thumbnails(video: &Value) -> Vec<String> {
+    let url = |value: &Value| match value {
+        Value::String(url) => Some(url.clone()),
+        image => structured::text(image, "url").or(structured::text(image, "contentUrl")),
+    };
+    let values = match video.get("thumbnailUrl").or(video.get("thumbnail")) {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(value) => vec![value],
+        None => vec![],
+    };
+    values.into_iter().filter_map(url).collect()
+}
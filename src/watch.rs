@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{self, HeaderMap};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::filter::Filter;
+use crate::format::Format;
+use crate::notify::{notify_all, Notification, Notifier};
+use crate::scrap::{self, ScrapOptions, Scraped, ScrapedWebpage};
+use crate::stamp::{content_hash, now};
+
+/// What a check remembers of a page to detect changes
+#[derive(Debug, Clone, PartialEq)]
+struct PageState {
+    status: u16,
+    title: String,
+    description: Option<String>,
+    price: Option<f64>,
+    currency: Option<String>,
+    content_hash: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PageState {
+    fn new(page: &ScrapedWebpage, headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            status: page.status,
+            title: page.title.clone(),
+            description: page.description.clone(),
+            price: page.price,
+            currency: page.currency.clone(),
+            content_hash: content_hash(&page.html),
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+        }
+    }
+
+    /// Validators of the last response, for a `304 Not Modified` answer
+    fn conditional_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![];
+        if let Some(etag) = &self.etag {
+            headers.push((header::IF_NONE_MATCH.to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push((header::IF_MODIFIED_SINCE.to_string(), last_modified.clone()));
+        }
+        headers
+    }
+
+    /// Fields which differ, with their old and new values (hashes for the content)
+    fn changes(&self, current: &PageState) -> Map<String, Value> {
+        let fields = [
+            ("status", json!(self.status), json!(current.status)),
+            ("title", json!(self.title), json!(current.title)),
+            (
+                "description",
+                json!(self.description),
+                json!(current.description),
+            ),
+            ("price", json!(self.price), json!(current.price)),
+            ("currency", json!(self.currency), json!(current.currency)),
+            (
+                "content",
+                json!(self.content_hash),
+                json!(current.content_hash),
+            ),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(field, old, new)| (field.to_string(), json!({ "old": old, "new": new })))
+            .collect()
+    }
+}
+
+/// Fields compared between checks, `<field>_changed` in `--emit-when` expressions
+pub const CHANGE_FIELDS: [&str; 6] = [
+    "status",
+    "title",
+    "description",
+    "price",
+    "currency",
+    "content",
+];
+
+/// A record with `<field>_changed` flags for the `CHANGE_FIELDS`, and `changed`
+/// when any of them did, as evaluated by `--emit-when`
+pub fn with_changes(mut record: Value, changed: &[&str]) -> Value {
+    if let Value::Object(fields) = &mut record {
+        for field in CHANGE_FIELDS {
+            fields.insert(
+                format!("{}_changed", field),
+                Value::Bool(changed.contains(&field)),
+            );
+        }
+        fields.insert("changed".to_string(), Value::Bool(!changed.is_empty()));
+    }
+    record
+}
+
+/// One check, printed as a JSON line with `--format json`
+#[derive(Debug, Serialize)]
+struct CheckReport<'a> {
+    checked_at: String,
+    url: &'a str,
+    /// `unchanged`, `changed`, `matched` (by `--emit-when`, without change) or `error`
+    result: &'static str,
+    /// Changed fields as `{"title": {"old": …, "new": …}}`
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    changes: Map<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl CheckReport<'_> {
+    fn print(&self, format: Format) -> Result<()> {
+        match (format, self.result) {
+            (Format::Json | Format::Jsonl, _) => println!("{}", serde_json::to_string(self)?),
+            (_, "error") => eprintln!(
+                "{} {} error: {}",
+                self.checked_at,
+                self.url,
+                self.error.as_deref().unwrap_or_default()
+            ),
+            (_, "changed") => println!(
+                "{} {} changed: {}",
+                self.checked_at,
+                self.url,
+                self.changes.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+            _ => println!("{} {} {}", self.checked_at, self.url, self.result),
+        }
+        Ok(())
+    }
+}
+
+/// `field: old → new` lines, strings unquoted
+fn describe_changes(changes: &Map<String, Value>) -> String {
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+    changes
+        .iter()
+        .map(|(field, change)| {
+            format!(
+                "{}: {} → {}",
+                field,
+                text(&change["old"]),
+                text(&change["new"])
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// How a check compares to the previous one
+enum Check {
+    Unchanged,
+    Fetched(Box<ScrapedWebpage>, PageState),
+}
+
+/// Conditional request, then extraction only when the content hash differs
+async fn check(url: &str, options: &ScrapOptions, previous: Option<&PageState>) -> Result<Check> {
+    let mut options = options.clone();
+    if let Some(previous) = previous {
+        options.headers.extend(previous.conditional_headers());
+    }
+    let response = scrap::retrieve_html_page(url, &options).await?;
+    if response.status == 304 {
+        return Ok(Check::Unchanged);
+    }
+    if previous.is_some_and(|previous| previous.content_hash == content_hash(&response.content)) {
+        return Ok(Check::Unchanged);
+    }
+    let headers = response.headers.clone();
+    match scrap::extract_response(url, response, &options).await? {
+        Scraped::Webpage(page) => {
+            let state = PageState::new(&page, &headers);
+            Ok(Check::Fetched(page, state))
+        }
+        _ => Err(anyhow!("watch requires an html page")),
+    }
+}
+
+/// How `scrapr watch` checks and reports
+#[derive(Debug, Clone)]
+pub struct WatchSettings {
+    /// Interval between checks
+    pub every: Duration,
+    /// Stop after this many checks
+    pub max_checks: Option<usize>,
+    pub format: Format,
+    /// Only report changes lowering the price
+    pub price_drops: bool,
+    /// Only report and notify the checks matching this expression, changed or not
+    pub emit_when: Option<Filter>,
+}
+
+/// Check a page at an interval, printing each check as `unchanged`, `changed` or `error`
+/// and notifying changes, only when the page matches `filter`; the first check is the
+/// baseline. With `emit_when`, only the matching checks are printed and notified
+pub async fn watch(
+    url: &str,
+    settings: &WatchSettings,
+    options: &ScrapOptions,
+    notifiers: &[Notifier],
+    filter: Option<&Filter>,
+) -> Result<()> {
+    scrap::check_homographs(url, options)?;
+    let mut previous: Option<PageState> = None;
+    // record of the last fetch, evaluated again by `emit_when` for unchanged checks
+    let mut last_record: Option<(Value, String)> = None;
+    let format = settings.format;
+    let mut checks = 0;
+    loop {
+        let mut report = CheckReport {
+            checked_at: now(),
+            url,
+            result: "unchanged",
+            changes: Map::new(),
+            error: None,
+        };
+        match check(url, options, previous.as_ref()).await {
+            Ok(Check::Unchanged) => match (&settings.emit_when, &last_record) {
+                (Some(emit_when), Some((record, title))) => {
+                    if emit_when.matches(&with_changes(record.clone(), &[])) {
+                        report.result = "matched";
+                        report.print(format)?;
+                        let notification = Notification {
+                            title: format!("{} matched", title),
+                            body: String::new(),
+                            url: url.to_string(),
+                        };
+                        notify_all(notifiers, &notification, options).await;
+                    }
+                }
+                (Some(_), None) => {}
+                (None, _) => report.print(format)?,
+            },
+            Ok(Check::Fetched(page, current)) => {
+                if let Some(previous) = &previous {
+                    report.changes = previous.changes(&current);
+                }
+                let record = serde_json::to_value(&page)?;
+                // prices in another currency do not compare
+                let price_drop = previous
+                    .as_ref()
+                    .filter(|previous| previous.currency == current.currency)
+                    .and_then(|previous| previous.price)
+                    .zip(current.price)
+                    .is_some_and(|(old, new)| new < old);
+                let matches = match filter {
+                    Some(filter) => filter.matches(&record),
+                    None => true,
+                };
+                let emit = match &settings.emit_when {
+                    Some(emit_when) => {
+                        let changed: Vec<&str> =
+                            report.changes.keys().map(String::as_str).collect();
+                        emit_when.matches(&with_changes(record.clone(), &changed))
+                    }
+                    // the first check is the baseline
+                    None => previous.is_some() && !report.changes.is_empty(),
+                };
+                if emit && matches && (price_drop || !settings.price_drops) {
+                    report.result = if report.changes.is_empty() {
+                        "matched"
+                    } else {
+                        "changed"
+                    };
+                    report.print(format)?;
+                    let title = match (price_drop, current.price) {
+                        (true, Some(price)) => format!(
+                            "{} price dropped to {} {}",
+                            page.title,
+                            price,
+                            current.currency.as_deref().unwrap_or_default()
+                        ),
+                        _ => format!("{} {}", page.title, report.result),
+                    };
+                    let notification = Notification {
+                        title: title.trim_end().to_string(),
+                        body: describe_changes(&report.changes),
+                        url: page.url.clone(),
+                    };
+                    notify_all(notifiers, &notification, options).await;
+                } else if previous.is_some()
+                    && report.changes.is_empty()
+                    && settings.emit_when.is_none()
+                {
+                    report.print(format)?;
+                }
+                last_record = Some((record, page.title.clone()));
+                previous = Some(current);
+            }
+            // a failed check keeps the last known state
+            Err(e) => {
+                report.result = "error";
+                report.error = Some(e.to_string());
+                report.print(format)?;
+            }
+        }
+        checks += 1;
+        if settings
+            .max_checks
+            .is_some_and(|max_checks| checks >= max_checks)
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(settings.every).await;
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::{anyhow, Result};
+use sxd_document::parser;
+use sxd_xpath::{evaluate_xpath, Value};
+
+/// Xml media types which are not feeds (rss/atom keep the html pipeline)
+pub fn is_generic_xml(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        let is_xml = mime == "application/xml" || mime == "text/xml" || mime.ends_with("+xml");
+        let is_excluded = [
+            "application/xhtml+xml",
+            "application/rss+xml",
+            "application/atom+xml",
+        ]
+        .contains(&mime);
+        is_xml && !is_excluded
+    })
+}
+
+/// Evaluate an xpath expression over a xml document, one string per matching node
+pub fn select_xpath(content: &str, xpath: &str) -> Result<Vec<String>> {
+    let package = parser::parse(content).map_err(|e| anyhow!("Invalid xml document: {}", e))?;
+    let document = package.as_document();
+    let value =
+        evaluate_xpath(&document, xpath).map_err(|e| anyhow!("Invalid xpath {}: {}", xpath, e))?;
+    let values = match value {
+        Value::Nodeset(nodes) => nodes
+            .document_order()
+            .iter()
+            .map(|node| node.string_value())
+            .collect(),
+        other => vec![other.string()],
+    };
+    Ok(values)
+}
@@ -0,0 +1,23 @@
+use scrapr::activitypub::discover_object_url;
+
+const PAGE: &str = "https://social.example/@x/1";
+
+fn alternate(href: &str) -> String {
+    format!(
+        r#"<html><head><link rel="alternate" type="application/activity+json" href="{}"></head></html>"#,
+        href
+    )
+}
+
+#[test]
+fn relative_object_urls_are_resolved() {
+    assert_eq!(
+        discover_object_url(&alternate("/users/x/statuses/1"), PAGE).as_deref(),
+        Some("https://social.example/users/x/statuses/1")
+    );
+    assert_eq!(
+        discover_object_url(&alternate("https://other.example/objects/1"), PAGE).as_deref(),
+        Some("https://other.example/objects/1")
+    );
+    assert_eq!(discover_object_url("<html></html>", PAGE), None);
+}
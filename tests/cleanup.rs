@@ -0,0 +1,216 @@
+use proptest::prelude::*;
+use scrapr::cleanup::{
+    clean_url, cleanup_tracking_params, is_shortener, is_tracking_param, normalize_url,
+};
+use url::Url;
+
+/// Query parameter as written in an url, with its name
+fn param() -> impl Strategy<Value = (String, String)> {
+    let name = prop_oneof![
+        "utm_[a-z]{1,8}",
+        Just("fbclid".to_string()),
+        Just("gclid".to_string()),
+        "[a-z_]{1,8}",
+    ];
+    // encoded bytes, `+` spaces and multibyte characters
+    let value = "([A-Za-z0-9._~-]|%[0-9A-F]{2}|\\+|é|日){0,8}";
+    (name, value).prop_map(|(name, value)| (name.clone(), format!("{}={}", name, value)))
+}
+
+fn url() -> impl Strategy<Value = (String, Vec<(String, String)>)> {
+    (
+        prop_oneof!["http", "https"],
+        "[a-z]{1,10}\\.(com|org|co\\.uk)",
+        "(/[A-Za-z0-9._~%-]{0,8}){0,3}",
+        prop::collection::vec(param(), 0..6),
+        prop::option::of("[a-z0-9=&]{0,8}"),
+    )
+        .prop_map(|(scheme, host, path, params, fragment)| {
+            let mut url = format!("{}://{}{}", scheme, host, path);
+            if !params.is_empty() {
+                let query: Vec<&str> = params.iter().map(|(_, param)| param.as_str()).collect();
+                url = format!("{}?{}", url, query.join("&"));
+            }
+            if let Some(fragment) = fragment {
+                url = format!("{}#{}", url, fragment);
+            }
+            (url, params)
+        })
+}
+
+fn query_params(url: &str) -> Vec<String> {
+    let url = url.split_once('#').map_or(url, |(url, _)| url);
+    url.split_once('?')
+        .map(|(_, query)| query.split('&').map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn cleanup_keeps_tricky_encodings() {
+    let cases = [
+        // encoded `&` and `=` stay inside their value
+        (
+            "https://example.com/?q=a%26b%3Dc&utm_source=x&next=%2Fhome%3Fa%3D1",
+            "https://example.com/?q=a%26b%3Dc&next=%2Fhome%3Fa%3D1",
+        ),
+        // a raw `=` in a value is part of it
+        (
+            "https://example.com/?token=ab==&gclid=1",
+            "https://example.com/?token=ab==",
+        ),
+        // spaces, whether `+` or `%20`
+        (
+            "https://example.com/s?utm_medium=email&q=rust+lang&tag=web%20dev",
+            "https://example.com/s?q=rust+lang&tag=web%20dev",
+        ),
+        // utf-8, encoded or not
+        (
+            "https://example.com/?ville=Montr%C3%A9al&fbclid=1&nom=Zoë",
+            "https://example.com/?ville=Montr%C3%A9al&nom=Zoë",
+        ),
+        // order, repeated and valueless parameters
+        (
+            "https://example.com/?b=2&utm_campaign=x&a=1&b=3&flag",
+            "https://example.com/?b=2&a=1&b=3&flag",
+        ),
+        // encoded tracking names, fragment
+        (
+            "https://example.com/p?utm%5Fsource=x&id=1#utm_source=y",
+            "https://example.com/p?id=1#utm_source=y",
+        ),
+        // nothing left
+        (
+            "https://example.com/p?utm_source=x&UTM_MEDIUM=y#top",
+            "https://example.com/p#top",
+        ),
+        (
+            "https://example.com/p?utm=1&ref=home",
+            "https://example.com/p?utm=1&ref=home",
+        ),
+    ];
+    for (url, expected) in cases {
+        assert_eq!(cleanup_tracking_params(url), expected, "{}", url);
+    }
+}
+
+#[test]
+fn cleanup_applies_host_rules() {
+    let cases = [
+        (
+            "https://www.amazon.co.uk/dp/B0C?ref=sr_1_1&pd_rd_w=abc&th=1",
+            "https://www.amazon.co.uk/dp/B0C?th=1",
+        ),
+        (
+            "https://github.com/rust-lang/rust/tree/master?ref=main",
+            "https://github.com/rust-lang/rust/tree/master?ref=main",
+        ),
+        (
+            "https://open.spotify.com/track/4uLU6h?si=a1b2",
+            "https://open.spotify.com/track/4uLU6h",
+        ),
+        (
+            "https://youtu.be/dQw4w9WgXcQ?si=xyz&t=42",
+            "https://youtu.be/dQw4w9WgXcQ?t=42",
+        ),
+        (
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=xyz",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        ),
+        (
+            "https://example.com/course?si=12&ref=home",
+            "https://example.com/course?si=12&ref=home",
+        ),
+        // only the registrable domain counts
+        (
+            "https://amazon.example.com/?ref=1",
+            "https://amazon.example.com/?ref=1",
+        ),
+    ];
+    for (url, expected) in cases {
+        assert_eq!(cleanup_tracking_params(url), expected, "{}", url);
+    }
+}
+
+#[test]
+fn clean_url_unwraps_redirectors() {
+    let cases = [
+        (
+            "https://www.google.com/url?q=https://example.com/post%3Fid%3D7%26utm_source%3Dx&sa=D",
+            "https://example.com/post?id=7",
+        ),
+        (
+            "https://l.facebook.com/l.php?u=https%3A%2F%2Fwww.youtube.com%2Fredirect%3Fq%3Dhttps%253A%252F%252Fexample.com%252F%26v%3D1&h=AT0",
+            "https://example.com/",
+        ),
+        (
+            "https://out.reddit.com/t3_abc?url=https%3A%2F%2Fexample.org%2F&token=x",
+            "https://example.org/",
+        ),
+        // not a destination
+        (
+            "https://www.google.com/url?q=javascript:alert(1)",
+            "https://www.google.com/url?q=javascript:alert(1)",
+        ),
+        (
+            "https://www.google.com/search?q=https://example.com",
+            "https://www.google.com/search?q=https://example.com",
+        ),
+    ];
+    for (url, expected) in cases {
+        assert_eq!(clean_url(url), expected, "{}", url);
+    }
+}
+
+#[test]
+fn normalize_url_canonicalizes() {
+    let cases = [
+        (
+            "HTTPS://Example.COM:443/a/../b?id=1",
+            "https://example.com/b?id=1",
+        ),
+        ("  http://example.com  ", "http://example.com/"),
+        ("https://example.com/café", "https://example.com/caf%C3%A9"),
+    ];
+    for (url, expected) in cases {
+        assert_eq!(normalize_url(url).unwrap(), expected, "{}", url);
+    }
+    assert!(normalize_url("example.com/page").is_err());
+}
+
+#[test]
+fn shorteners_are_known_hosts() {
+    assert!(is_shortener("https://bit.ly/3abc"));
+    assert!(is_shortener("https://T.CO/xyz"));
+    assert!(is_shortener("https://www.tinyurl.com/abc"));
+    assert!(!is_shortener("https://notbit.ly/abc"));
+    assert!(!is_shortener("https://example.com/bit.ly"));
+}
+
+proptest! {
+    #[test]
+    fn cleanup_is_idempotent((url, _) in url()) {
+        let cleaned = cleanup_tracking_params(&url);
+        prop_assert_eq!(cleanup_tracking_params(&cleaned), cleaned);
+    }
+
+    #[test]
+    fn cleanup_keeps_scheme_host_path_and_fragment((url, _) in url()) {
+        let cleaned = cleanup_tracking_params(&url);
+        let (before, after) = (Url::parse(&url).unwrap(), Url::parse(&cleaned).unwrap());
+        prop_assert_eq!(before.scheme(), after.scheme());
+        prop_assert_eq!(before.host_str(), after.host_str());
+        prop_assert_eq!(before.path(), after.path());
+        prop_assert_eq!(before.fragment(), after.fragment());
+    }
+
+    #[test]
+    fn cleanup_keeps_other_params_as_written((url, params) in url()) {
+        let host = Url::parse(&url).unwrap().host_str().unwrap().to_string();
+        let kept: Vec<String> = params
+            .into_iter()
+            .filter(|(name, _)| !is_tracking_param(&host, name))
+            .map(|(_, param)| param)
+            .collect();
+        prop_assert_eq!(query_params(&cleanup_tracking_params(&url)), kept);
+    }
+}
@@ -0,0 +1,100 @@
+use scrapr::filter::Filter;
+use serde_json::{json, Value};
+
+fn matches(expression: &str, value: &Value) -> bool {
+    Filter::parse(expression)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .matches(value)
+}
+
+fn error(expression: &str) -> String {
+    match Filter::parse(expression) {
+        Ok(_) => panic!("{} parsed", expression),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    let value = json!({ "a": true, "b": false, "c": false });
+    assert!(matches("a || b && c", &value));
+    assert!(matches("b && c || a", &value));
+    assert!(!matches("(a || b) && c", &value));
+    assert!(matches("a or b and c", &value));
+}
+
+#[test]
+fn not_binds_tighter_than_and() {
+    let value = json!({ "a": true, "b": true });
+    assert!(!matches("!a && b", &value));
+    assert!(!matches("not a and b", &value));
+    assert!(matches("!(a && !b)", &value));
+    assert!(matches("!!a", &value));
+}
+
+#[test]
+fn comparisons_bind_tighter_than_logic() {
+    let value = json!({ "status": 404, "language": "en", "http": { "version": 2 } });
+    assert!(matches(
+        "language == \"fr\" || status >= 400 && status < 500",
+        &value
+    ));
+    assert!(matches("!status == 200", &json!({ "status": null })));
+    assert!(matches("http.version == 2", &value));
+    assert!(matches("missing.field == null", &value));
+    assert!(!matches("status > \"400\"", &value));
+}
+
+#[test]
+fn quoted_strings() {
+    let value = json!({ "title": "Say \"hi\" to Rust's book" });
+    assert!(matches(r#"title contains "\"hi\"""#, &value));
+    assert!(matches(r"title contains 'Rust\'s'", &value));
+    assert!(matches(
+        "title contains \"&& || ( )\" || title contains 'book'",
+        &value
+    ));
+    assert!(!matches("title == 'say'", &value));
+    assert!(matches("title != ''", &value));
+}
+
+#[test]
+fn contains_on_arrays() {
+    let value = json!({ "tags": ["rust", "web"], "count": [1, 2] });
+    assert!(matches("tags contains 'web'", &value));
+    assert!(!matches("tags contains 'go'", &value));
+    assert!(matches("count contains 2", &value));
+}
+
+#[test]
+fn bare_fields_are_truthy() {
+    let value = json!({ "empty": "", "zero": 0, "no": false, "text": "x" });
+    assert!(!matches("empty", &value));
+    assert!(matches("zero", &value));
+    assert!(!matches("no", &value));
+    assert!(matches("text", &value));
+    assert!(!matches("missing", &value));
+}
+
+#[test]
+fn invalid_expressions() {
+    assert!(error("title == \"open").contains("unterminated string"));
+    assert!(error("(a || b").contains("missing )"));
+    assert!(error("a b").contains("unexpected Field"));
+    assert!(error("a # b").contains("unexpected character #"));
+    assert!(error("").contains("unexpected end"));
+    assert!(error("a &&").contains("unexpected end"));
+    assert!(error("a & b").contains("unexpected character &"));
+    assert!(error("== 1").contains("unexpected Operator"));
+    assert!(error("1.2.3 == 1").contains("invalid number 1.2.3"));
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let deep = format!("{}a", "!".repeat(100));
+    assert!(error(&deep).contains("nested more than"));
+    let deep = format!("{}a{}", "(".repeat(100), ")".repeat(100));
+    assert!(error(&deep).contains("nested more than"));
+    let shallow = format!("{}a{}", "(".repeat(10), ")".repeat(10));
+    assert!(matches(&shallow, &json!({ "a": 1 })));
+}
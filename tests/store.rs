@@ -0,0 +1,34 @@
+#![cfg(feature = "store")]
+
+use scrapr::scrap::{extract_webpage, HtmlPage, ScrapOptions};
+use scrapr::store::Store;
+
+const URL: &str = "https://example.com/post";
+
+#[test]
+fn saved_tags_are_exported() {
+    let data_dir = std::env::temp_dir().join(format!("scrapr-store-{}", std::process::id()));
+    let html = r#"<html><head><title>Post</title>
+        <meta property="article:tag" content="rust">
+        <meta name="keywords" content="web, scraping">
+        </head><body><p>Text</p></body></html>"#;
+    let page = extract_webpage(
+        URL,
+        HtmlPage::ok(URL, html.to_string()),
+        &ScrapOptions::default(),
+    )
+    .unwrap();
+    assert!(!page.tags.is_empty());
+
+    let store = Store::open(&data_dir).unwrap();
+    store.save(&page).unwrap();
+    store.save(&page).unwrap();
+    let archive = store.export().unwrap();
+    std::fs::remove_dir_all(&data_dir).unwrap();
+
+    let mut tags = page.tags.clone();
+    tags.sort();
+    tags.dedup();
+    assert_eq!(archive.links.len(), 1);
+    assert_eq!(archive.links[0].tags, tags);
+}